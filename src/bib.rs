@@ -0,0 +1,4 @@
+//! bibliographies for citation processing
+
+#[cfg(feature = "fetch")]
+pub mod fetch;
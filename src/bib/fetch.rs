@@ -0,0 +1,171 @@
+//! downloading bibliography entries and citation styles
+//!
+//! Entries are fetched as CSL JSON, the format pandoc's citation processing reads natively,
+//! and can be passed to a conversion with
+//! [`Pandoc::add_csl_bibliography`](../../struct.Pandoc.html#method.add_csl_bibliography):
+//!
+//! ```no_run
+//! use pandoc::bib::fetch;
+//!
+//! let mut entries = vec![fetch::doi("10.1145/3290366").unwrap()];
+//! entries.extend(fetch::zotero_collection("thesis").unwrap());
+//! let mut pandoc = pandoc::new();
+//! pandoc.add_input("thesis.md");
+//! pandoc.add_csl_bibliography(&entries).unwrap();
+//! pandoc.add_option(pandoc::PandocOption::Citeproc);
+//! pandoc.set_csl_style("ieee").unwrap();
+//! ```
+
+use crate::PandocError;
+use serde_json::Value;
+use std::env;
+use std::path::{Path, PathBuf};
+use std::time::Duration;
+
+/// where the Better BibTeX plugin of a running Zotero serves its exports
+const BETTER_BIBTEX_URL: &str = "http://127.0.0.1:23119/better-bibtex/export/collection";
+
+/// the official repository of citation styles, with the independent styles at the top and
+/// the ones reusing another style's format in `dependent`
+const STYLES_URL: &str = "https://raw.githubusercontent.com/citation-style-language/styles/master";
+
+fn request(url: &str, accept: &str) -> Result<ureq::Response, Box<ureq::Error>> {
+    ureq::AgentBuilder::new()
+        .timeout(Duration::from_secs(30))
+        .build()
+        .get(url)
+        .set("Accept", accept)
+        .call()
+        .map_err(Box::new)
+}
+
+fn fetch_error(url: &str, error: Box<ureq::Error>) -> PandocError {
+    PandocError::FetchFailed(match *error {
+        ureq::Error::Status(code, _) => format!("{} returned status {}", url, code),
+        ureq::Error::Transport(transport) => transport.to_string(),
+    })
+}
+
+fn into_string(response: ureq::Response) -> Result<String, PandocError> {
+    response
+        .into_string()
+        .map_err(|e| PandocError::FetchFailed(e.to_string()))
+}
+
+fn get(url: &str, accept: &str) -> Result<String, PandocError> {
+    into_string(request(url, accept).map_err(|e| fetch_error(url, e))?)
+}
+
+fn parse(json: &str) -> Result<Value, PandocError> {
+    serde_json::from_str(json).map_err(|e| PandocError::FetchFailed(e.to_string()))
+}
+
+/// The doi.org URL of the DOI `doi`.
+///
+/// DOIs may contain characters like `#`, `?` or `%` that have a meaning in URLs, so
+/// everything but the unreserved characters and the `/` between prefix and suffix is
+/// percent-encoded.
+pub fn doi_url(doi: &str) -> String {
+    let mut url = String::from("https://doi.org/");
+    for byte in doi.bytes() {
+        match byte {
+            b'A'..=b'Z' | b'a'..=b'z' | b'0'..=b'9' | b'-' | b'.' | b'_' | b'~' | b'/' => {
+                url.push(char::from(byte))
+            }
+            _ => url.push_str(&format!("%{:02X}", byte)),
+        }
+    }
+    url
+}
+
+/// The CSL JSON entry of the work with the DOI `doi`, e.g. `10.1145/3290366`.
+///
+/// The entry is requested from doi.org with content negotiation, which the registration
+/// agencies like Crossref and DataCite answer. Its `id` is set to the DOI, so the work is
+/// cited as `[@10.1145/3290366]`.
+pub fn doi(doi: &str) -> Result<Value, PandocError> {
+    let json = get(&doi_url(doi), "application/vnd.citationstyles.csl+json")?;
+    let mut entry = parse(&json)?;
+    match entry.as_object_mut() {
+        Some(fields) => fields.insert("id".to_string(), Value::from(doi)),
+        None => {
+            return Err(PandocError::FetchFailed(format!(
+                "doi.org returned no entry for {}",
+                doi
+            )))
+        }
+    };
+    Ok(entry)
+}
+
+/// The CSL JSON entries of the Zotero collection `collection`, a path like `courses/nature`
+/// within the personal library.
+///
+/// Needs a running Zotero with the Better BibTeX plugin, the entries keep the citation keys
+/// Better BibTeX assigned.
+pub fn zotero_collection(collection: &str) -> Result<Vec<Value>, PandocError> {
+    let json = get(
+        &format!("{}?/1/{}.csljson", BETTER_BIBTEX_URL, collection),
+        "application/json",
+    )?;
+    match parse(&json)? {
+        Value::Array(entries) => Ok(entries),
+        _ => Err(PandocError::FetchFailed(format!(
+            "the Zotero collection {} is not a list of entries",
+            collection
+        ))),
+    }
+}
+
+/// the per-user cache directory of the downloaded citation styles
+pub(crate) fn default_style_cache() -> PathBuf {
+    let dir = if cfg!(windows) {
+        env::var_os("LOCALAPPDATA").map(PathBuf::from)
+    } else if cfg!(target_os = "macos") {
+        env::var_os("HOME").map(|home| Path::new(&home).join("Library").join("Caches"))
+    } else {
+        env::var_os("XDG_CACHE_HOME")
+            .map(PathBuf::from)
+            .or_else(|| env::var_os("HOME").map(|home| Path::new(&home).join(".cache")))
+    };
+    dir.unwrap_or_else(env::temp_dir)
+        .join("rust-pandoc")
+        .join("csl")
+}
+
+/// The file of the citation style `name` from the official style repository, e.g. `ieee` or
+/// `apa`, downloaded into `cache` unless it is there already.
+///
+/// The names are the file names of the styles without the `.csl` extension, as listed at
+/// <https://www.zotero.org/styles>. Cached styles are never updated, delete the file to
+/// download the style again.
+pub fn csl_style<P: AsRef<Path>>(name: &str, cache: P) -> Result<PathBuf, PandocError> {
+    let valid = !name.is_empty()
+        && name
+            .chars()
+            .all(|c| c.is_ascii_lowercase() || c.is_ascii_digit() || c == '-');
+    if !valid {
+        return Err(PandocError::InvalidConfig(format!(
+            "{:?} is not the name of a citation style",
+            name
+        )));
+    }
+    let file = cache.as_ref().join(format!("{}.csl", name));
+    if file.is_file() {
+        return Ok(file);
+    }
+    let independent = format!("{}/{}.csl", STYLES_URL, name);
+    let style = match request(&independent, "application/xml") {
+        Err(ref error) if matches!(**error, ureq::Error::Status(404, _)) => {
+            let dependent = format!("{}/dependent/{}.csl", STYLES_URL, name);
+            get(&dependent, "application/xml")?
+        }
+        response => into_string(response.map_err(|e| fetch_error(&independent, e))?)?,
+    };
+    std::fs::create_dir_all(cache.as_ref()).map_err(PandocError::IoErr)?;
+    // concurrent downloads of the same style must not see a partially written file
+    let partial = file.with_extension(format!("csl.{}.part", std::process::id()));
+    std::fs::write(&partial, style).map_err(PandocError::IoErr)?;
+    std::fs::rename(&partial, &file).map_err(PandocError::IoErr)?;
+    Ok(file)
+}
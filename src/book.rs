@@ -0,0 +1,210 @@
+//! assembling books from separately parsed chapters
+
+use crate::TempPath;
+use crate::{InputFormat, InputKind, OutputFormat, OutputKind, Tld};
+use crate::{Pandoc, PandocError, PandocExecutor, PandocOption, PandocOutput, ProcessExecutor};
+use std::path::{Path, PathBuf};
+use std::sync::Arc;
+
+/// One chapter of a [`Book`].
+#[derive(PartialEq, Eq, Clone, Debug)]
+pub struct Chapter {
+    file: PathBuf,
+    title: Option<String>,
+    id: Option<String>,
+    shift_heading_level_by: i32,
+    metadata: Vec<(String, String)>,
+}
+
+impl Chapter {
+    /// A chapter read from `file`, pandoc infers the format from the extension.
+    pub fn new<T: AsRef<Path> + ?Sized>(file: &T) -> Chapter {
+        Chapter {
+            file: file.as_ref().to_owned(),
+            title: None,
+            id: None,
+            shift_heading_level_by: 0,
+            metadata: Vec::new(),
+        }
+    }
+
+    /// Start the chapter with a level 1 heading, the title is parsed as markdown.
+    pub fn set_title<T: AsRef<str> + ?Sized>(&mut self, title: &T) -> &mut Chapter {
+        self.title = Some(title.as_ref().to_owned());
+        self
+    }
+
+    /// Set the identifier of the chapter heading, and prefix all identifiers in the chapter
+    /// with `id-`.
+    ///
+    /// Links within the chapter are adjusted, links to other chapters have to use the
+    /// prefixed identifiers, e.g. `#usage-installation`. Without an id, identifiers of
+    /// different chapters can clash, as every chapter is parsed separately.
+    pub fn set_id<T: AsRef<str> + ?Sized>(&mut self, id: &T) -> &mut Chapter {
+        self.id = Some(id.as_ref().to_owned());
+        self
+    }
+
+    /// Shift the headings of the chapter, e.g. by 1 if the chapter uses level 1 headings
+    /// for its sections below a title set with [`set_title`](#method.set_title).
+    pub fn set_shift_heading_level_by(&mut self, shift: i32) -> &mut Chapter {
+        self.shift_heading_level_by = shift;
+        self
+    }
+
+    /// Set metadata while parsing the chapter.
+    ///
+    /// The metadata of all chapters is merged into the metadata of the book, where the
+    /// metadata of the book and of earlier chapters take precedence.
+    pub fn add_metadata<T: AsRef<str> + ?Sized, U: AsRef<str> + ?Sized>(
+        &mut self,
+        key: &T,
+        value: &U,
+    ) -> &mut Chapter {
+        self.metadata
+            .push((key.as_ref().to_owned(), value.as_ref().to_owned()));
+        self
+    }
+}
+
+/// A book made of chapters which are parsed one at a time.
+///
+/// Concatenating the chapter files into one input mixes up footnotes and link references
+/// of different chapters. Instead, every chapter is parsed on its own, with its own
+/// heading shift, and the results are combined into one document with `--file-scope`:
+///
+/// ```no_run
+/// use pandoc::book::{Book, Chapter};
+///
+/// let mut book = Book::new("The Manual");
+/// book.add_chapter(Chapter::new("intro.md").set_title("Introduction").set_id("intro"))
+///     .add_chapter(
+///         Chapter::new("usage.md")
+///             .set_title("Usage")
+///             .set_id("usage")
+///             .set_shift_heading_level_by(1),
+///     );
+/// book.build("manual.pdf").unwrap().execute().unwrap();
+/// ```
+#[derive(PartialEq, Eq, Clone, Debug)]
+pub struct Book {
+    title: String,
+    chapters: Vec<Chapter>,
+}
+
+/// the lua filter prefixing the identifiers of a chapter
+fn prefix_filter(prefix: &str) -> String {
+    let prefix: String = prefix
+        .chars()
+        .filter(|c| c.is_alphanumeric() || *c == '-' || *c == '_')
+        .collect();
+    format!(
+        r##"local prefix = "{}-"
+local function prefix_ids(el)
+  if el.identifier ~= nil and el.identifier ~= "" then
+    el.identifier = prefix .. el.identifier
+  end
+  if el.t == "Link" and el.target:sub(1, 1) == "#" then
+    el.target = "#" .. prefix .. el.target:sub(2)
+  end
+  return el
+end
+return {{{{ Block = prefix_ids, Inline = prefix_ids }}}}
+"##,
+        prefix
+    )
+}
+
+/// run `pandoc` producing pandoc's JSON format in a temporary file
+fn to_json(
+    mut pandoc: Pandoc,
+    executor: &impl PandocExecutor,
+) -> Result<Arc<TempPath>, PandocError> {
+    pandoc.set_output_format(OutputFormat::Json, Vec::new());
+    pandoc.set_output(OutputKind::TempFile(".json".to_string()));
+    match pandoc.execute_with(executor)? {
+        PandocOutput::ToTempFile(file) => Ok(Arc::new(file)),
+        _ => unreachable!("temporary file outputs are returned as temporary files"),
+    }
+}
+
+impl Book {
+    /// Start a book with the given title.
+    pub fn new<T: AsRef<str> + ?Sized>(title: &T) -> Book {
+        Book {
+            title: title.as_ref().to_owned(),
+            chapters: Vec::new(),
+        }
+    }
+
+    /// Add a chapter, chapters are in the order they were added.
+    pub fn add_chapter(&mut self, chapter: &Chapter) -> &mut Book {
+        self.chapters.push(chapter.clone());
+        self
+    }
+
+    /// Parse the chapters and create the `Pandoc` builder writing the book to `output`.
+    ///
+    /// The output format is inferred from the extension of `output`, so a `.pdf`, `.epub` or
+    /// `.html` file is written. The top-level headings are chapters in PDF output.
+    pub fn build<T: AsRef<Path> + ?Sized>(&self, output: &T) -> Result<Pandoc, PandocError> {
+        self.build_with(&Pandoc::new(), &ProcessExecutor, output)
+    }
+
+    /// Parse the chapters with `executor` and create the builder writing the book to
+    /// `output`, see [`build`](#method.build).
+    ///
+    /// The chapters are parsed with the pandoc `template` finds with its path hints, and with
+    /// its timeout, sandbox, runtime system and data directory options. The returned builder
+    /// is a copy of `template` with the input and output replaced, so options like the PDF
+    /// engine set on it apply to the book.
+    pub fn build_with<T: AsRef<Path> + ?Sized>(
+        &self,
+        template: &Pandoc,
+        executor: &impl PandocExecutor,
+        output: &T,
+    ) -> Result<Pandoc, PandocError> {
+        let mut parts = Vec::new();
+        for chapter in &self.chapters {
+            if let Some(ref title) = chapter.title {
+                let heading = match chapter.id {
+                    Some(ref id) => format!("# {} {{#{}}}\n", title, id),
+                    None => format!("# {}\n", title),
+                };
+                let mut pandoc = template.child();
+                pandoc.set_input(InputKind::Pipe(heading));
+                pandoc.set_input_format(InputFormat::Markdown, Vec::new());
+                parts.push(to_json(pandoc, executor)?);
+            }
+            let mut pandoc = template.child();
+            pandoc.add_input(&chapter.file);
+            if chapter.shift_heading_level_by != 0 {
+                pandoc.add_option(PandocOption::ShiftHeadingLevelBy(
+                    chapter.shift_heading_level_by,
+                ));
+            }
+            for (key, value) in &chapter.metadata {
+                pandoc.add_option(PandocOption::Meta(key.clone(), Some(value.clone())));
+            }
+            if let Some(ref id) = chapter.id {
+                let filter = TempPath::new(".lua").map_err(PandocError::IoErr)?;
+                std::fs::write(&filter, prefix_filter(id)).map_err(PandocError::IoErr)?;
+                pandoc.add_option(PandocOption::LuaFilter(filter.path().to_owned()));
+                pandoc.temp_files.push(Arc::new(filter));
+            }
+            parts.push(to_json(pandoc, executor)?);
+        }
+        let mut pandoc = template.clone();
+        pandoc.input = None;
+        pandoc
+            .add_inputs(parts.iter().map(|part| part.path()))
+            .set_input_format(InputFormat::Json, Vec::new())
+            .set_output(OutputKind::File(output.as_ref().to_owned()))
+            .add_option(PandocOption::FileScope)
+            .add_option(PandocOption::Standalone)
+            .add_option(PandocOption::TopLevelDivision(Tld::Chapter))
+            .set_title(&self.title);
+        pandoc.temp_files.extend(parts);
+        Ok(pandoc)
+    }
+}
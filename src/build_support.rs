@@ -0,0 +1,67 @@
+//! helpers for converting documents in build scripts
+//!
+//! The conversions tell cargo to rerun the build script when an input changes, and turn a
+//! missing pandoc installation into a build warning instead of a failed build, so crates can
+//! still be built on machines without pandoc:
+//!
+//! ```no_run
+//! // build.rs
+//! if let Some(man_page) = pandoc::build_support::convert_to_out_dir("README.md", "tool.1").unwrap() {
+//!     println!("cargo:rustc-env=MAN_PAGE={}", man_page.display());
+//! }
+//! ```
+
+use crate::{OutputKind, Pandoc, PandocError, PandocOption, PandocOutput};
+use std::path::{Path, PathBuf};
+
+/// The `OUT_DIR` cargo sets for build scripts.
+///
+/// # Panics
+///
+/// Panics if called outside of a build script.
+pub fn out_dir() -> PathBuf {
+    std::env::var_os("OUT_DIR")
+        .expect("OUT_DIR is only set for build scripts")
+        .into()
+}
+
+/// Print `cargo:rerun-if-changed` lines for the input files and the files referenced by the
+/// options of `pandoc`.
+///
+/// Cargo can't watch glob patterns, so files newly matching an input pattern are not noticed.
+pub fn rerun_if_changed(pandoc: &Pandoc) {
+    for file in pandoc.dependencies() {
+        println!("cargo:rerun-if-changed={}", file.display());
+    }
+}
+
+/// Execute the conversion from a build script.
+///
+/// Prints the `cargo:rerun-if-changed` lines for the inputs. If pandoc is not installed, a
+/// `cargo:warning` is printed and `None` is returned instead of failing.
+pub fn execute(pandoc: Pandoc) -> Result<Option<PandocOutput>, PandocError> {
+    rerun_if_changed(&pandoc);
+    match pandoc.execute() {
+        Err(PandocError::PandocNotFound) => {
+            println!("cargo:warning=pandoc was not found, skipping the document conversion");
+            Ok(None)
+        }
+        result => result.map(Some),
+    }
+}
+
+/// Convert `input` to a standalone document in `OUT_DIR` with the name `output`.
+///
+/// Pandoc infers the formats from the file extensions. Returns the path of the converted
+/// file, or `None` if pandoc is not installed, see [`execute`](fn.execute.html).
+pub fn convert_to_out_dir<P: AsRef<Path>, Q: AsRef<Path>>(
+    input: P,
+    output: Q,
+) -> Result<Option<PathBuf>, PandocError> {
+    let output = out_dir().join(output);
+    let mut pandoc = Pandoc::new();
+    pandoc.add_input(input.as_ref());
+    pandoc.set_output(OutputKind::File(output.clone()));
+    pandoc.add_option(PandocOption::Standalone);
+    Ok(execute(pandoc)?.map(|_| output))
+}
@@ -0,0 +1,179 @@
+//! caching of conversion outputs, see `Pandoc::set_cache_dir`
+
+use crate::{InputFormat, InputKind, InputSource, Invocation, OutputFormat, OutputKind};
+use crate::{Pandoc, PandocError, PandocExecutor, PandocOption, PandocOutput};
+use std::fmt::Debug;
+use std::path::{Path, PathBuf};
+
+/// The place of the output of one conversion in the cache directory.
+pub(crate) struct CacheEntry {
+    path: PathBuf,
+}
+
+/// 64-bit FNV-1a over explicitly written bytes
+///
+/// Unlike `DefaultHasher` and the `Hash` implementations of the standard library, it hashes
+/// the same on every platform and with every Rust release, so entries stay valid across
+/// builds of the program.
+struct StableHasher(u64);
+
+impl StableHasher {
+    fn new() -> StableHasher {
+        StableHasher(0xcbf2_9ce4_8422_2325)
+    }
+
+    /// hash `bytes`, prefixed with their length, so consecutive fields can't run together
+    fn write(&mut self, bytes: &[u8]) {
+        for &byte in (bytes.len() as u64).to_le_bytes().iter().chain(bytes) {
+            self.0 ^= u64::from(byte);
+            self.0 = self.0.wrapping_mul(0x0100_0000_01b3);
+        }
+    }
+
+    fn write_debug<T: Debug + ?Sized>(&mut self, value: &T) {
+        self.write(format!("{:?}", value).as_bytes());
+    }
+}
+
+/// hash the contents of `file`, looking it up in the resource path like pandoc
+fn hash_file(pandoc: &Pandoc, file: &Path, hasher: &mut StableHasher) -> Option<()> {
+    let contents = std::fs::read(file).ok().or_else(|| {
+        pandoc
+            .options
+            .iter()
+            .filter_map(|option| match *option {
+                PandocOption::ResourcePath(ref paths) => Some(paths),
+                _ => None,
+            })
+            .flatten()
+            .find_map(|dir| std::fs::read(dir.join(file)).ok())
+    })?;
+    hasher.write(&contents);
+    Some(())
+}
+
+impl CacheEntry {
+    /// The cache entry of the conversion configured by `pandoc`, or `None` if it can't be
+    /// cached.
+    pub(crate) fn new(
+        pandoc: &Pandoc,
+        dir: &Path,
+        executor: &dyn PandocExecutor,
+    ) -> Result<Option<CacheEntry>, PandocError> {
+        if !pandoc.filters.is_empty() || !pandoc.input_preprocessors.is_empty() {
+            return Ok(None);
+        }
+        let mut hasher = StableHasher::new();
+        match pandoc.output {
+            Some(OutputKind::File(ref file)) => hasher.write_debug(&file.extension()),
+            Some(OutputKind::TempFile(ref suffix)) => hasher.write(suffix.as_bytes()),
+            Some(OutputKind::Pipe) => {}
+            _ => return Ok(None),
+        }
+        let inputs = match pandoc.input {
+            Some(InputKind::Pipe(ref text)) => {
+                hasher.write(text.as_bytes());
+                Vec::new()
+            }
+            Some(InputKind::Files(_)) => pandoc.input_files(),
+            Some(InputKind::Sources(ref sources)) => {
+                if sources
+                    .iter()
+                    .any(|source| matches!(source, InputSource::Url(_)))
+                {
+                    return Ok(None);
+                }
+                pandoc.input_files()
+            }
+            _ => return Ok(None),
+        };
+        for input in &inputs {
+            hasher.write_debug(&input.extension());
+            if hash_file(pandoc, input, &mut hasher).is_none() {
+                return Ok(None);
+            }
+        }
+        // the behavior of custom readers and writers is defined by their scripts
+        let reader = match pandoc.input_format {
+            Some((InputFormat::Lua(ref script), _)) => Some(script.as_path()),
+            _ => None,
+        };
+        let writer = match pandoc.output_format {
+            Some((OutputFormat::Lua(ref script), _)) => Some(Path::new(script)),
+            _ => None,
+        };
+        for script in reader.into_iter().chain(writer) {
+            if hash_file(pandoc, script, &mut hasher).is_none() {
+                return Ok(None);
+            }
+        }
+        hasher.write_debug(&(
+            &pandoc.input_format,
+            &pandoc.output_format,
+            &pandoc.args,
+            pandoc.infer_input_format,
+            pandoc.reproducible,
+            pandoc.lossy_utf8,
+            &pandoc.fail_on,
+        ));
+        // pandoc dates documents with it even if the conversion is not reproducible
+        hasher.write_debug(&std::env::var_os("SOURCE_DATE_EPOCH"));
+        for option in &pandoc.options {
+            match option.referenced_file() {
+                // the contents matter, not the path, which may be a generated temporary file
+                Some(file) => {
+                    let option = format!("{:?}", option);
+                    hasher.write(option.split('(').next().unwrap_or_default().as_bytes());
+                    if hash_file(pandoc, file, &mut hasher).is_none() {
+                        return Ok(None);
+                    }
+                }
+                None => hasher.write_debug(option),
+            }
+        }
+        let mut cmd = pandoc.command();
+        cmd.arg("--version")
+            .stdout(std::process::Stdio::piped())
+            .stderr(std::process::Stdio::piped());
+        let version = executor.execute(Invocation::new(cmd, None))?;
+        if !version.status.success() {
+            return Ok(None);
+        }
+        hasher.write(&version.stdout);
+        Ok(Some(CacheEntry {
+            path: dir.join(format!("{:016x}", hasher.0)),
+        }))
+    }
+
+    /// The cached output, if the conversion ran before.
+    pub(crate) fn load(&self) -> Option<Vec<u8>> {
+        std::fs::read(&self.path).ok()
+    }
+
+    /// Store the output of a successful conversion.
+    pub(crate) fn store_output(&self, output: &PandocOutput) -> Result<(), PandocError> {
+        match *output {
+            PandocOutput::ToFile(ref file) => {
+                self.store(&std::fs::read(file).map_err(PandocError::IoErr)?)
+            }
+            PandocOutput::ToTempFile(ref file) => {
+                self.store(&std::fs::read(file).map_err(PandocError::IoErr)?)
+            }
+            PandocOutput::ToBuffer(ref text) => self.store(text.as_bytes()),
+            PandocOutput::ToBufferRaw(ref bytes) => self.store(bytes),
+            _ => Ok(()),
+        }
+    }
+
+    fn store(&self, output: &[u8]) -> Result<(), PandocError> {
+        let dir = self
+            .path
+            .parent()
+            .expect("cache entries are in the cache dir");
+        std::fs::create_dir_all(dir).map_err(PandocError::IoErr)?;
+        // a concurrent conversion must not see a partially written entry
+        let partial = self.path.with_extension("partial");
+        std::fs::write(&partial, output).map_err(PandocError::IoErr)?;
+        std::fs::rename(&partial, &self.path).map_err(PandocError::IoErr)
+    }
+}
@@ -0,0 +1,121 @@
+//! citation processing with citeproc
+
+#[cfg(feature = "ast")]
+use crate::{InputFormat, OutputFormat, OutputKind, Pandoc, PandocError, PandocOutput};
+#[cfg(feature = "ast")]
+use pandoc_ast::{Inline, MetaValue, MutVisitor};
+#[cfg(feature = "ast")]
+use std::collections::BTreeSet;
+#[cfg(feature = "ast")]
+use std::path::Path;
+use std::path::PathBuf;
+
+/// Everything pandoc's citation processing needs, see
+/// [`Pandoc::set_citeproc`](struct.Pandoc.html#method.set_citeproc).
+#[derive(PartialEq, Eq, Clone, Debug, Default)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[cfg_attr(feature = "serde", serde(default))]
+pub struct CitationConfig {
+    /// the bibliography files (BibLaTeX, BibTeX, CSL JSON or YAML)
+    pub bibliographies: Vec<PathBuf>,
+    /// the citation style, pandoc defaults to Chicago author-date
+    pub csl: Option<PathBuf>,
+    /// link citations to their bibliography entries
+    pub link_citations: bool,
+    /// keys of entries listed in the bibliography without being cited, `*` for all entries
+    pub nocite: Vec<String>,
+}
+
+/// The citations of a document, see
+/// [`Pandoc::citation_report`](struct.Pandoc.html#method.citation_report).
+#[cfg(feature = "ast")]
+#[derive(PartialEq, Eq, Clone, Debug, Default)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct CitationReport {
+    /// the keys of all cited works
+    pub cited: BTreeSet<String>,
+    /// the cited keys that are neither in the bibliographies nor in the `references`
+    /// metadata
+    pub missing: BTreeSet<String>,
+}
+
+#[cfg(feature = "ast")]
+#[derive(Default)]
+struct CiteCollector {
+    keys: BTreeSet<String>,
+}
+
+#[cfg(feature = "ast")]
+impl MutVisitor for CiteCollector {
+    fn visit_inline(&mut self, inline: &mut Inline) {
+        if let Inline::Cite(ref citations, _) = *inline {
+            self.keys
+                .extend(citations.iter().map(|citation| citation.citationId.clone()));
+        }
+        self.walk_inline(inline)
+    }
+}
+
+/// The keys of the works cited in `document`, including citations in the metadata.
+#[cfg(feature = "ast")]
+pub fn cited_keys(document: &pandoc_ast::Pandoc) -> BTreeSet<String> {
+    let mut collector = CiteCollector::default();
+    collector.walk_pandoc(&mut document.clone());
+    collector.keys
+}
+
+/// the ids of the entries in the `references` metadata of `document`
+#[cfg(feature = "ast")]
+pub(crate) fn reference_ids(document: &pandoc_ast::Pandoc) -> BTreeSet<String> {
+    let references = match document.meta.get("references") {
+        Some(MetaValue::MetaList(references)) => references,
+        _ => return BTreeSet::new(),
+    };
+    references
+        .iter()
+        .filter_map(|reference| match *reference {
+            MetaValue::MetaMap(ref fields) => match fields.get("id").map(|id| &**id) {
+                Some(MetaValue::MetaString(id)) => Some(id.clone()),
+                Some(MetaValue::MetaInlines(id)) => Some(crate::outline::stringify(id)),
+                _ => None,
+            },
+            _ => None,
+        })
+        .collect()
+}
+
+/// The ids of the entries of the bibliography `file`, read by converting it to CSL JSON
+/// with the pandoc of `pandoc`.
+#[cfg(feature = "ast")]
+pub(crate) fn bibliography_keys(
+    pandoc: &Pandoc,
+    file: &Path,
+) -> Result<BTreeSet<String>, PandocError> {
+    let extension = file
+        .extension()
+        .and_then(|extension| extension.to_str())
+        .unwrap_or_default()
+        .to_ascii_lowercase();
+    let format = match extension.as_str() {
+        "json" => InputFormat::CslJson,
+        // YAML bibliographies are markdown metadata blocks with a `references` field
+        "yaml" | "yml" => InputFormat::Markdown,
+        extension => InputFormat::from_extension(extension).unwrap_or(InputFormat::BibLaTeX),
+    };
+    let mut reader = pandoc.child();
+    reader
+        .add_input(file)
+        .set_input_format(format, Vec::new())
+        .set_output_format(OutputFormat::CslJson, Vec::new())
+        .set_output(OutputKind::Pipe);
+    let json = match reader.execute()? {
+        PandocOutput::ToBuffer(json) => json,
+        _ => unreachable!("text output to a pipe is returned as a buffer"),
+    };
+    let entries: Vec<serde_json::Value> =
+        serde_json::from_str(&json).map_err(|e| PandocError::InvalidAst(e.to_string()))?;
+    Ok(entries
+        .iter()
+        .filter_map(|entry| entry.get("id")?.as_str().map(str::to_string))
+        .collect())
+}
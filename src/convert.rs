@@ -0,0 +1,65 @@
+//! one-line conversions for the common cases
+
+use crate::{InputFormat, InputKind, OutputFormat, OutputKind};
+use crate::{Pandoc, PandocError, PandocOption, PandocOutput};
+use std::ffi::OsStr;
+use std::path::{Path, PathBuf};
+
+fn convert(input: &str, from: InputFormat, to: OutputFormat) -> Result<PandocOutput, PandocError> {
+    let mut pandoc = Pandoc::new();
+    pandoc.set_input(InputKind::Pipe(input.to_string()));
+    pandoc.set_input_format(from, Vec::new());
+    pandoc.set_output(OutputKind::Pipe);
+    pandoc.set_output_format(to, Vec::new());
+    pandoc.execute()
+}
+
+fn into_string(output: PandocOutput) -> String {
+    match output {
+        PandocOutput::ToBuffer(text) => text,
+        _ => unreachable!("text output to a pipe is returned as a buffer"),
+    }
+}
+
+/// Convert pandoc markdown to an HTML5 fragment.
+pub fn markdown_to_html(markdown: &str) -> Result<String, PandocError> {
+    convert(markdown, InputFormat::Markdown, OutputFormat::Html5).map(into_string)
+}
+
+/// Convert HTML to pandoc markdown.
+pub fn html_to_markdown(html: &str) -> Result<String, PandocError> {
+    convert(html, InputFormat::Html, OutputFormat::Markdown).map(into_string)
+}
+
+/// Convert pandoc markdown to the contents of a Word document.
+pub fn markdown_to_docx_bytes(markdown: &str) -> Result<Vec<u8>, PandocError> {
+    match convert(markdown, InputFormat::Markdown, OutputFormat::Docx)? {
+        PandocOutput::ToBufferRaw(bytes) => Ok(bytes),
+        _ => unreachable!("binary output to a pipe is returned as raw bytes"),
+    }
+}
+
+/// Convert the file `from` to a standalone document `to`, returning the path of the output.
+///
+/// Like the pandoc command line, the reader and writer are chosen by the file extensions.
+/// Pandoc falls back to markdown input and HTML output for unknown extensions.
+pub fn convert_file<P: AsRef<Path>, Q: AsRef<Path>>(
+    from: P,
+    to: Q,
+) -> Result<PathBuf, PandocError> {
+    let to = to.as_ref().to_owned();
+    let mut pandoc = Pandoc::new();
+    pandoc.set_infer_input_format(true);
+    pandoc.add_input(from.as_ref());
+    if let Some(format) = to
+        .extension()
+        .and_then(OsStr::to_str)
+        .and_then(OutputFormat::from_extension)
+    {
+        pandoc.set_output_format(format, Vec::new());
+    }
+    pandoc.set_output(OutputKind::File(to.clone()));
+    pandoc.add_option(PandocOption::Standalone);
+    pandoc.execute()?;
+    Ok(to)
+}
@@ -0,0 +1,66 @@
+//! numbered cross-references with pandoc-crossref
+
+use std::path::PathBuf;
+
+/// The settings of the pandoc-crossref filter, see
+/// [`Pandoc::enable_crossref`](struct.Pandoc.html#method.enable_crossref).
+///
+/// Unset fields keep pandoc-crossref's defaults.
+#[derive(PartialEq, Eq, Clone, Debug, Default)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[cfg_attr(feature = "serde", serde(default))]
+pub struct CrossrefConfig {
+    /// the pandoc-crossref executable, searched in the path hints and `PATH` if not set
+    pub filter: Option<PathBuf>,
+    /// the word put before figure numbers in references, e.g. `Fig.`
+    pub figure_prefix: Option<String>,
+    /// the word put before table numbers in references
+    pub table_prefix: Option<String>,
+    /// the word put before equation numbers in references
+    pub equation_prefix: Option<String>,
+    /// the word put before code listing numbers in references
+    pub listing_prefix: Option<String>,
+    /// the word put before section numbers in references
+    pub section_prefix: Option<String>,
+    /// number figures, tables and equations per chapter, e.g. `2.3`
+    pub chapters: bool,
+    /// number the sections, so they can be referenced
+    pub number_sections: bool,
+    /// make references links to their targets
+    pub link_references: bool,
+    /// allow captions on code blocks, to reference them as listings
+    pub code_block_captions: bool,
+    /// use the cleveref LaTeX package for references in LaTeX output
+    pub cref: bool,
+}
+
+impl CrossrefConfig {
+    /// the metadata options pandoc-crossref reads its settings from
+    pub(crate) fn metadata(&self) -> Vec<(&'static str, String)> {
+        let prefixes = [
+            ("figPrefix", &self.figure_prefix),
+            ("tblPrefix", &self.table_prefix),
+            ("eqnPrefix", &self.equation_prefix),
+            ("lstPrefix", &self.listing_prefix),
+            ("secPrefix", &self.section_prefix),
+        ];
+        let flags = [
+            ("chapters", self.chapters),
+            ("numberSections", self.number_sections),
+            ("linkReferences", self.link_references),
+            ("codeBlockCaptions", self.code_block_captions),
+            ("cref", self.cref),
+        ];
+        prefixes
+            .iter()
+            .filter_map(|&(key, value)| Some((key, value.clone()?)))
+            // pandoc parses `true` given with `--metadata` as a boolean
+            .chain(
+                flags
+                    .iter()
+                    .filter(|&&(_, flag)| flag)
+                    .map(|&(key, _)| (key, "true".to_string())),
+            )
+            .collect()
+    }
+}
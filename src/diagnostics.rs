@@ -0,0 +1,86 @@
+//! parsing of the warnings pandoc prints to stderr
+
+/// Categories of warnings reported by pandoc
+#[derive(PartialEq, Eq, Hash, Copy, Clone, Debug)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[non_exhaustive]
+pub enum WarningClass {
+    /// an image or other resource could not be found or fetched
+    MissingResource,
+    /// the same identifier was assigned to several elements
+    DuplicateIdentifier,
+    /// a citation key was not found in the bibliography
+    CitationNotFound,
+    /// any warning not covered by the other classes
+    Other,
+}
+
+/// A single warning reported by pandoc
+#[derive(PartialEq, Eq, Clone, Debug)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct Warning {
+    /// the category of the warning
+    pub class: WarningClass,
+    /// the warning text, without the `[WARNING]` prefix
+    pub message: String,
+}
+
+impl std::fmt::Display for Warning {
+    fn fmt(&self, fmt: &mut std::fmt::Formatter) -> std::fmt::Result {
+        write!(fmt, "{}", self.message)
+    }
+}
+
+impl WarningClass {
+    fn classify(message: &str) -> WarningClass {
+        if message.starts_with("Could not fetch resource")
+            || message.starts_with("Could not find image")
+            || message.contains("not found in resource path")
+        {
+            WarningClass::MissingResource
+        } else if message.starts_with("Duplicate identifier") {
+            WarningClass::DuplicateIdentifier
+        } else if message.starts_with("Citeproc: citation")
+            || (message.starts_with("pandoc-citeproc: reference") && message.ends_with("not found"))
+        {
+            WarningClass::CitationNotFound
+        } else {
+            WarningClass::Other
+        }
+    }
+}
+
+/// Parse the warnings from pandoc's stderr output.
+///
+/// Warnings are recognized by pandoc's `[WARNING]` prefix. Older pandoc versions print some
+/// warnings without a prefix, these are recognized by their text if they belong to a known
+/// class. Indented continuation lines are appended to the preceding warning.
+pub fn parse_warnings(stderr: &str) -> Vec<Warning> {
+    let mut warnings: Vec<Warning> = Vec::new();
+    let mut continued = false;
+    for line in stderr.lines() {
+        if let Some(message) = line.strip_prefix("[WARNING]") {
+            let message = message.trim().to_string();
+            warnings.push(Warning {
+                class: WarningClass::classify(&message),
+                message,
+            });
+            continued = true;
+        } else if continued && line.starts_with(char::is_whitespace) && !line.trim().is_empty() {
+            let warning = warnings.last_mut().unwrap();
+            warning.message.push('\n');
+            warning.message.push_str(line.trim());
+        } else {
+            let message = line.trim_start_matches("pandoc: ").trim();
+            let class = WarningClass::classify(message);
+            continued = class != WarningClass::Other;
+            if continued {
+                warnings.push(Warning {
+                    class,
+                    message: message.to_string(),
+                });
+            }
+        }
+    }
+    warnings
+}
@@ -0,0 +1,292 @@
+//! structural comparison of two documents
+//!
+//! Both documents are read into pandoc's AST and compared block by block, so changes of the
+//! formatting in the output or of the line wrapping in the input don't show up:
+//!
+//! ```no_run
+//! let mut old = pandoc::new();
+//! old.add_input("report-v1.docx");
+//! let mut new = pandoc::new();
+//! new.add_input("report-v2.docx");
+//! let diff = pandoc::diff::diff(old, new).unwrap();
+//! std::fs::write("changes.html", diff.to_html().unwrap()).unwrap();
+//! ```
+
+use crate::outline::stringify;
+use crate::{InputFormat, InputKind, OutputFormat, OutputKind, Pandoc, PandocError, PandocOutput};
+use pandoc_ast::{Block, Format};
+
+/// A change of the blocks between the two documents.
+#[derive(Clone, Debug, PartialEq)]
+#[non_exhaustive]
+pub enum Change {
+    /// the block is in both documents
+    Unchanged(Block),
+    /// the block is only in the new document
+    Inserted(Block),
+    /// the block is only in the old document
+    Deleted(Block),
+    /// the block was edited, e.g. a paragraph with a rephrased sentence
+    Modified {
+        /// the block in the old document
+        old: Box<Block>,
+        /// the block in the new document
+        new: Box<Block>,
+    },
+    /// A section, a heading and the blocks up to the next heading, is at a different
+    /// position in the new document. It is listed at its new position, with the changes
+    /// within the section.
+    Moved {
+        /// the text of the section's heading
+        heading: String,
+        /// the changes of the blocks of the section, starting with its heading
+        changes: Vec<Change>,
+    },
+}
+
+/// The changes from one document to another, in the order of the new document, see
+/// [`diff`](fn.diff.html).
+#[derive(Clone, Debug, PartialEq)]
+pub struct DocumentDiff {
+    /// the blocks of both documents, with how each of them changed
+    pub changes: Vec<Change>,
+    /// the AST version of the new document, to render the changes with the same pandoc
+    api_version: Vec<u32>,
+}
+
+/// a heading and the blocks up to the next heading, the first section has no heading if
+/// the document doesn't start with one
+struct Section<'a> {
+    heading: Option<&'a Block>,
+    blocks: &'a [Block],
+}
+
+fn sections(blocks: &[Block]) -> Vec<Section<'_>> {
+    let mut sections = Vec::new();
+    let mut start = 0;
+    for end in 1..=blocks.len() {
+        if end == blocks.len() || is_heading(&blocks[end]) {
+            sections.push(Section {
+                heading: Some(&blocks[start]).filter(|block| is_heading(block)),
+                blocks: &blocks[start..end],
+            });
+            start = end;
+        }
+    }
+    sections
+}
+
+fn is_heading(block: &Block) -> bool {
+    matches!(*block, Block::Header(..))
+}
+
+/// the index pairs of a longest common subsequence of `old` and `new`
+fn lcs<T: PartialEq>(old: &[T], new: &[T]) -> Vec<(usize, usize)> {
+    // lengths[i][j] is the length of the longest common subsequence of old[i..] and new[j..]
+    let mut lengths = vec![vec![0usize; new.len() + 1]; old.len() + 1];
+    for i in (0..old.len()).rev() {
+        for j in (0..new.len()).rev() {
+            lengths[i][j] = if old[i] == new[j] {
+                lengths[i + 1][j + 1] + 1
+            } else {
+                lengths[i + 1][j].max(lengths[i][j + 1])
+            };
+        }
+    }
+    let mut pairs = Vec::new();
+    let (mut i, mut j) = (0, 0);
+    while i < old.len() && j < new.len() {
+        if old[i] == new[j] {
+            pairs.push((i, j));
+            i += 1;
+            j += 1;
+        } else if lengths[i + 1][j] >= lengths[i][j + 1] {
+            i += 1;
+        } else {
+            j += 1;
+        }
+    }
+    pairs
+}
+
+/// Compare the blocks of two sections, or of two documents without headings.
+///
+/// Runs of deleted blocks followed by inserted blocks are paired into modifications where
+/// the blocks are of the same kind.
+fn diff_blocks(old: &[Block], new: &[Block]) -> Vec<Change> {
+    let mut changes = Vec::new();
+    let (mut i, mut j) = (0, 0);
+    let anchors = lcs(old, new)
+        .into_iter()
+        .chain(std::iter::once((old.len(), new.len())));
+    for (next_i, next_j) in anchors {
+        let mut deleted = old[i..next_i].iter().peekable();
+        for inserted in &new[j..next_j] {
+            let kind = std::mem::discriminant(inserted);
+            while let Some(block) = deleted.next_if(|block| std::mem::discriminant(*block) != kind)
+            {
+                changes.push(Change::Deleted(block.clone()));
+            }
+            match deleted.next() {
+                Some(block) => changes.push(Change::Modified {
+                    old: Box::new(block.clone()),
+                    new: Box::new(inserted.clone()),
+                }),
+                None => changes.push(Change::Inserted(inserted.clone())),
+            }
+        }
+        changes.extend(deleted.cloned().map(Change::Deleted));
+        if next_i < old.len() {
+            changes.push(Change::Unchanged(new[next_j].clone()));
+        }
+        i = next_i + 1;
+        j = next_j + 1;
+    }
+    changes
+}
+
+/// Compare two documents read into pandoc's AST.
+///
+/// The sections of the documents are matched by their headings. Matched sections are
+/// compared block by block, sections whose order changed are reported as
+/// `Change::Moved`. The metadata of the documents is not compared.
+pub fn diff_documents(old: &pandoc_ast::Pandoc, new: &pandoc_ast::Pandoc) -> DocumentDiff {
+    let old_sections = sections(&old.blocks);
+    let new_sections = sections(&new.blocks);
+
+    // the old section matched with each new section, repeated headings are matched in order
+    let mut matched = vec![false; old_sections.len()];
+    let partners: Vec<Option<usize>> = new_sections
+        .iter()
+        .map(|section| {
+            let partner = (0..old_sections.len())
+                .find(|&i| !matched[i] && old_sections[i].heading == section.heading)?;
+            matched[partner] = true;
+            Some(partner)
+        })
+        .collect();
+    // the matched sections that kept their order, all others were moved
+    let old_order: Vec<usize> = (0..old_sections.len()).filter(|&i| matched[i]).collect();
+    let new_order: Vec<usize> = partners.iter().flatten().copied().collect();
+    let in_order: Vec<usize> = lcs(&old_order, &new_order)
+        .into_iter()
+        .map(|(i, _)| old_order[i])
+        .collect();
+
+    let mut changes = Vec::new();
+    let mut next_old = 0;
+    let mut delete_until = |changes: &mut Vec<Change>, end: usize| {
+        while next_old < end {
+            if !matched[next_old] {
+                changes.extend(
+                    old_sections[next_old]
+                        .blocks
+                        .iter()
+                        .cloned()
+                        .map(Change::Deleted),
+                );
+            }
+            next_old += 1;
+        }
+    };
+    for (section, partner) in new_sections.iter().zip(partners) {
+        match partner {
+            None => changes.extend(section.blocks.iter().cloned().map(Change::Inserted)),
+            Some(partner) if in_order.contains(&partner) => {
+                delete_until(&mut changes, partner + 1);
+                changes.extend(diff_blocks(old_sections[partner].blocks, section.blocks));
+            }
+            Some(partner) => changes.push(Change::Moved {
+                heading: match section.heading {
+                    Some(Block::Header(_, _, ref inlines)) => stringify(inlines),
+                    _ => String::new(),
+                },
+                changes: diff_blocks(old_sections[partner].blocks, section.blocks),
+            }),
+        }
+    }
+    delete_until(&mut changes, old_sections.len());
+
+    DocumentDiff {
+        changes,
+        api_version: new.pandoc_api_version.clone(),
+    }
+}
+
+/// Read both documents with their configured readers and compare them, see
+/// [`diff_documents`](fn.diff_documents.html).
+pub fn diff(old: Pandoc, new: Pandoc) -> Result<DocumentDiff, PandocError> {
+    Ok(diff_documents(
+        &old.execute_to_ast()?,
+        &new.execute_to_ast()?,
+    ))
+}
+
+fn html(markup: &str) -> Block {
+    Block::RawBlock(Format("html".to_string()), markup.to_string())
+}
+
+fn push_marked(blocks: &mut Vec<Block>, changes: &[Change]) {
+    for change in changes {
+        match *change {
+            Change::Unchanged(ref block) => blocks.push(block.clone()),
+            Change::Inserted(ref block) => {
+                blocks.extend([html("<ins>"), block.clone(), html("</ins>")])
+            }
+            Change::Deleted(ref block) => {
+                blocks.extend([html("<del>"), block.clone(), html("</del>")])
+            }
+            Change::Modified { ref old, ref new } => blocks.extend([
+                html("<del>"),
+                (**old).clone(),
+                html("</del>"),
+                html("<ins>"),
+                (**new).clone(),
+                html("</ins>"),
+            ]),
+            Change::Moved { ref changes, .. } => {
+                blocks.push(html("<div class=\"moved\">"));
+                push_marked(blocks, changes);
+                blocks.push(html("</div>"));
+            }
+        }
+    }
+}
+
+impl DocumentDiff {
+    /// Whether the documents have the same blocks.
+    pub fn is_empty(&self) -> bool {
+        self.changes
+            .iter()
+            .all(|change| matches!(*change, Change::Unchanged(_)))
+    }
+
+    /// The new document with the changes marked up.
+    ///
+    /// Inserted blocks are wrapped in `<ins>` and deleted ones in `<del>` elements, a
+    /// modified block is shown as both. Moved sections are wrapped in a `<div>` with the
+    /// class `moved`. The markup consists of raw HTML blocks, which only HTML writers
+    /// output.
+    pub fn to_document(&self) -> pandoc_ast::Pandoc {
+        let mut blocks = Vec::new();
+        push_marked(&mut blocks, &self.changes);
+        pandoc_ast::Pandoc {
+            meta: Default::default(),
+            blocks,
+            pandoc_api_version: self.api_version.clone(),
+        }
+    }
+
+    /// Render the [marked up document](#method.to_document) as an HTML5 fragment.
+    pub fn to_html(&self) -> Result<String, PandocError> {
+        let mut pandoc = Pandoc::new();
+        pandoc.set_input(InputKind::Pipe(self.to_document().to_json()));
+        pandoc.set_input_format(InputFormat::Json, Vec::new());
+        pandoc.set_output(OutputKind::Pipe);
+        pandoc.set_output_format(OutputFormat::Html5, Vec::new());
+        match pandoc.execute()? {
+            PandocOutput::ToBuffer(html) => Ok(html),
+            _ => unreachable!("text output to a pipe is returned as a buffer"),
+        }
+    }
+}
@@ -0,0 +1,210 @@
+//! building documents in Rust instead of generating markdown
+//!
+//! [`DocumentBuilder`] assembles pandoc's AST directly, so text taken from data doesn't need
+//! to be escaped for any input format. The document is passed to pandoc as JSON:
+//!
+//! ```no_run
+//! use pandoc::doc::DocumentBuilder;
+//!
+//! let mut doc = DocumentBuilder::new();
+//! doc.meta("title", "Monthly sales")
+//!     .heading(1, "Summary")
+//!     .paragraph("Revenue grew by *5%*, no markup is interpreted here.")
+//!     .table(&["Region", "Revenue"], &[vec!["North", "120"], vec!["South", "95"]])
+//!     .code_block(Some("sql"), "SELECT region, sum(revenue) FROM sales GROUP BY region");
+//!
+//! let mut pandoc = pandoc::new();
+//! pandoc.set_input_document(&doc.build());
+//! pandoc.set_output(pandoc::OutputKind::File("report.docx".into()));
+//! pandoc.execute().unwrap();
+//! ```
+//!
+//! The documents use the AST of pandoc 3.0 and newer.
+
+use pandoc_ast::{
+    Alignment, Block, ColWidth, Inline, Map, MetaValue, Pandoc as Document, Row, TableHead,
+};
+
+/// The AST version of the built documents, pandoc reads documents with the same major and
+/// minor version.
+const API_VERSION: [u32; 2] = [1, 23];
+
+/// Inline content, the conversions from strings split the text into words.
+#[derive(Clone, Debug, PartialEq, Default)]
+pub struct Inlines(pub Vec<Inline>);
+
+impl From<&str> for Inlines {
+    fn from(text: &str) -> Inlines {
+        Inlines(text_inlines(text))
+    }
+}
+
+impl From<String> for Inlines {
+    fn from(text: String) -> Inlines {
+        Inlines::from(text.as_str())
+    }
+}
+
+impl From<Vec<Inline>> for Inlines {
+    fn from(inlines: Vec<Inline>) -> Inlines {
+        Inlines(inlines)
+    }
+}
+
+fn no_attr() -> pandoc_ast::Attr {
+    (String::new(), Vec::new(), Vec::new())
+}
+
+fn text_inlines(text: &str) -> Vec<Inline> {
+    let mut inlines = Vec::new();
+    for word in text.split_whitespace() {
+        if !inlines.is_empty() {
+            inlines.push(Inline::Space);
+        }
+        inlines.push(Inline::Str(word.to_string()));
+    }
+    inlines
+}
+
+/// Plain text.
+pub fn text(text: &str) -> Vec<Inline> {
+    text_inlines(text)
+}
+
+/// Emphasized text.
+pub fn emph<T: Into<Inlines>>(content: T) -> Inline {
+    Inline::Emph(content.into().0)
+}
+
+/// Strongly emphasized text.
+pub fn strong<T: Into<Inlines>>(content: T) -> Inline {
+    Inline::Strong(content.into().0)
+}
+
+/// Inline code.
+pub fn code(code: &str) -> Inline {
+    Inline::Code(no_attr(), code.to_string())
+}
+
+/// A hyperlink to `url`.
+pub fn link<T: Into<Inlines>>(content: T, url: &str) -> Inline {
+    Inline::Link(
+        no_attr(),
+        content.into().0,
+        (url.to_string(), String::new()),
+    )
+}
+
+fn cell(content: Inlines) -> pandoc_ast::Cell {
+    (
+        no_attr(),
+        Alignment::AlignDefault,
+        1,
+        1,
+        vec![Block::Plain(content.0)],
+    )
+}
+
+fn row<T: Into<Inlines> + Clone>(cells: &[T]) -> Row {
+    (
+        no_attr(),
+        cells.iter().cloned().map(|c| cell(c.into())).collect(),
+    )
+}
+
+/// Assembles a pandoc document block by block.
+#[derive(Clone, Debug, Default)]
+pub struct DocumentBuilder {
+    meta: Map<String, MetaValue>,
+    blocks: Vec<Block>,
+}
+
+impl DocumentBuilder {
+    /// An empty document.
+    pub fn new() -> DocumentBuilder {
+        DocumentBuilder::default()
+    }
+
+    /// Set the metadata field `key`, e.g. `title` or `date`.
+    pub fn meta<T: Into<Inlines>>(&mut self, key: &str, value: T) -> &mut DocumentBuilder {
+        self.meta_value(key, MetaValue::MetaInlines(value.into().0))
+    }
+
+    /// Set the metadata field `key` to a structured value, e.g. a list of authors.
+    pub fn meta_value(&mut self, key: &str, value: MetaValue) -> &mut DocumentBuilder {
+        self.meta.insert(key.to_string(), value);
+        self
+    }
+
+    /// Append a heading, level 1 being the top level.
+    pub fn heading<T: Into<Inlines>>(&mut self, level: u32, content: T) -> &mut DocumentBuilder {
+        // pandoc generates an identifier if the heading has none
+        self.block(Block::Header(i64::from(level), no_attr(), content.into().0))
+    }
+
+    /// Append a paragraph.
+    pub fn paragraph<T: Into<Inlines>>(&mut self, content: T) -> &mut DocumentBuilder {
+        self.block(Block::Para(content.into().0))
+    }
+
+    /// Append a code block, highlighted as `language` if given.
+    pub fn code_block(&mut self, language: Option<&str>, code: &str) -> &mut DocumentBuilder {
+        let classes = language.into_iter().map(str::to_string).collect();
+        self.block(Block::CodeBlock(
+            (String::new(), classes, Vec::new()),
+            code.to_string(),
+        ))
+    }
+
+    /// Append a tight bullet list.
+    pub fn bullet_list<T: Into<Inlines>, I: IntoIterator<Item = T>>(
+        &mut self,
+        items: I,
+    ) -> &mut DocumentBuilder {
+        let items = items
+            .into_iter()
+            .map(|item| vec![Block::Plain(item.into().0)])
+            .collect();
+        self.block(Block::BulletList(items))
+    }
+
+    /// Append a table with a header row.
+    pub fn table<T: Into<Inlines> + Clone, R: AsRef<[T]>>(
+        &mut self,
+        header: &[T],
+        rows: &[R],
+    ) -> &mut DocumentBuilder {
+        let columns = header.len();
+        let head: TableHead = (no_attr(), vec![row(header)]);
+        let body = (
+            no_attr(),
+            0,
+            Vec::new(),
+            rows.iter().map(|cells| row(cells.as_ref())).collect(),
+        );
+        self.block(Block::Table(
+            no_attr(),
+            (None, Vec::new()),
+            vec![(Alignment::AlignDefault, ColWidth::ColWidthDefault); columns],
+            head,
+            vec![body],
+            (no_attr(), Vec::new()),
+        ))
+    }
+
+    /// Append any block of pandoc's AST.
+    pub fn block(&mut self, block: Block) -> &mut DocumentBuilder {
+        self.blocks.push(block);
+        self
+    }
+
+    /// The document, to be passed to
+    /// [`Pandoc::set_input_document`](../struct.Pandoc.html#method.set_input_document).
+    pub fn build(&self) -> Document {
+        Document {
+            meta: self.meta.clone(),
+            blocks: self.blocks.clone(),
+            pandoc_api_version: API_VERSION.to_vec(),
+        }
+    }
+}
@@ -0,0 +1,157 @@
+//! pluggable execution of the assembled pandoc command line
+
+use crate::PandocError;
+use std::ffi::OsStr;
+use std::io::{Read, Write};
+use std::process::{Command, Output};
+use std::time::{Duration, Instant};
+
+/// A fully assembled pandoc invocation, handed to a [`PandocExecutor`] to be run.
+///
+/// The command contains the program, the arguments, the environment and the stdio
+/// configuration. Stderr is always piped, stdout is piped if the output is captured.
+#[derive(Debug)]
+pub struct Invocation {
+    command: Command,
+    input: Option<Vec<u8>>,
+    timeout: Option<Duration>,
+}
+
+impl Invocation {
+    pub(crate) fn new(command: Command, input: Option<Vec<u8>>) -> Invocation {
+        Invocation {
+            command,
+            input,
+            timeout: None,
+        }
+    }
+
+    pub(crate) fn with_timeout(mut self, timeout: Option<Duration>) -> Invocation {
+        self.timeout = timeout;
+        self
+    }
+
+    /// The command that would be spawned.
+    pub fn command(&self) -> &Command {
+        &self.command
+    }
+
+    /// Mutable access to the command, e.g. to adjust the environment.
+    pub fn command_mut(&mut self) -> &mut Command {
+        &mut self.command
+    }
+
+    /// The program to run, usually `pandoc`.
+    pub fn program(&self) -> &OsStr {
+        self.command.get_program()
+    }
+
+    /// The command-line arguments, without the program.
+    pub fn args(&self) -> impl Iterator<Item = &OsStr> {
+        self.command.get_args()
+    }
+
+    /// The data to write to pandoc's stdin, if the input is piped.
+    pub fn input(&self) -> Option<&[u8]> {
+        self.input.as_deref()
+    }
+
+    /// How long pandoc may run before it is killed, see
+    /// [`Pandoc::set_timeout`](struct.Pandoc.html#method.set_timeout).
+    pub fn timeout(&self) -> Option<Duration> {
+        self.timeout
+    }
+
+    /// Split the invocation into the command and the data for stdin.
+    pub fn into_parts(self) -> (Command, Option<Vec<u8>>) {
+        (self.command, self.input)
+    }
+}
+
+/// A backend that runs pandoc invocations.
+///
+/// [`Pandoc::execute`](struct.Pandoc.html#method.execute) uses [`ProcessExecutor`], which
+/// spawns a local process. Other implementations can e.g. run pandoc in a container or return
+/// canned output in tests, see [`Pandoc::execute_with`](struct.Pandoc.html#method.execute_with).
+///
+/// A non-successful exit status is not an error of the executor, the caller inspects the
+/// returned `Output`.
+pub trait PandocExecutor {
+    /// Run the invocation to completion and collect its output.
+    fn execute(&self, invocation: Invocation) -> Result<Output, PandocError>;
+
+    /// Whether the answers to queries like `--list-extensions` may be cached across
+    /// conversions, which is only true if they come from the installed pandoc.
+    fn caches_queries(&self) -> bool {
+        false
+    }
+}
+
+/// The default executor, spawning pandoc as a child process.
+#[derive(Copy, Clone, Debug, Default)]
+pub struct ProcessExecutor;
+
+impl PandocExecutor for ProcessExecutor {
+    fn caches_queries(&self) -> bool {
+        true
+    }
+
+    fn execute(&self, invocation: Invocation) -> Result<Output, PandocError> {
+        let timeout = invocation.timeout();
+        let (mut command, input) = invocation.into_parts();
+        let mut child = command.spawn()?;
+        let timeout = match timeout {
+            Some(timeout) => timeout,
+            None => {
+                if let (Some(ref mut stdin), Some(input)) = (child.stdin.take(), input) {
+                    stdin.write_all(&input)?;
+                }
+                return Ok(child.wait_with_output()?);
+            }
+        };
+
+        // all pipes are serviced on their own threads, so a stalled pandoc can't block the
+        // check of the deadline
+        let writer = match (child.stdin.take(), input) {
+            (Some(mut stdin), Some(input)) => {
+                Some(std::thread::spawn(move || stdin.write_all(&input)))
+            }
+            _ => None,
+        };
+        let reader = |pipe: Option<Box<dyn Read + Send>>| {
+            pipe.map(|mut pipe| {
+                std::thread::spawn(move || {
+                    let mut buf = Vec::new();
+                    pipe.read_to_end(&mut buf).map(|_| buf)
+                })
+            })
+        };
+        let stdout = reader(child.stdout.take().map(|p| Box::new(p) as _));
+        let stderr = reader(child.stderr.take().map(|p| Box::new(p) as _));
+        let deadline = Instant::now() + timeout;
+        let status = loop {
+            if let Some(status) = child.try_wait()? {
+                break status;
+            }
+            if Instant::now() >= deadline {
+                let _ = child.kill();
+                let _ = child.wait();
+                return Err(PandocError::Timeout(timeout));
+            }
+            std::thread::sleep(Duration::from_millis(10));
+        };
+        let join = |thread: Option<std::thread::JoinHandle<std::io::Result<Vec<u8>>>>| {
+            thread.map_or(Ok(Vec::new()), |thread| {
+                thread.join().expect("reading a pipe does not panic")
+            })
+        };
+        if let Some(writer) = writer {
+            writer.join().expect("writing to a pipe does not panic")?;
+        }
+        Ok(Output {
+            status,
+            stdout: join(stdout)?,
+            stderr: join(stderr)?,
+        })
+    }
+}
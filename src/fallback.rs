@@ -0,0 +1,152 @@
+//! degraded markdown to HTML conversion for machines without pandoc
+
+use crate::{InputFormat, InputKind, InputSource, OutputFormat, OutputKind};
+use crate::{Pandoc, PandocError, PandocOption, PandocOutput, TempPath};
+use std::io::Write;
+use std::path::Path;
+
+/// The program that performed a conversion, see
+/// [`Pandoc::execute_or_fallback`](struct.Pandoc.html#method.execute_or_fallback).
+#[derive(PartialEq, Eq, Copy, Clone, Debug)]
+#[non_exhaustive]
+pub enum Converter {
+    /// pandoc ran as configured
+    Pandoc,
+    /// pandoc was not found, the built-in markdown renderer produced the output
+    Fallback,
+}
+
+fn is_markdown(format: &InputFormat) -> bool {
+    match *format {
+        InputFormat::Markdown
+        | InputFormat::MarkdownStrict
+        | InputFormat::MarkdownPhpextra
+        | InputFormat::Gfm
+        | InputFormat::Commonmark
+        | InputFormat::CommonmarkX => true,
+        #[allow(deprecated)]
+        InputFormat::MarkdownGithub => true,
+        _ => false,
+    }
+}
+
+fn unsupported(what: impl std::fmt::Debug) -> PandocError {
+    PandocError::Unsupported(format!(
+        "{:?} is not supported by the fallback renderer",
+        what
+    ))
+}
+
+fn escape(text: &str) -> String {
+    text.replace('&', "&amp;")
+        .replace('<', "&lt;")
+        .replace('>', "&gt;")
+        .replace('"', "&quot;")
+}
+
+/// Render the markdown input of `pandoc` to HTML, ignoring all options but `Standalone` and
+/// the `title` metadata.
+pub(crate) fn convert(mut pandoc: Pandoc) -> Result<PandocOutput, PandocError> {
+    if !pandoc.filters.is_empty() {
+        return Err(unsupported("filtering"));
+    }
+    pandoc.infer_input_format = true;
+    pandoc.infer_input_format()?;
+    pandoc.apply_input_preprocessors()?;
+    match pandoc.input_format {
+        None => {}
+        Some((ref format, _)) if is_markdown(format) => {}
+        Some((ref format, _)) => return Err(unsupported(format)),
+    }
+    let output = pandoc.output.take().ok_or(PandocError::NoOutputSpecified)?;
+    let format = match (pandoc.output_format.take(), &output) {
+        (Some((format, _)), _) => Some(format),
+        (None, &OutputKind::File(ref file)) | (None, &OutputKind::Tee(ref file)) => file
+            .extension()
+            .and_then(|extension| extension.to_str())
+            .and_then(OutputFormat::from_extension),
+        (None, _) => None,
+    };
+    match format {
+        None | Some(OutputFormat::Html) | Some(OutputFormat::Html5) => {}
+        Some(format) => return Err(unsupported(format)),
+    }
+
+    let markdown = match pandoc.input.take() {
+        Some(InputKind::Pipe(text)) => text,
+        Some(InputKind::Files(files)) => read_files(files.iter().map(|file| file.as_path()))?,
+        Some(InputKind::Sources(sources)) => {
+            let mut files = Vec::new();
+            for source in &sources {
+                match *source {
+                    InputSource::File(ref file) => files.push(file.as_path()),
+                    ref other => return Err(unsupported(other)),
+                }
+            }
+            read_files(files.into_iter())?
+        }
+        Some(other) => return Err(unsupported(other)),
+        None => return Err(PandocError::NoInputSpecified),
+    };
+
+    let mut options = pulldown_cmark::Options::empty();
+    options.insert(pulldown_cmark::Options::ENABLE_TABLES);
+    options.insert(pulldown_cmark::Options::ENABLE_FOOTNOTES);
+    options.insert(pulldown_cmark::Options::ENABLE_STRIKETHROUGH);
+    options.insert(pulldown_cmark::Options::ENABLE_TASKLISTS);
+    let mut html = String::new();
+    pulldown_cmark::html::push_html(
+        &mut html,
+        pulldown_cmark::Parser::new_ext(&markdown, options),
+    );
+
+    let standalone = pandoc.options.contains(&PandocOption::Standalone);
+    if standalone {
+        let title = pandoc.options.iter().find_map(|option| match *option {
+            PandocOption::Meta(ref key, Some(ref value)) if key == "title" => Some(value.as_str()),
+            _ => None,
+        });
+        html = format!(
+            "<!DOCTYPE html>\n<html>\n<head>\n<meta charset=\"utf-8\" />\n<title>{}</title>\n\
+             </head>\n<body>\n{}</body>\n</html>\n",
+            escape(title.unwrap_or_default()),
+            html
+        );
+    }
+
+    match output {
+        OutputKind::File(file) => {
+            std::fs::write(&file, html).map_err(PandocError::IoErr)?;
+            Ok(PandocOutput::ToFile(file))
+        }
+        OutputKind::TempFile(suffix) => {
+            let temp = TempPath::new(&suffix).map_err(PandocError::IoErr)?;
+            std::fs::write(&temp, html).map_err(PandocError::IoErr)?;
+            Ok(PandocOutput::ToTempFile(temp))
+        }
+        OutputKind::Tee(file) => {
+            std::fs::write(file, &html).map_err(PandocError::IoErr)?;
+            Ok(PandocOutput::ToBuffer(html))
+        }
+        OutputKind::Pipe => Ok(PandocOutput::ToBuffer(html)),
+        OutputKind::InheritStdout => {
+            std::io::stdout()
+                .write_all(html.as_bytes())
+                .map_err(PandocError::IoErr)?;
+            Ok(PandocOutput::ToStdout)
+        }
+        other => Err(unsupported(other)),
+    }
+}
+
+/// concatenate the input files like pandoc does
+fn read_files<'a>(files: impl Iterator<Item = &'a Path>) -> Result<String, PandocError> {
+    let mut text = String::new();
+    for file in files {
+        if !text.is_empty() {
+            text.push_str("\n\n");
+        }
+        text.push_str(&std::fs::read_to_string(file).map_err(PandocError::IoErr)?);
+    }
+    Ok(text)
+}
@@ -0,0 +1,45 @@
+//! fonts of LaTeX and ConTeXt output
+
+/// The fonts of PDF output, see [`Pandoc::set_fonts`](struct.Pandoc.html#method.set_fonts).
+///
+/// Fonts are given by their system name, e.g. `Noto Serif`, and need a Unicode engine.
+/// Unset fields keep the defaults of the template.
+#[derive(PartialEq, Eq, Clone, Debug, Default)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[cfg_attr(feature = "serde", serde(default))]
+pub struct Fonts {
+    /// the font of the body text
+    pub main: Option<String>,
+    /// the sans serif font, used by some classes for headings
+    pub sans: Option<String>,
+    /// the font of code
+    pub mono: Option<String>,
+    /// the font of formulas, which needs to be an OpenType math font
+    pub math: Option<String>,
+    /// the font of Chinese, Japanese and Korean text
+    pub cjk_main: Option<String>,
+    /// the size of the body text, e.g. `11pt`, which works with any engine
+    pub size: Option<String>,
+}
+
+impl Fonts {
+    /// the template variables of the fonts
+    pub(crate) fn variables(&self) -> Vec<(&'static str, &str)> {
+        [
+            ("mainfont", &self.main),
+            ("sansfont", &self.sans),
+            ("monofont", &self.mono),
+            ("mathfont", &self.math),
+            ("CJKmainfont", &self.cjk_main),
+            ("fontsize", &self.size),
+        ]
+        .iter()
+        .filter_map(|&(key, value)| Some((key, value.as_deref()?)))
+        .collect()
+    }
+
+    /// whether fonts other than the template's defaults are selected
+    pub(crate) fn needs_unicode_engine(&self) -> bool {
+        self.variables().iter().any(|&(key, _)| key != "fontsize")
+    }
+}
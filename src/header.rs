@@ -0,0 +1,102 @@
+//! snippets for the header of standalone documents
+
+use crate::OutputFormat;
+
+/// The markup of [`HeaderIncludes`] snippets.
+#[derive(PartialEq, Eq, Copy, Clone, Debug)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[non_exhaustive]
+pub enum HeaderFormat {
+    /// preamble commands of the LaTeX and beamer writers
+    Latex,
+    /// elements of the `<head>` of the HTML, slide show and EPUB writers
+    Html,
+}
+
+/// Raw snippets for the header of standalone output, see
+/// [`Pandoc::add_header_includes`](struct.Pandoc.html#method.add_header_includes).
+///
+/// The snippets end up in the header in the order they were added.
+#[derive(PartialEq, Eq, Clone, Debug)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct HeaderIncludes {
+    format: HeaderFormat,
+    snippets: Vec<String>,
+}
+
+impl HeaderIncludes {
+    /// Snippets for the LaTeX preamble.
+    pub fn latex() -> HeaderIncludes {
+        HeaderIncludes::new(HeaderFormat::Latex)
+    }
+
+    /// Fragments of the HTML `<head>`.
+    pub fn html() -> HeaderIncludes {
+        HeaderIncludes::new(HeaderFormat::Html)
+    }
+
+    fn new(format: HeaderFormat) -> HeaderIncludes {
+        HeaderIncludes {
+            format,
+            snippets: Vec::new(),
+        }
+    }
+
+    /// Whether the snippets are LaTeX or HTML.
+    pub fn format(&self) -> HeaderFormat {
+        self.format
+    }
+
+    /// Add a snippet, passed to pandoc as is.
+    pub fn raw<T: AsRef<str> + ?Sized>(&mut self, snippet: &T) -> &mut HeaderIncludes {
+        self.snippets.push(snippet.as_ref().to_owned());
+        self
+    }
+
+    /// Load the LaTeX package `name`, e.g. `\usepackage[margin=2cm]{geometry}`.
+    pub fn use_package(&mut self, name: &str, options: &[&str]) -> &mut HeaderIncludes {
+        let snippet = if options.is_empty() {
+            format!("\\usepackage{{{}}}", name)
+        } else {
+            format!("\\usepackage[{}]{{{}}}", options.join(","), name)
+        };
+        self.raw(&snippet)
+    }
+
+    /// Add an HTML `<style>` element with the rules `css`.
+    pub fn style(&mut self, css: &str) -> &mut HeaderIncludes {
+        self.raw(&format!("<style>\n{}\n</style>", css))
+    }
+
+    /// Whether no snippets were added.
+    pub fn is_empty(&self) -> bool {
+        self.snippets.is_empty()
+    }
+
+    /// the contents of the generated header file
+    pub(crate) fn contents(&self) -> String {
+        let mut contents = self.snippets.join("\n");
+        contents.push('\n');
+        contents
+    }
+
+    pub(crate) fn file_suffix(&self) -> &'static str {
+        match self.format {
+            HeaderFormat::Latex => ".tex",
+            HeaderFormat::Html => ".html",
+        }
+    }
+
+    /// whether the header of `format` is written in the other markup
+    pub(crate) fn conflicts_with(&self, format: &OutputFormat) -> bool {
+        use crate::OutputFormat::*;
+        let format = match *format {
+            Latex | Beamer => HeaderFormat::Latex,
+            Html | Html5 | ChunkedHtml | Slidy | Slideous | Dzslides | Revealjs | S5 | Epub
+            | Epub3 => HeaderFormat::Html,
+            // PDF can be created with LaTeX or an HTML engine
+            _ => return false,
+        };
+        format != self.format
+    }
+}
@@ -0,0 +1,64 @@
+//! settings of the HTML writers
+
+use std::path::PathBuf;
+
+/// The settings of HTML output, see
+/// [`Pandoc::set_html_config`](struct.Pandoc.html#method.set_html_config).
+///
+/// Unset fields keep the defaults of the template.
+#[derive(PartialEq, Eq, Clone, Debug, Default)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[cfg_attr(feature = "serde", serde(default))]
+pub struct HtmlConfig {
+    /// the URLs of the stylesheets, linked in this order
+    pub stylesheets: Vec<String>,
+    /// the heading of the table of contents
+    pub toc_title: Option<String>,
+    /// the language of the document, e.g. `en-GB`, set as `lang` attribute of the `<html>`
+    /// element
+    pub lang: Option<String>,
+    /// Whether to include the template's default styles. They are included unless
+    /// stylesheets are given.
+    pub document_css: Option<bool>,
+    /// `<meta>` elements as pairs of `name` and `content`, e.g. `("robots", "noindex")`.
+    /// pandoc's template already sets the `viewport`.
+    pub meta_tags: Vec<(String, String)>,
+    /// raw fragments of the `<head>`, e.g. `<script>` elements
+    pub head: Vec<String>,
+    /// files included at the start of the `<body>`, e.g. a page header
+    pub before_body: Vec<PathBuf>,
+    /// files included at the end of the `<body>`, e.g. a page footer
+    pub after_body: Vec<PathBuf>,
+}
+
+/// escape `text` for an HTML attribute value
+fn escape(text: &str) -> String {
+    let mut escaped = String::with_capacity(text.len());
+    for c in text.chars() {
+        match c {
+            '&' => escaped.push_str("&amp;"),
+            '"' => escaped.push_str("&quot;"),
+            '<' => escaped.push_str("&lt;"),
+            '>' => escaped.push_str("&gt;"),
+            c => escaped.push(c),
+        }
+    }
+    escaped
+}
+
+impl HtmlConfig {
+    /// the snippets of the `<head>`, the meta tags first
+    pub(crate) fn head_snippets(&self) -> Vec<String> {
+        self.meta_tags
+            .iter()
+            .map(|(name, content)| {
+                format!(
+                    "<meta name=\"{}\" content=\"{}\" />",
+                    escape(name),
+                    escape(content)
+                )
+            })
+            .chain(self.head.iter().cloned())
+            .collect()
+    }
+}
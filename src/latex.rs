@@ -0,0 +1,67 @@
+//! runtime discovery of TeX distributions
+
+use std::env;
+use std::path::{Path, PathBuf};
+
+/// the `bin/<arch>` directories of all TeX Live releases in `root`, newest release first
+fn texlive_dirs(root: &Path) -> Vec<PathBuf> {
+    let mut years: Vec<(u32, PathBuf)> = match std::fs::read_dir(root) {
+        Ok(entries) => entries
+            .flatten()
+            .filter_map(|entry| {
+                let year = entry.file_name().to_str()?.parse().ok()?;
+                Some((year, entry.path()))
+            })
+            .collect(),
+        Err(_) => return Vec::new(),
+    };
+    years.sort_by_key(|(year, _)| std::cmp::Reverse(*year));
+    let mut dirs = Vec::new();
+    for (_, release) in years {
+        if let Ok(arches) = std::fs::read_dir(release.join("bin")) {
+            let mut arches: Vec<PathBuf> = arches
+                .flatten()
+                .map(|entry| entry.path())
+                .filter(|path| path.is_dir())
+                .collect();
+            arches.sort();
+            dirs.extend(arches);
+        }
+    }
+    dirs
+}
+
+/// the directories TeX distributions and tectonic are installed to, if they exist
+pub(crate) fn tex_dirs() -> Vec<PathBuf> {
+    let mut dirs = Vec::new();
+    let home = env::var_os(if cfg!(windows) { "USERPROFILE" } else { "HOME" }).map(PathBuf::from);
+    if cfg!(windows) {
+        // system-wide and per-user MiKTeX installations, of current and 2.9 releases
+        let bases = [
+            env::var_os("ProgramFiles").map(PathBuf::from),
+            env::var_os("ProgramFiles(x86)").map(PathBuf::from),
+            env::var_os("LOCALAPPDATA").map(|dir| Path::new(&dir).join("Programs")),
+        ];
+        for base in bases.iter().flatten() {
+            for miktex in &["MiKTeX", "MiKTeX 2.9"] {
+                let bin = base.join(miktex).join("miktex").join("bin");
+                dirs.push(bin.join("x64"));
+                dirs.push(bin);
+            }
+        }
+        dirs.extend(texlive_dirs(Path::new(r"C:\texlive")));
+    } else {
+        dirs.extend(texlive_dirs(Path::new("/usr/local/texlive")));
+        dirs.extend(texlive_dirs(Path::new("/opt/texlive")));
+        if let Some(ref home) = home {
+            dirs.extend(texlive_dirs(&home.join("texlive")));
+        }
+    }
+    // tectonic is commonly installed with `cargo install`
+    match env::var_os("CARGO_HOME") {
+        Some(cargo_home) => dirs.push(Path::new(&cargo_home).join("bin")),
+        None => dirs.extend(home.map(|home| home.join(".cargo").join("bin"))),
+    }
+    dirs.retain(|dir| dir.is_dir());
+    dirs
+}
@@ -1,10 +1,7 @@
 //! API that wraps the pandoc command line tool
 
-use itertools::Itertools;
-
-use std::io::Write;
+use std::io::{Read, Write};
 use std::path::{Path, PathBuf};
-use std::rc::Rc;
 use std::str;
 
 /// path to pandoc executable
@@ -14,31 +11,116 @@ const PANDOC_PATH: &[&str] = &[
     concat!(env!("LOCALAPPDATA"), r#"\Pandoc\"#),
 ];
 /// path to pandoc executable
-#[cfg(not(windows))]
+#[cfg(target_os = "macos")]
+const PANDOC_PATH: &[&str] = &[
+    // Homebrew on Apple silicon and Intel
+    "/opt/homebrew/bin",
+    "/usr/local/opt/pandoc/bin",
+    // MacPorts
+    "/opt/local/bin",
+];
+/// path to pandoc executable
+#[cfg(not(any(windows, target_os = "macos")))]
 const PANDOC_PATH: &[&str] = &[];
 
-/// path where miktex executables can be found
+/// TeX distributions are found at runtime on windows, see `latex::tex_dirs`
 #[cfg(windows)]
+const LATEX_PATH: &[&str] = &[];
+/// path where MacTeX, Homebrew and MacPorts put the TeX executables
+#[cfg(target_os = "macos")]
 const LATEX_PATH: &[&str] = &[
-    r#"C:\Program Files (x86)\MiKTeX 2.9\miktex\bin"#,
-    r#"C:\Program Files\MiKTeX 2.9\miktex\bin"#,
+    "/Library/TeX/texbin",
+    "/opt/homebrew/bin",
+    "/usr/local/bin",
+    "/opt/local/bin",
 ];
-/// path where miktex executables can be found
-#[cfg(not(windows))]
-const LATEX_PATH: &[&str] = &[r"/usr/local/bin", r"/usr/local/texlive/2015/bin/i386-linux"];
-
-/// character to split path variable on windows
-#[cfg(windows)]
-const PATH_DELIMIT: &str = ";";
-
-/// character to split path variable on 'other platforms'
-#[cfg(not(windows))]
-const PATH_DELIMIT: &str = ":";
+/// path where TeX executables are commonly installed
+#[cfg(not(any(windows, target_os = "macos")))]
+const LATEX_PATH: &[&str] = &[r"/usr/local/bin"];
 
+use std::collections::HashMap;
 use std::env;
+use std::ffi::{OsStr, OsString};
 use std::process::Command;
+use std::sync::{Arc, Mutex, OnceLock};
+
+pub mod bib;
+pub mod book;
+pub mod build_support;
+mod cache;
+mod citations;
+mod convert;
+mod crossref;
+mod diagnostics;
+#[cfg(feature = "ast")]
+pub mod diff;
+#[cfg(feature = "ast")]
+pub mod doc;
+mod executor;
+#[cfg(feature = "fallback")]
+mod fallback;
+mod fonts;
+mod header;
+mod html;
+mod latex;
+#[cfg(feature = "ast")]
+mod links;
+#[cfg(feature = "mdbook")]
+pub mod mdbook;
+mod metadata;
+#[cfg(feature = "test-util")]
+pub mod mock;
+#[cfg(feature = "ast")]
+mod outline;
+mod pdf;
+mod pool;
+mod prepared;
+pub mod presets;
+#[cfg(feature = "server")]
+pub mod server;
+#[cfg(feature = "ast")]
+mod split;
+#[cfg(feature = "ast")]
+mod stats;
+mod temp;
+pub mod templates;
+#[cfg(feature = "test-util")]
+pub mod testing;
+mod tree;
+#[cfg(feature = "watch")]
+pub mod watch;
+
+pub use crate::citations::CitationConfig;
+#[cfg(feature = "ast")]
+pub use crate::citations::{cited_keys, CitationReport};
+pub use crate::convert::{
+    convert_file, html_to_markdown, markdown_to_docx_bytes, markdown_to_html,
+};
+pub use crate::crossref::CrossrefConfig;
+pub use crate::diagnostics::{parse_warnings, Warning, WarningClass};
+pub use crate::executor::{Invocation, PandocExecutor, ProcessExecutor};
+#[cfg(feature = "fallback")]
+pub use crate::fallback::Converter;
+pub use crate::fonts::Fonts;
+pub use crate::header::{HeaderFormat, HeaderIncludes};
+pub use crate::html::HtmlConfig;
+#[cfg(feature = "ast")]
+pub use crate::links::{link_inventory, DocumentLink, LinkKind, SourcePosition};
+pub use crate::metadata::Author;
+#[cfg(feature = "ast")]
+pub use crate::outline::{heading_tree, Heading};
+pub use crate::pdf::PdfError;
+pub use crate::pool::{ConversionPool, JobHandle};
+pub use crate::prepared::PreparedPandoc;
+#[cfg(feature = "ast")]
+pub use crate::stats::{document_stats, DocumentStats};
+pub use crate::temp::{TempDir, TempPath};
+pub use crate::tree::{convert_tree, TreeOptions};
+#[cfg(feature = "ast")]
+pub use pandoc_ast;
 
-#[derive(PartialEq, Copy, Clone, Debug)]
+#[derive(PartialEq, Eq, Hash, Copy, Clone, Debug)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub enum TrackChanges {
     Accept,
     Reject,
@@ -55,7 +137,8 @@ impl std::fmt::Display for TrackChanges {
     }
 }
 
-#[derive(PartialEq, Copy, Clone, Debug)]
+#[derive(PartialEq, Eq, Hash, Copy, Clone, Debug)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub enum EmailObfuscation {
     None,
     Javascript,
@@ -72,16 +155,142 @@ impl std::fmt::Display for EmailObfuscation {
     }
 }
 
+/// how pandoc treats the output cells when reading Jupyter notebooks
+#[derive(PartialEq, Eq, Hash, Copy, Clone, Debug)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum IpynbOutput {
+    /// keep all data formats of every output cell
+    All,
+    /// drop the output cells
+    None,
+    /// keep the richest data format the output format can represent
+    Best,
+}
+
+impl std::fmt::Display for IpynbOutput {
+    fn fmt(&self, fmt: &mut std::fmt::Formatter) -> std::fmt::Result {
+        match *self {
+            IpynbOutput::All => write!(fmt, "all"),
+            IpynbOutput::None => write!(fmt, "none"),
+            IpynbOutput::Best => write!(fmt, "best"),
+        }
+    }
+}
+
+/// The table of contents, see [`Pandoc::set_toc_config`](struct.Pandoc.html#method.set_toc_config)
+#[derive(PartialEq, Eq, Clone, Debug, Default)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[cfg_attr(feature = "serde", serde(default))]
+pub struct TocConfig {
+    /// the deepest heading level listed, pandoc defaults to 3
+    pub depth: Option<u32>,
+    /// the heading of the table of contents, the default depends on the format and language
+    pub title: Option<String>,
+    /// number the sections, which also numbers the entries of the table of contents
+    pub numbered: bool,
+}
+
+/// Paper sizes, see [`PageLayout`]
+#[derive(PartialEq, Eq, Clone, Debug)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[non_exhaustive]
+pub enum PaperSize {
+    /// ISO A4, 210 × 297 mm
+    A4,
+    /// ISO A5, 148 × 210 mm
+    A5,
+    /// ISO B5, 176 × 250 mm
+    B5,
+    /// US letter, 8.5 × 11 in
+    Letter,
+    /// US legal, 8.5 × 14 in
+    Legal,
+    /// US executive, 7.25 × 10.5 in
+    Executive,
+    /// a paper size name passed on unchanged
+    Other(String),
+}
+
+impl PaperSize {
+    /// the name of the paper size in ConTeXt, which differs in case from the LaTeX names
+    fn context_name(&self) -> String {
+        match *self {
+            PaperSize::A4 => "A4".to_string(),
+            PaperSize::A5 => "A5".to_string(),
+            PaperSize::B5 => "B5".to_string(),
+            ref other => other.to_string(),
+        }
+    }
+}
+
+impl std::fmt::Display for PaperSize {
+    fn fmt(&self, fmt: &mut std::fmt::Formatter) -> std::fmt::Result {
+        match *self {
+            PaperSize::A4 => write!(fmt, "a4"),
+            PaperSize::A5 => write!(fmt, "a5"),
+            PaperSize::B5 => write!(fmt, "b5"),
+            PaperSize::Letter => write!(fmt, "letter"),
+            PaperSize::Legal => write!(fmt, "legal"),
+            PaperSize::Executive => write!(fmt, "executive"),
+            PaperSize::Other(ref name) => write!(fmt, "{}", name),
+        }
+    }
+}
+
+/// Page margins as TeX lengths like `2.5cm` or `1in`, see [`PageLayout`]
+#[derive(PartialEq, Eq, Clone, Debug)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct Margins {
+    /// the margin above the text
+    pub top: String,
+    /// the margin right of the text
+    pub right: String,
+    /// the margin below the text
+    pub bottom: String,
+    /// the margin left of the text
+    pub left: String,
+}
+
+impl Margins {
+    /// the same margin on all sides
+    pub fn uniform<T: AsRef<str> + ?Sized>(margin: &T) -> Margins {
+        let margin = margin.as_ref().to_owned();
+        Margins {
+            top: margin.clone(),
+            right: margin.clone(),
+            bottom: margin.clone(),
+            left: margin,
+        }
+    }
+}
+
+/// The page layout of PDF output, see [`Pandoc::set_page_layout`](struct.Pandoc.html#method.set_page_layout)
+#[derive(PartialEq, Eq, Clone, Debug, Default)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[cfg_attr(feature = "serde", serde(default))]
+pub struct PageLayout {
+    /// the paper size, pandoc's templates default to letter or A4 depending on the engine
+    pub paper_size: Option<PaperSize>,
+    /// the page margins, the defaults of the document class are used otherwise
+    pub margins: Option<Margins>,
+    /// rotate the pages
+    pub landscape: bool,
+    /// set the text in two columns, only supported by LaTeX
+    pub two_column: bool,
+}
+
 pub type URL = String;
 
-#[derive(PartialEq, Clone, Debug)]
+#[derive(PartialEq, Eq, Hash, Clone, Debug)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub enum Tld {
     Chapter,
     Section,
     Part,
 }
 
-#[derive(PartialEq, Clone, Debug)]
+#[derive(PartialEq, Eq, Hash, Clone, Debug)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 #[non_exhaustive]
 pub enum PandocOption {
     /// --data-dir=DIRECTORY
@@ -149,6 +358,10 @@ pub enum PandocOption {
     IncludeAfterBody(PathBuf),
     /// --self-contained
     SelfContained,
+    /// --embed-resources, supersedes `SelfContained` as of pandoc 2.19
+    EmbedResources,
+    /// --file-scope
+    FileScope,
     /// --offline
     Offline,
     /// -5 --html5
@@ -194,6 +407,9 @@ pub enum PandocOption {
     #[deprecated(note = "replaced by ReferenceDoc")]
     ReferenceDocx(PathBuf),
     /// --reference-doc=FILENAME
+    ///
+    /// A `.docx`, `.odt` or `.pptx` file whose styles (and for pptx, slide layouts) are used
+    /// for output of the matching format.
     ReferenceDoc(PathBuf),
     /// --epub-stylesheet=FILENAME
     EpubStylesheet(PathBuf),
@@ -217,6 +433,8 @@ pub enum PandocOption {
     Csl(PathBuf),
     /// --citation-abbreviations=FILE
     CitationAbbreviations(PathBuf),
+    /// --metadata-file=FILE
+    MetadataFile(PathBuf),
     /// --natbib
     Natbib,
     /// --biblatex
@@ -280,16 +498,92 @@ pub enum PandocOption {
     /// Manually specify line endings: crlf (Windows), lf (macOS/Linux/UNIX), or native
     /// (line endings appropriate to the OS on which pandoc is being run). The default is native.
     EOL(String),
+    /// --ipynb-output=all|none|best
+    ///
+    /// Only has an effect when reading `InputFormat::Ipynb`. Pandoc's default is `best`.
+    IpynbOutput(IpynbOutput),
 }
 
-#[derive(PartialEq, Clone, Debug)]
+#[derive(PartialEq, Eq, Hash, Clone, Debug)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 #[non_exhaustive]
 pub enum PandocRuntimeSystemOption {
     /// -M<size>
     MaximumHeapMemory(String),
+    /// -K<size>
+    ///
+    /// Limits the stack, pandoc's default is 80% of the heap limit.
+    MaximumStackSize(String),
+    /// -A<size>
+    ///
+    /// The size of the allocation area used by the garbage collector.
+    AllocationAreaSize(String),
+    /// -I<seconds>
+    ///
+    /// Run a garbage collection after the program was idle for this long, `"0"` disables the
+    /// idle collection.
+    IdleGcDelay(String),
+    /// Any other runtime system option, passed unchanged, e.g. `"-c"`
+    Other(String),
 }
 
 impl PandocOption {
+    /// the local file this option makes pandoc read, if any
+    ///
+    /// Bare file names of options that pandoc also looks up in its data directory are not
+    /// reported, neither are URLs.
+    fn referenced_file(&self) -> Option<&Path> {
+        use crate::PandocOption::*;
+        let (path, in_data_dir) = match *self {
+            Defaults(ref p) | Template(ref p) | Csl(ref p) => (p, true),
+            IncludeInHeader(ref p)
+            | IncludeBeforeBody(ref p)
+            | IncludeAfterBody(ref p)
+            | Bibliography(ref p)
+            | CitationAbbreviations(ref p)
+            | MetadataFile(ref p)
+            | ReferenceOdt(ref p)
+            | ReferenceDoc(ref p)
+            | EpubStylesheet(ref p)
+            | EpubCoverImage(ref p)
+            | EpubMetadata(ref p) => (p, false),
+            #[allow(deprecated)]
+            ReferenceDocx(ref p) => (p, false),
+            _ => return None,
+        };
+        if in_data_dir && path.components().count() == 1 {
+            return None;
+        }
+        if path.to_string_lossy().contains("://") {
+            return None;
+        }
+        Some(path)
+    }
+
+    /// whether the option changes how the input is read, so it only belongs to the pass
+    /// reading the input when the conversion is split into a reader and a writer pass
+    fn affects_reader(&self) -> bool {
+        use crate::PandocOption::*;
+        match *self {
+            Strict
+            | ParseRaw
+            | Smart
+            | OldDashes
+            | ShiftHeadingLevelBy(_)
+            | IndentedCodeClasses(_)
+            | PreserveTabs
+            | TabStop(_)
+            | TrackChanges(_)
+            | ExtractMedia(_)
+            | FileScope
+            | DefaultImageExtension(_)
+            | MetadataFile(_) => true,
+            #[allow(deprecated)]
+            BaseHeaderLevel(_) => true,
+            _ => false,
+        }
+    }
+
     fn apply<'a>(&self, pandoc: &'a mut Command) -> &'a mut Command {
         use crate::PandocOption::*;
         use crate::Tld::*;
@@ -302,68 +596,62 @@ impl PandocOption {
                         format!("{}, {}", b, n)
                     }
                 });
-                pandoc.args(&[&format!("--number-offset={}", nums)])
+                pandoc.args([&format!("--number-offset={}", nums)])
             }
-            DataDir(ref dir) => pandoc.args(&[&format!("--data-dir={}", dir.display())]),
-            Defaults(ref p) => pandoc.args(&[&format!("--defaults={}", p.display())]),
-            Strict => pandoc.args(&["--strict"]),
-            ParseRaw => pandoc.args(&["--parse-raw"]),
-            Smart => pandoc.args(&["--smart"]),
-            OldDashes => pandoc.args(&["--old-dashes"]),
+            DataDir(ref dir) => pandoc.arg(file_arg("--data-dir=", dir)),
+            Defaults(ref p) => pandoc.arg(file_arg("--defaults=", p)),
+            Strict => pandoc.args(["--strict"]),
+            ParseRaw => pandoc.args(["--parse-raw"]),
+            Smart => pandoc.args(["--smart"]),
+            OldDashes => pandoc.args(["--old-dashes"]),
             #[allow(deprecated)]
-            BaseHeaderLevel(n) => pandoc.args(&[&format!("--base-header-level={}", n)]),
-            ShiftHeadingLevelBy(n) => pandoc.args(&[&format!("--shift-heading-level-by={}", n)]),
-            IndentedCodeClasses(ref s) => pandoc.args(&[&format!("--indented-code-classes={}", s)]),
-            Filter(ref program) => pandoc.args(&[&format!("--filter={}", program.display())]),
-            LuaFilter(ref script) => pandoc.args(&[&format!("--lua-filter={}", script.display())]),
-            Normalize => pandoc.args(&["--normalize"]),
-            PreserveTabs => pandoc.args(&["--preserve-tabs"]),
-            TabStop(n) => pandoc.args(&[&format!("--tab-stop={}", n)]),
-            TrackChanges(ref v) => pandoc.args(&[&format!("--track-changes={}", v)]),
-            ExtractMedia(ref p) => pandoc.args(&[&format!("--extract-media={}", p.display())]),
-            Standalone => pandoc.args(&["--standalone"]),
-            Template(ref p) => pandoc.args(&[&format!("--template={}", p.display())]),
-            Meta(ref k, Some(ref v)) => pandoc.args(&["-M", &format!("{}:{}", k, v)]),
-            Meta(ref k, None) => pandoc.args(&["-M", k]),
-            Var(ref k, Some(ref v)) => pandoc.args(&["-V", &format!("{}:{}", k, v)]),
-            Var(ref k, None) => pandoc.args(&["-V", k]),
+            BaseHeaderLevel(n) => pandoc.args([&format!("--base-header-level={}", n)]),
+            ShiftHeadingLevelBy(n) => pandoc.args([&format!("--shift-heading-level-by={}", n)]),
+            IndentedCodeClasses(ref s) => pandoc.args([&format!("--indented-code-classes={}", s)]),
+            Filter(ref program) => pandoc.arg(file_arg("--filter=", program)),
+            LuaFilter(ref script) => pandoc.arg(file_arg("--lua-filter=", script)),
+            Normalize => pandoc.args(["--normalize"]),
+            PreserveTabs => pandoc.args(["--preserve-tabs"]),
+            TabStop(n) => pandoc.args([&format!("--tab-stop={}", n)]),
+            TrackChanges(ref v) => pandoc.args([&format!("--track-changes={}", v)]),
+            ExtractMedia(ref p) => pandoc.arg(file_arg("--extract-media=", p)),
+            Standalone => pandoc.args(["--standalone"]),
+            Template(ref p) => pandoc.arg(file_arg("--template=", p)),
+            Meta(ref k, Some(ref v)) => pandoc.args(["-M", &format!("{}:{}", k, v)]),
+            Meta(ref k, None) => pandoc.args(["-M", k]),
+            Var(ref k, Some(ref v)) => pandoc.args(["-V", &format!("{}:{}", k, v)]),
+            Var(ref k, None) => pandoc.args(["-V", k]),
             PrintDefaultTemplate(ref f) => {
-                pandoc.args(&[&format!("--print-default-template={}", f)])
-            }
-            PrintDefaultDataFile(ref f) => {
-                pandoc.args(&[&format!("--print-default-data-file={}", f.display())])
+                pandoc.args([&format!("--print-default-template={}", f)])
             }
-            NoWrap => pandoc.args(&["--wrap=none"]),
-            Columns(n) => pandoc.args(&[&format!("--columns={}", n)]),
-            TableOfContents => pandoc.args(&["--table-of-contents"]),
-            TableOfContentsDepth(d) => pandoc.args(&[&format!("--toc-depth={}", d)]),
-            NoHighlight => pandoc.args(&["--no-highlight"]),
-            HighlightStyle(ref s) => pandoc.args(&[&format!("--highlight-style={}", s)]),
-            IncludeInHeader(ref p) => {
-                pandoc.args(&[&format!("--include-in-header={}", p.display())])
-            }
-            IncludeBeforeBody(ref p) => {
-                pandoc.args(&[&format!("--include-before-body={}", p.display())])
-            }
-            IncludeAfterBody(ref p) => {
-                pandoc.args(&[&format!("--include-after-body={}", p.display())])
-            }
-            SelfContained => pandoc.args(&["--self-contained"]),
-            Offline => pandoc.args(&["--offline"]),
-            Html5 => pandoc.args(&["--html5"]),
-            HtmlQTags => pandoc.args(&["--html-q-tags"]),
-            Ascii => pandoc.args(&["--ascii"]),
-            ReferenceLinks => pandoc.args(&["--reference-links"]),
-            AtxHeaders => pandoc.args(&["--markdown-headings=atx"]),
-            TopLevelDivision(Chapter) => pandoc.args(&["--top-level-division=chapter"]),
-            TopLevelDivision(Section) => pandoc.args(&["--top-level-division=section"]),
-            TopLevelDivision(Part) => pandoc.args(&["--top-level-division=part"]),
-            NumberSections => pandoc.args(&["--number-sections"]),
-            NoTexLigatures => pandoc.args(&["--no-tex-ligatures"]),
-            Listings => pandoc.args(&["--listings"]),
-            Incremental => pandoc.args(&["--incremental"]),
+            PrintDefaultDataFile(ref f) => pandoc.arg(file_arg("--print-default-data-file=", f)),
+            NoWrap => pandoc.args(["--wrap=none"]),
+            Columns(n) => pandoc.args([&format!("--columns={}", n)]),
+            TableOfContents => pandoc.args(["--table-of-contents"]),
+            TableOfContentsDepth(d) => pandoc.args([&format!("--toc-depth={}", d)]),
+            NoHighlight => pandoc.args(["--no-highlight"]),
+            HighlightStyle(ref s) => pandoc.args([&format!("--highlight-style={}", s)]),
+            IncludeInHeader(ref p) => pandoc.arg(file_arg("--include-in-header=", p)),
+            IncludeBeforeBody(ref p) => pandoc.arg(file_arg("--include-before-body=", p)),
+            IncludeAfterBody(ref p) => pandoc.arg(file_arg("--include-after-body=", p)),
+            SelfContained => pandoc.args(["--self-contained"]),
+            EmbedResources => pandoc.args(["--embed-resources"]),
+            FileScope => pandoc.args(["--file-scope"]),
+            Offline => pandoc.args(["--offline"]),
+            Html5 => pandoc.args(["--html5"]),
+            HtmlQTags => pandoc.args(["--html-q-tags"]),
+            Ascii => pandoc.args(["--ascii"]),
+            ReferenceLinks => pandoc.args(["--reference-links"]),
+            AtxHeaders => pandoc.args(["--markdown-headings=atx"]),
+            TopLevelDivision(Chapter) => pandoc.args(["--top-level-division=chapter"]),
+            TopLevelDivision(Section) => pandoc.args(["--top-level-division=section"]),
+            TopLevelDivision(Part) => pandoc.args(["--top-level-division=part"]),
+            NumberSections => pandoc.args(["--number-sections"]),
+            NoTexLigatures => pandoc.args(["--no-tex-ligatures"]),
+            Listings => pandoc.args(["--listings"]),
+            Incremental => pandoc.args(["--incremental"]),
             SlideLevel(n) => pandoc.args(&[format!("--slide-level={}", n)]),
-            SectionDivs => pandoc.args(&["--section-divs"]),
+            SectionDivs => pandoc.args(["--section-divs"]),
             DefaultImageExtension(ref s) => {
                 pandoc.args(&[format!("--default-image-extension={}", s)])
             }
@@ -371,88 +659,131 @@ impl PandocOption {
             IdPrefix(ref s) => pandoc.args(&[format!("--id-prefix={}", s)]),
             TitlePrefix(ref s) => pandoc.args(&[format!("--title-prefix={}", s)]),
             Css(ref url) => pandoc.args(&[format!("--css={}", url)]),
-            ReferenceOdt(ref file) => pandoc.args(&[format!("--reference-odt={}", file.display())]),
+            ReferenceOdt(ref file) => pandoc.arg(file_arg("--reference-odt=", file)),
             #[allow(deprecated)]
-            ReferenceDocx(ref file) => {
-                pandoc.args(&[&format!("--reference-docx={}", file.display())])
-            }
-            ReferenceDoc(ref file) => {
-                pandoc.args(&[&format!("--reference-doc={}", file.display())])
-            }
-            EpubStylesheet(ref file) => {
-                pandoc.args(&[&format!("--epub-stylesheet={}", file.display())])
-            }
-            EpubCoverImage(ref file) => {
-                pandoc.args(&[&format!("--epub-cover-image={}", file.display())])
-            }
-            EpubMetadata(ref file) => {
-                pandoc.args(&[&format!("--epub-metadata={}", file.display())])
-            }
-            EpubEmbedFont(ref file) => {
-                pandoc.args(&[&format!("--epub-embed-font={}", file.display())])
-            }
-            EpubChapterLevel(num) => pandoc.args(&[&format!("--epub-chapter-level={}", num)]),
-            PdfEngine(ref program) => {
-                pandoc.args(&[&format!("--pdf-engine={}", program.display())])
-            }
-            PdfEngineOpt(ref s) => pandoc.args(&[&format!("--pdf-engine-opt={}", s)]),
-            Citeproc => pandoc.args(&["--citeproc"]),
-            Bibliography(ref file) => pandoc.args(&[&format!("--bibliography={}", file.display())]),
-            Csl(ref file) => pandoc.args(&[&format!("--csl={}", file.display())]),
-            CitationAbbreviations(ref f) => {
-                pandoc.args(&[&format!("--citation-abbreviations={}", f.display())])
-            }
-            Natbib => pandoc.args(&["--natbib"]),
-            Biblatex => pandoc.args(&["--biblatex"]),
-            LatexMathML(Some(ref url)) => pandoc.args(&[&format!("--latexmathml={}", url)]),
-            AsciiMathML(Some(ref url)) => pandoc.args(&[&format!("--asciimathml={}", url)]),
-            MathML(Some(ref url)) => pandoc.args(&[&format!("--mathml={}", url)]),
-            MimeTex(Some(ref url)) => pandoc.args(&[&format!("--mimetex={}", url)]),
-            WebTex(Some(ref url)) => pandoc.args(&[&format!("--webtex={}", url)]),
-            JsMath(Some(ref url)) => pandoc.args(&[&format!("--jsmath={}", url)]),
-            MathJax(Some(ref url)) => pandoc.args(&[&format!("--mathjax={}", url)]),
-            Katex(Some(ref url)) => pandoc.args(&[&format!("--katex={}", url)]),
-            LatexMathML(None) => pandoc.args(&["--latexmathml"]),
-            AsciiMathML(None) => pandoc.args(&["--asciimathml"]),
-            MathML(None) => pandoc.args(&["--mathml"]),
-            MimeTex(None) => pandoc.args(&["--mimetex"]),
-            WebTex(None) => pandoc.args(&["--webtex"]),
-            JsMath(None) => pandoc.args(&["--jsmath"]),
-            MathJax(None) => pandoc.args(&["--mathjax"]),
-            Katex(None) => pandoc.args(&["--katex"]),
-            KatexStylesheet(ref url) => pandoc.args(&[&format!("--katex-stylesheet={}", url)]),
-            GladTex => pandoc.args(&["--gladtex"]),
-            Trace => pandoc.args(&["--trace"]),
-            DumpArgs => pandoc.args(&["--dump-args"]),
-            IgnoreArgs => pandoc.args(&["--ignore-args"]),
-            Verbose => pandoc.args(&["--verbose"]),
+            ReferenceDocx(ref file) => pandoc.arg(file_arg("--reference-docx=", file)),
+            ReferenceDoc(ref file) => pandoc.arg(file_arg("--reference-doc=", file)),
+            EpubStylesheet(ref file) => pandoc.arg(file_arg("--epub-stylesheet=", file)),
+            EpubCoverImage(ref file) => pandoc.arg(file_arg("--epub-cover-image=", file)),
+            EpubMetadata(ref file) => pandoc.arg(file_arg("--epub-metadata=", file)),
+            EpubEmbedFont(ref file) => pandoc.arg(file_arg("--epub-embed-font=", file)),
+            EpubChapterLevel(num) => pandoc.args([&format!("--epub-chapter-level={}", num)]),
+            PdfEngine(ref program) => pandoc.arg(file_arg("--pdf-engine=", program)),
+            PdfEngineOpt(ref s) => pandoc.args([&format!("--pdf-engine-opt={}", s)]),
+            Citeproc => pandoc.args(["--citeproc"]),
+            Bibliography(ref file) => pandoc.arg(file_arg("--bibliography=", file)),
+            Csl(ref file) => pandoc.arg(file_arg("--csl=", file)),
+            CitationAbbreviations(ref f) => pandoc.arg(file_arg("--citation-abbreviations=", f)),
+            MetadataFile(ref f) => pandoc.arg(file_arg("--metadata-file=", f)),
+            Natbib => pandoc.args(["--natbib"]),
+            Biblatex => pandoc.args(["--biblatex"]),
+            LatexMathML(Some(ref url)) => pandoc.args([&format!("--latexmathml={}", url)]),
+            AsciiMathML(Some(ref url)) => pandoc.args([&format!("--asciimathml={}", url)]),
+            MathML(Some(ref url)) => pandoc.args([&format!("--mathml={}", url)]),
+            MimeTex(Some(ref url)) => pandoc.args([&format!("--mimetex={}", url)]),
+            WebTex(Some(ref url)) => pandoc.args([&format!("--webtex={}", url)]),
+            JsMath(Some(ref url)) => pandoc.args([&format!("--jsmath={}", url)]),
+            MathJax(Some(ref url)) => pandoc.args([&format!("--mathjax={}", url)]),
+            Katex(Some(ref url)) => pandoc.args([&format!("--katex={}", url)]),
+            LatexMathML(None) => pandoc.args(["--latexmathml"]),
+            AsciiMathML(None) => pandoc.args(["--asciimathml"]),
+            MathML(None) => pandoc.args(["--mathml"]),
+            MimeTex(None) => pandoc.args(["--mimetex"]),
+            WebTex(None) => pandoc.args(["--webtex"]),
+            JsMath(None) => pandoc.args(["--jsmath"]),
+            MathJax(None) => pandoc.args(["--mathjax"]),
+            Katex(None) => pandoc.args(["--katex"]),
+            KatexStylesheet(ref url) => pandoc.args([&format!("--katex-stylesheet={}", url)]),
+            GladTex => pandoc.args(["--gladtex"]),
+            Trace => pandoc.args(["--trace"]),
+            DumpArgs => pandoc.args(["--dump-args"]),
+            IgnoreArgs => pandoc.args(["--ignore-args"]),
+            Verbose => pandoc.args(["--verbose"]),
             ResourcePath(ref paths) => {
-                let delimiter = if cfg!(windows) { ";" } else { ":" };
+                // the same delimiter as in `PATH`, directories containing it can't be given
                 let paths = paths
                     .iter()
-                    .map(|path| path.display().to_string())
-                    .join(delimiter);
-                pandoc.args(&[&format!("--resource-path={}", paths)])
+                    .filter(|path| env::join_paths(std::iter::once(path)).is_ok());
+                let paths = env::join_paths(paths).expect("invalid directories were removed");
+                pandoc.arg(path_arg("--resource-path=", &paths))
             }
             RuntimeSystem(ref rts_options) => {
-                pandoc.args(&["+RTS"]);
+                pandoc.args(["+RTS"]);
                 for option in rts_options {
                     match option {
                         PandocRuntimeSystemOption::MaximumHeapMemory(ref s) => {
-                            pandoc.args(&[&format!("-M{}", s)]);
+                            pandoc.args([&format!("-M{}", s)]);
+                        }
+                        PandocRuntimeSystemOption::MaximumStackSize(ref s) => {
+                            pandoc.args([&format!("-K{}", s)]);
+                        }
+                        PandocRuntimeSystemOption::AllocationAreaSize(ref s) => {
+                            pandoc.args([&format!("-A{}", s)]);
+                        }
+                        PandocRuntimeSystemOption::IdleGcDelay(ref s) => {
+                            pandoc.args([&format!("-I{}", s)]);
+                        }
+                        PandocRuntimeSystemOption::Other(ref s) => {
+                            pandoc.args([s]);
                         }
                     }
                 }
-                pandoc.args(&["-RTS"])
+                pandoc.args(["-RTS"])
             }
-            Sandbox => pandoc.args(&["--sandbox"]),
-            EOL(ref eol) => pandoc.args(&[&format!("--eol={}", eol)]),
+            Sandbox => pandoc.args(["--sandbox"]),
+            EOL(ref eol) => pandoc.args([&format!("--eol={}", eol)]),
+            IpynbOutput(o) => pandoc.args([&format!("--ipynb-output={}", o)]),
         }
     }
 }
 
+/// `flag` directly followed by `path`, which may not be valid UTF-8
+fn path_arg<T: AsRef<OsStr> + ?Sized>(flag: &str, path: &T) -> OsString {
+    let mut arg = OsString::from(flag);
+    arg.push(path);
+    arg
+}
+
+/// `flag` directly followed by the path of a file, see `long_path`
+fn file_arg(flag: &str, path: &Path) -> OsString {
+    path_arg(flag, long_path(path).as_os_str())
+}
+
+/// `path` in the extended-length form if it is too long for the windows APIs
+///
+/// Longer paths, as they occur in deeply nested directories, otherwise fail with obscure IO
+/// errors. The extended-length form has to be absolute, so the path is resolved against the
+/// current directory.
+#[cfg(windows)]
+fn long_path(path: &Path) -> std::borrow::Cow<'_, Path> {
+    use std::borrow::Cow;
+    // MAX_PATH, including the terminating null
+    const MAX_PATH: usize = 260;
+    let absolute = match std::path::absolute(path) {
+        Ok(absolute) => absolute,
+        Err(_) => return Cow::Borrowed(path),
+    };
+    let absolute_str = match absolute.to_str() {
+        Some(absolute) if absolute.len() >= MAX_PATH && !absolute.starts_with(r"\\?\") => absolute,
+        _ => return Cow::Borrowed(path),
+    };
+    let verbatim = match absolute_str.strip_prefix(r"\\") {
+        Some(unc) => format!(r"\\?\UNC\{}", unc),
+        None => format!(r"\\?\{}", absolute_str),
+    };
+    Cow::Owned(PathBuf::from(verbatim))
+}
+
+/// only windows limits the length of paths
+#[cfg(not(windows))]
+fn long_path(path: &Path) -> std::borrow::Cow<'_, Path> {
+    std::borrow::Cow::Borrowed(path)
+}
+
 /// equivalent to the latex document class
 #[derive(Debug, Clone)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[non_exhaustive]
 pub enum DocumentClass {
     /// compact form of report
     Article,
@@ -460,9 +791,21 @@ pub enum DocumentClass {
     Report,
     /// no abstract
     Book,
+    /// KOMA-Script version of `Article`, with European typography defaults
+    ScrArticle,
+    /// KOMA-Script version of `Report`
+    ScrReport,
+    /// KOMA-Script version of `Book`
+    ScrBook,
+    /// flexible class for books and reports, covering many packages' features
+    Memoir,
+    /// slides, usually set with the beamer output format instead
+    Beamer,
+    /// any other class, by the name passed to `\documentclass`
+    Custom(String),
 }
 
-pub use crate::DocumentClass::*;
+pub use crate::DocumentClass::{Article, Book, Report};
 
 impl std::fmt::Display for DocumentClass {
     fn fmt(&self, fmt: &mut std::fmt::Formatter) -> std::fmt::Result {
@@ -470,12 +813,19 @@ impl std::fmt::Display for DocumentClass {
             Article => write!(fmt, "article"),
             Report => write!(fmt, "report"),
             Book => write!(fmt, "book"),
+            DocumentClass::ScrArticle => write!(fmt, "scrartcl"),
+            DocumentClass::ScrReport => write!(fmt, "scrreprt"),
+            DocumentClass::ScrBook => write!(fmt, "scrbook"),
+            DocumentClass::Memoir => write!(fmt, "memoir"),
+            DocumentClass::Beamer => write!(fmt, "beamer"),
+            DocumentClass::Custom(ref name) => write!(fmt, "{}", name),
         }
     }
 }
 
 /// typesafe access to -t FORMAT, -w FORMAT, --to=FORMAT, --write=FORMAT
-#[derive(Debug, Clone)]
+#[derive(PartialEq, Eq, Hash, Debug, Clone)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 #[non_exhaustive]
 pub enum OutputFormat {
     /// native Haskell
@@ -491,17 +841,27 @@ pub enum OutputFormat {
     /// PHP Markdown extra extended markdown
     MarkdownPhpextra,
     /// github extended markdown
+    #[deprecated(note = "pandoc deprecated markdown_github, use Gfm")]
     MarkdownGithub,
+    /// GitHub-Flavored Markdown
+    Gfm,
     /// CommonMark markdown
     Commonmark,
     /// CommonMark markdown with extensions
     CommonmarkX,
+    /// Markua, the markdown dialect of Leanpub \
+    /// *Only available as of `pandoc 2.19`*
+    Markua,
     /// reStructuredText
     Rst,
     /// XHTML 1
     Html,
     /// HTML 5
     Html5,
+    /// HTML 5 split into one page per section, written to an `OutputKind::Directory`, or as a
+    /// zip archive to files ending in `.zip` and to pipes \
+    /// *Only available as of `pandoc 3.0`*
+    ChunkedHtml,
     /// LaTeX
     Latex,
     /// LaTeX beamer slide show
@@ -512,10 +872,26 @@ pub enum OutputFormat {
     Pdf,
     /// Groff man
     Man,
+    /// Groff ms, also used to produce PDFs with `--pdf-engine=pdfroff`
+    Ms,
     /// MediaWiki markup
     MediaWiki,
     /// DokuWiki markup
     Dokuwiki,
+    /// XWiki markup
+    XWiki,
+    /// ZimWiki markup
+    ZimWiki,
+    /// Jira/Confluence wiki markup
+    Jira,
+    /// Emacs Muse
+    Muse,
+    /// BibTeX bibliography
+    BibTeX,
+    /// BibLaTeX bibliography
+    BibLaTeX,
+    /// CSL JSON bibliography
+    CslJson,
     /// Textile
     Textile,
     /// Emacs Org-Mode
@@ -524,14 +900,30 @@ pub enum OutputFormat {
     Texinfo,
     /// OPML
     Opml,
-    /// DocBook
+    /// DocBook, same as `Docbook4`
     Docbook,
+    /// DocBook 4
+    Docbook4,
+    /// DocBook 5
+    Docbook5,
+    /// JATS XML, same as `JatsArchiving`
+    Jats,
+    /// JATS XML, Archiving and Interchange Tag Set
+    JatsArchiving,
+    /// JATS XML, Journal Publishing Tag Set
+    JatsPublishing,
+    /// JATS XML, Article Authoring Tag Set
+    JatsArticleAuthoring,
+    /// TEI Simple
+    Tei,
     /// Open Document
     OpenDocument,
     /// OpenOffice text document
     Odt,
     /// Word docx
     Docx,
+    /// PowerPoint slide show
+    Pptx,
     /// Haddock markup
     Haddock,
     /// Rich text format
@@ -556,10 +948,23 @@ pub enum OutputFormat {
     Revealjs,
     /// S5 HTML and javascript slide show
     S5,
+    /// Typst markup, also used to produce PDFs with `--pdf-engine=typst` \
+    /// *Only available as of `pandoc 3.0`*
+    Typst,
+    /// Jupyter notebook \
+    /// *Only available as of `pandoc 2.6`*
+    Ipynb,
     /// the path of a custom lua writer (see Custom writers)
+    ///
+    /// Writers that are not found relative to the working directory are looked up in the
+    /// `custom` subdirectory of pandoc's user data directory. With
+    /// `PandocOption::Standalone`, pandoc needs a template, either from a `Template` function
+    /// of the writer or given with `PandocOption::Template`.
     Lua(String),
     /// Other
     Other(String),
+    /// Other format producing binary output, returned as `PandocOutput::ToBufferRaw`
+    OtherBinary(String),
 }
 
 impl std::fmt::Display for OutputFormat {
@@ -572,27 +977,47 @@ impl std::fmt::Display for OutputFormat {
             Markdown => write!(fmt, "markdown"),
             MarkdownStrict => write!(fmt, "markdown_strict"),
             MarkdownPhpextra => write!(fmt, "markdown_phpextra"),
+            #[allow(deprecated)]
             MarkdownGithub => write!(fmt, "markdown_github"),
+            Gfm => write!(fmt, "gfm"),
             Commonmark => write!(fmt, "commonmark"),
             CommonmarkX => write!(fmt, "commonmark_x"),
+            Markua => write!(fmt, "markua"),
             Rst => write!(fmt, "rst"),
             Html => write!(fmt, "html"),
             Html5 => write!(fmt, "html5"),
+            ChunkedHtml => write!(fmt, "chunkedhtml"),
             Latex => write!(fmt, "latex"),
             Beamer => write!(fmt, "beamer"),
             Context => write!(fmt, "context"),
             Pdf => write!(fmt, "pdf"),
             Man => write!(fmt, "man"),
+            Ms => write!(fmt, "ms"),
             MediaWiki => write!(fmt, "mediawiki"),
             Dokuwiki => write!(fmt, "dokuwiki"),
+            XWiki => write!(fmt, "xwiki"),
+            ZimWiki => write!(fmt, "zimwiki"),
+            Jira => write!(fmt, "jira"),
+            Muse => write!(fmt, "muse"),
+            BibTeX => write!(fmt, "bibtex"),
+            BibLaTeX => write!(fmt, "biblatex"),
+            CslJson => write!(fmt, "csljson"),
             Textile => write!(fmt, "textile"),
             Org => write!(fmt, "org"),
             Texinfo => write!(fmt, "texinfo"),
             Opml => write!(fmt, "opml"),
             Docbook => write!(fmt, "docbook"),
+            Docbook4 => write!(fmt, "docbook4"),
+            Docbook5 => write!(fmt, "docbook5"),
+            Jats => write!(fmt, "jats"),
+            JatsArchiving => write!(fmt, "jats_archiving"),
+            JatsPublishing => write!(fmt, "jats_publishing"),
+            JatsArticleAuthoring => write!(fmt, "jats_articleauthoring"),
+            Tei => write!(fmt, "tei"),
             OpenDocument => write!(fmt, "open_document"),
             Odt => write!(fmt, "odt"),
             Docx => write!(fmt, "docx"),
+            Pptx => write!(fmt, "pptx"),
             Haddock => write!(fmt, "haddock"),
             Rtf => write!(fmt, "rtf"),
             Epub => write!(fmt, "epub"),
@@ -605,14 +1030,179 @@ impl std::fmt::Display for OutputFormat {
             Dzslides => write!(fmt, "dzslides"),
             Revealjs => write!(fmt, "revealjs"),
             S5 => write!(fmt, "s5"),
-            Lua(_) => unimplemented!(),
-            Other(f) => write!(fmt, "{}", f),
+            Typst => write!(fmt, "typst"),
+            Ipynb => write!(fmt, "ipynb"),
+            Lua(script) => write!(fmt, "{}", script),
+            Other(f) | OtherBinary(f) => write!(fmt, "{}", f),
+        }
+    }
+}
+
+impl std::str::FromStr for OutputFormat {
+    type Err = PandocError;
+
+    /// Parse a writer name like `html5`, paths ending in `.lua` are returned as `Lua` and
+    /// other unknown names as `Other`.
+    ///
+    /// Extensions are rejected, use
+    /// [`OutputFormat::parse_with_extensions`](#method.parse_with_extensions) for those.
+    fn from_str(name: &str) -> Result<OutputFormat, PandocError> {
+        use crate::OutputFormat::*;
+        // paths can contain characters not allowed in format names
+        if name.ends_with(".lua") {
+            return Ok(Lua(name.to_string()));
+        }
+        if !is_format_name(name) {
+            return Err(PandocError::InvalidFormat(name.to_string()));
+        }
+        Ok(match name {
+            "native" => Native,
+            "json" => Json,
+            "plain" => Plain,
+            "markdown" => Markdown,
+            "markdown_strict" => MarkdownStrict,
+            "markdown_phpextra" => MarkdownPhpextra,
+            #[allow(deprecated)]
+            "markdown_github" => MarkdownGithub,
+            "gfm" => Gfm,
+            "commonmark" => Commonmark,
+            "commonmark_x" => CommonmarkX,
+            "markua" => Markua,
+            "rst" => Rst,
+            "html" => Html,
+            "html5" => Html5,
+            "chunkedhtml" => ChunkedHtml,
+            "latex" => Latex,
+            "beamer" => Beamer,
+            "context" => Context,
+            "pdf" => Pdf,
+            "man" => Man,
+            "ms" => Ms,
+            "mediawiki" => MediaWiki,
+            "dokuwiki" => Dokuwiki,
+            "xwiki" => XWiki,
+            "zimwiki" => ZimWiki,
+            "jira" => Jira,
+            "muse" => Muse,
+            "bibtex" => BibTeX,
+            "biblatex" => BibLaTeX,
+            "csljson" => CslJson,
+            "textile" => Textile,
+            "org" => Org,
+            "texinfo" => Texinfo,
+            "opml" => Opml,
+            "docbook" => Docbook,
+            "docbook4" => Docbook4,
+            "docbook5" => Docbook5,
+            "jats" => Jats,
+            "jats_archiving" => JatsArchiving,
+            "jats_publishing" => JatsPublishing,
+            "jats_articleauthoring" => JatsArticleAuthoring,
+            "tei" => Tei,
+            "open_document" => OpenDocument,
+            "odt" => Odt,
+            "docx" => Docx,
+            "pptx" => Pptx,
+            "haddock" => Haddock,
+            "rtf" => Rtf,
+            "epub" => Epub,
+            "epub3" => Epub3,
+            "fb2" => Fb2,
+            "asciidoc" => Asciidoc,
+            "icml" => Icml,
+            "slidy" => Slidy,
+            "slideous" => Slideous,
+            "dzslides" => Dzslides,
+            "revealjs" => Revealjs,
+            "s5" => S5,
+            "typst" => Typst,
+            "ipynb" => Ipynb,
+            "opendocument" => OpenDocument,
+            _ => Other(name.to_string()),
+        })
+    }
+}
+
+impl std::convert::TryFrom<&str> for OutputFormat {
+    type Error = PandocError;
+    fn try_from(name: &str) -> Result<OutputFormat, PandocError> {
+        name.parse()
+    }
+}
+
+impl OutputFormat {
+    /// Parse a format specification like `gfm-raw_html`, as accepted by pandoc's `--to`
+    /// option, into the format and its extensions.
+    pub fn parse_with_extensions(
+        spec: &str,
+    ) -> Result<(OutputFormat, Vec<MarkdownExtension>), PandocError> {
+        let (name, extensions) = parse_format_spec(spec)?;
+        Ok((name.parse()?, extensions))
+    }
+
+    /// The writer pandoc uses for output files with the given extension (without the leading
+    /// dot).
+    ///
+    /// Returns `None` for extensions pandoc can't infer a writer from. Like pandoc, `txt` and
+    /// `text` files are written as markdown, set `OutputFormat::Plain` explicitly for plain
+    /// text.
+    pub fn from_extension(extension: &str) -> Option<OutputFormat> {
+        use crate::OutputFormat::*;
+        Some(match extension.to_ascii_lowercase().as_str() {
+            "md" | "markdown" | "mkd" | "mkdn" | "mdwn" | "mdown" | "rmd" | "txt" | "text" => {
+                Markdown
+            }
+            "native" => Native,
+            "json" => Json,
+            "rst" => Rst,
+            "htm" | "html" | "xhtml" => Html,
+            "tex" | "latex" | "ltx" => Latex,
+            "ctx" | "context" => Context,
+            "pdf" => Pdf,
+            "wiki" => MediaWiki,
+            "dokuwiki" => Dokuwiki,
+            "textile" => Textile,
+            "org" => Org,
+            "texi" | "texinfo" => Texinfo,
+            "opml" => Opml,
+            "db" => Docbook,
+            "odt" => Odt,
+            "docx" => Docx,
+            "rtf" => Rtf,
+            "epub" => Epub,
+            "fb2" => Fb2,
+            "adoc" | "asciidoc" => Asciidoc,
+            "icml" => Icml,
+            "1" | "2" | "3" | "4" | "5" | "6" | "7" | "8" | "9" => Man,
+            "ms" | "roff" => Ms,
+            "pptx" => Pptx,
+            "ipynb" => Ipynb,
+            "muse" => Muse,
+            "typ" => Typst,
+            _ => return None,
+        })
+    }
+
+    /// Whether pandoc produces binary (non UTF-8) output for this format.
+    ///
+    /// Binary output written to a pipe is returned as `PandocOutput::ToBufferRaw`. `Other`
+    /// formats are classified by their name, use `OtherBinary` for binary custom formats.
+    pub fn is_binary(&self) -> bool {
+        use crate::OutputFormat::*;
+        match self {
+            Pdf | Docx | Pptx | Odt | Epub | Epub3 | ChunkedHtml | OtherBinary(_) => true,
+            Other(f) => {
+                let name = f.split(['+', '-']).next().unwrap_or_default();
+                ["pdf", "docx", "odt", "pptx", "epub", "epub2", "epub3"].contains(&name)
+            }
+            _ => false,
         }
     }
 }
 
 /// typesafe access to -f FORMAT, -r FORMAT, --from=FORMAT, --read=FORMAT
-#[derive(Debug, Clone)]
+#[derive(PartialEq, Eq, Hash, Debug, Clone)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 #[non_exhaustive]
 pub enum InputFormat {
     /// native Haskell
@@ -626,7 +1216,10 @@ pub enum InputFormat {
     /// PHP Markdown extra extended markdown
     MarkdownPhpextra,
     /// github extended markdown
+    #[deprecated(note = "pandoc deprecated markdown_github, use Gfm")]
     MarkdownGithub,
+    /// GitHub-Flavored Markdown
+    Gfm,
     /// CommonMark markdown
     Commonmark,
     /// CommonMark markdown with extensions
@@ -656,10 +1249,44 @@ pub enum InputFormat {
     MediaWiki,
     /// TWiki markup
     Twiki,
+    /// TikiWiki markup
+    TikiWiki,
+    /// Creole 1.0
+    Creole,
+    /// Vimwiki
+    Vimwiki,
+    /// Jira/Confluence wiki markup
+    Jira,
+    /// Emacs Muse
+    Muse,
+    /// BibTeX bibliography
+    BibTeX,
+    /// BibLaTeX bibliography
+    BibLaTeX,
+    /// CSL JSON bibliography
+    CslJson,
+    /// RIS bibliography
+    Ris,
+    /// EndNote XML bibliography
+    EndNoteXml,
+    /// comma-separated values, read as a single table
+    Csv,
+    /// tab-separated values, read as a single table \
+    /// *Only available as of `pandoc 2.19`*
+    Tsv,
     /// Haddock markup
     Haddock,
     /// LaTeX
     Latex,
+    /// Typst markup \
+    /// *Only available as of `pandoc 3.1.12`*
+    Typst,
+    /// Jupyter notebook \
+    /// *Only available as of `pandoc 2.6`*
+    Ipynb,
+    /// the path of a custom lua reader \
+    /// *Only available as of `pandoc 3.0`*
+    Lua(PathBuf),
     /// Other
     Other(String),
 }
@@ -673,7 +1300,9 @@ impl std::fmt::Display for InputFormat {
             Markdown => write!(fmt, "markdown"),
             MarkdownStrict => write!(fmt, "markdown_strict"),
             MarkdownPhpextra => write!(fmt, "markdown_phpextra"),
+            #[allow(deprecated)]
             MarkdownGithub => write!(fmt, "markdown_github"),
+            Gfm => write!(fmt, "gfm"),
             Commonmark => write!(fmt, "commonmark"),
             CommonmarkX => write!(fmt, "commonmark_x"),
             Rst => write!(fmt, "rst"),
@@ -690,13 +1319,158 @@ impl std::fmt::Display for InputFormat {
             DocBook => write!(fmt, "docbook"),
             T2t => write!(fmt, "t2t"),
             Twiki => write!(fmt, "twiki"),
+            TikiWiki => write!(fmt, "tikiwiki"),
+            Creole => write!(fmt, "creole"),
+            Vimwiki => write!(fmt, "vimwiki"),
+            Jira => write!(fmt, "jira"),
+            Muse => write!(fmt, "muse"),
+            BibTeX => write!(fmt, "bibtex"),
+            BibLaTeX => write!(fmt, "biblatex"),
+            CslJson => write!(fmt, "csljson"),
+            Ris => write!(fmt, "ris"),
+            EndNoteXml => write!(fmt, "endnotexml"),
+            Csv => write!(fmt, "csv"),
+            Tsv => write!(fmt, "tsv"),
+            Typst => write!(fmt, "typst"),
+            Ipynb => write!(fmt, "ipynb"),
+            Lua(script) => write!(fmt, "{}", script.display()),
             Other(f) => write!(fmt, "{}", f),
         }
     }
 }
 
+impl std::str::FromStr for InputFormat {
+    type Err = PandocError;
+
+    /// Parse a reader name like `markdown`, paths ending in `.lua` are returned as `Lua` and
+    /// other unknown names as `Other`.
+    ///
+    /// Extensions are rejected, use
+    /// [`InputFormat::parse_with_extensions`](#method.parse_with_extensions) for those.
+    fn from_str(name: &str) -> Result<InputFormat, PandocError> {
+        use crate::InputFormat::*;
+        // paths can contain characters not allowed in format names
+        if name.ends_with(".lua") {
+            return Ok(Lua(PathBuf::from(name)));
+        }
+        if !is_format_name(name) {
+            return Err(PandocError::InvalidFormat(name.to_string()));
+        }
+        Ok(match name {
+            "native" => Native,
+            "json" => Json,
+            "markdown" => Markdown,
+            "markdown_strict" => MarkdownStrict,
+            "markdown_phpextra" => MarkdownPhpextra,
+            #[allow(deprecated)]
+            "markdown_github" => MarkdownGithub,
+            "gfm" => Gfm,
+            "commonmark" => Commonmark,
+            "commonmark_x" => CommonmarkX,
+            "rst" => Rst,
+            "rtf" => Rtf,
+            "html" => Html,
+            "latex" => Latex,
+            "mediawiki" => MediaWiki,
+            "textile" => Textile,
+            "org" => Org,
+            "opml" => Opml,
+            "docx" => Docx,
+            "haddock" => Haddock,
+            "epub" => Epub,
+            "docbook" => DocBook,
+            "t2t" => T2t,
+            "twiki" => Twiki,
+            "tikiwiki" => TikiWiki,
+            "creole" => Creole,
+            "vimwiki" => Vimwiki,
+            "jira" => Jira,
+            "muse" => Muse,
+            "bibtex" => BibTeX,
+            "biblatex" => BibLaTeX,
+            "csljson" => CslJson,
+            "ris" => Ris,
+            "endnotexml" => EndNoteXml,
+            "csv" => Csv,
+            "tsv" => Tsv,
+            "typst" => Typst,
+            "ipynb" => Ipynb,
+            _ => Other(name.to_string()),
+        })
+    }
+}
+
+impl std::convert::TryFrom<&str> for InputFormat {
+    type Error = PandocError;
+    fn try_from(name: &str) -> Result<InputFormat, PandocError> {
+        name.parse()
+    }
+}
+
+impl InputFormat {
+    /// Parse a format specification like `markdown+smart-raw_html`, as accepted by pandoc's
+    /// `--from` option, into the format and its extensions.
+    pub fn parse_with_extensions(
+        spec: &str,
+    ) -> Result<(InputFormat, Vec<MarkdownExtension>), PandocError> {
+        let (name, extensions) = parse_format_spec(spec)?;
+        Ok((name.parse()?, extensions))
+    }
+
+    /// Whether the format is a container like docx, whose images pandoc keeps in memory
+    /// while converting it.
+    pub(crate) fn is_binary(&self) -> bool {
+        match *self {
+            InputFormat::Docx | InputFormat::Epub => true,
+            InputFormat::Other(ref f) => {
+                ["docx", "epub", "odt", "pptx", "xlsx"].contains(&f.as_str())
+            }
+            _ => false,
+        }
+    }
+
+    /// The reader pandoc uses for files with the given extension (without the leading dot).
+    ///
+    /// Returns `None` for extensions pandoc does not associate with a reader.
+    pub fn from_extension(extension: &str) -> Option<InputFormat> {
+        use crate::InputFormat::*;
+        Some(match extension.to_ascii_lowercase().as_str() {
+            "md" | "markdown" | "mkd" | "mkdn" | "mdwn" | "mdown" | "rmd" | "txt" | "text" => {
+                Markdown
+            }
+            "native" => Native,
+            "json" => Json,
+            "textile" => Textile,
+            "rst" => Rst,
+            "rtf" => Rtf,
+            "htm" | "html" | "xhtml" => Html,
+            "db" => DocBook,
+            "t2t" => T2t,
+            "docx" => Docx,
+            "epub" => Epub,
+            "opml" => Opml,
+            "org" => Org,
+            "wiki" => MediaWiki,
+            "tex" | "latex" | "ltx" => Latex,
+            "odt" => Other("odt".to_string()),
+            "fb2" => Other("fb2".to_string()),
+            "dokuwiki" => Other("dokuwiki".to_string()),
+            "ipynb" => Ipynb,
+            "muse" => Muse,
+            "csv" => Csv,
+            "tsv" => Tsv,
+            "bib" => BibLaTeX,
+            "ris" => Ris,
+            "typ" => Typst,
+            "1" | "2" | "3" | "4" | "5" | "6" | "7" | "8" | "9" => Other("man".to_string()),
+            _ => return None,
+        })
+    }
+}
+
 #[allow(missing_docs)]
-#[derive(Debug, Clone)]
+#[derive(PartialEq, Eq, Hash, Debug, Clone)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 #[non_exhaustive]
 pub enum MarkdownExtension {
     Smart,
@@ -758,7 +1532,21 @@ pub enum MarkdownExtension {
     MmdHeaderIdentifiers,
     CompactDefinitionLists,
     RebaseRelativePaths,
+    Emoji,
+    GfmAutoIdentifiers,
+    TexMathGfm,
+    Alerts,
+    WikilinksTitleAfterPipe,
+    WikilinksTitleBeforePipe,
+    EastAsianLineBreaks,
+    FourSpaceRule,
+    Gutenberg,
+    Sourcepos,
+    ShortSubsuperscripts,
+    Mark,
     Other(String),
+    /// disables the wrapped extension, which is enabled by default for the format
+    Disable(Box<MarkdownExtension>),
 }
 
 impl std::fmt::Display for MarkdownExtension {
@@ -824,38 +1612,513 @@ impl std::fmt::Display for MarkdownExtension {
             MmdHeaderIdentifiers => write!(fmt, "mmd_header_identifiers"),
             CompactDefinitionLists => write!(fmt, "compact_definition_lists"),
             RebaseRelativePaths => write!(fmt, "rebase_relative_paths"),
+            Emoji => write!(fmt, "emoji"),
+            GfmAutoIdentifiers => write!(fmt, "gfm_auto_identifiers"),
+            TexMathGfm => write!(fmt, "tex_math_gfm"),
+            Alerts => write!(fmt, "alerts"),
+            WikilinksTitleAfterPipe => write!(fmt, "wikilinks_title_after_pipe"),
+            WikilinksTitleBeforePipe => write!(fmt, "wikilinks_title_before_pipe"),
+            EastAsianLineBreaks => write!(fmt, "east_asian_line_breaks"),
+            FourSpaceRule => write!(fmt, "four_space_rule"),
+            Gutenberg => write!(fmt, "gutenberg"),
+            Sourcepos => write!(fmt, "sourcepos"),
+            ShortSubsuperscripts => write!(fmt, "short_subsuperscripts"),
+            Mark => write!(fmt, "mark"),
             Other(e) => write!(fmt, "{}", e),
+            Disable(e) => write!(fmt, "{}", e),
         }
     }
 }
 
-#[derive(Clone, Debug)]
+impl std::str::FromStr for MarkdownExtension {
+    type Err = PandocError;
+
+    /// Parse an extension name like `smart`, unknown names are returned as `Other`.
+    fn from_str(name: &str) -> Result<MarkdownExtension, PandocError> {
+        use crate::MarkdownExtension::*;
+        if !is_format_name(name) {
+            return Err(PandocError::InvalidFormat(name.to_string()));
+        }
+        Ok(match name {
+            "smart" => Smart,
+            "attributes" => Attributes,
+            "escaped_line_breaks" => EscapedLineBreaks,
+            "blank_before_header" => BlankBeforeHeader,
+            "header_attributes" => HeaderAttributes,
+            "auto_identifiers" => AutoIdentifiers,
+            "implicit_header_references" => ImplicitHeaderReferences,
+            "blank_before_block_quote" => BlankBeforeBlockQuote,
+            "fenced_divs" => FencedDivs,
+            "fenced_code_blocks" => FencedCodeBlocks,
+            "backtick_code_blocks" => BacktickCodeBlocks,
+            "fenced_code_attributes" => FencedCodeAttributes,
+            "line_blocks" => LineBlocks,
+            "fancy_lists" => FancyLists,
+            "startnum" => Startnum,
+            "task_lists" => TaskLists,
+            "definition_lists" => DefinitionLists,
+            "example_lists" => ExampleLists,
+            "table_captions" => TableCaptions,
+            "simple_tables" => SimpleTables,
+            "multiline_tables" => MultilineTables,
+            "grid_tables" => GridTables,
+            "pipe_tables" => PipeTables,
+            "pandoc_title_block" => PandocTitleBlock,
+            "yaml_metadata_block" => YamlMetadataBlock,
+            "all_symbols_escapable" => AllSymbolsEscapable,
+            "intraword_underscores" => IntrawordUnderscores,
+            "strikeout" => Strikeout,
+            "superscript" => Superscript,
+            "subscript" => Subscript,
+            "inline_code_attributes" => InlineCodeAttributes,
+            "tex_math_dollars" => TexMathDollars,
+            "raw_attribute" => RawAttribute,
+            "raw_html" => RawHtml,
+            "markdown_in_html_blocks" => MarkdownInHtmlBlocks,
+            "native_divs" => NativeDivs,
+            "native_spans" => NativeSpans,
+            "bracketed_spans" => BracketedSpans,
+            "raw_tex" => RawTex,
+            "latex_macros" => LatexMacros,
+            "shortcut_reference_links" => ShortcutReferenceLinks,
+            "implicit_figures" => ImplicitFigures,
+            "footnotes" => Footnotes,
+            "inline_notes" => InlineNotes,
+            "citations" => Citations,
+            "lists_without_preceding_blankline" => ListsWithoutPrecedingBlankline,
+            "hard_line_breaks" => HardLineBreaks,
+            "ignore_line_breaks" => IgnoreLineBreaks,
+            "tex_math_single_backslash" => TexMathSingleBackslash,
+            "tex_math_double_backslash" => TexMathDoubleBackslash,
+            "markdown_attribute" => MarkdownAttribute,
+            "mmd_title_block" => MmdTitleBlock,
+            "abbreviations" => Abbreviations,
+            "autolink_bare_uris" => AutolinkBareUris,
+            "ascii_identifiers" => AsciiIdentifiers,
+            "link_attributes" => LinkAttributes,
+            "mmd_header_identifiers" => MmdHeaderIdentifiers,
+            "compact_definition_lists" => CompactDefinitionLists,
+            "rebase_relative_paths" => RebaseRelativePaths,
+            "emoji" => Emoji,
+            "gfm_auto_identifiers" => GfmAutoIdentifiers,
+            "tex_math_gfm" => TexMathGfm,
+            "alerts" => Alerts,
+            "wikilinks_title_after_pipe" => WikilinksTitleAfterPipe,
+            "wikilinks_title_before_pipe" => WikilinksTitleBeforePipe,
+            "east_asian_line_breaks" => EastAsianLineBreaks,
+            "four_space_rule" => FourSpaceRule,
+            "gutenberg" => Gutenberg,
+            "sourcepos" => Sourcepos,
+            "short_subsuperscripts" => ShortSubsuperscripts,
+            "mark" => Mark,
+            _ => Other(name.to_string()),
+        })
+    }
+}
+
+/// `format` followed by the extensions, e.g. `markdown+smart-raw_html`
+pub(crate) fn format_arg<T: std::fmt::Display>(
+    format: &T,
+    extensions: &[MarkdownExtension],
+) -> String {
+    let mut arg = format.to_string();
+    for extension in extensions {
+        match *extension {
+            MarkdownExtension::Disable(_) => arg.push('-'),
+            _ => arg.push('+'),
+        }
+        arg.push_str(&extension.to_string());
+    }
+    arg
+}
+
+fn is_format_name(name: &str) -> bool {
+    !name.is_empty()
+        && name
+            .chars()
+            .all(|c| c.is_ascii_alphanumeric() || matches!(c, '_' | '.' | '/' | '\\'))
+}
+
+/// split a format specification like `gfm+smart-raw_html` into the name and the extensions
+fn parse_format_spec(spec: &str) -> Result<(&str, Vec<MarkdownExtension>), PandocError> {
+    let invalid = || PandocError::InvalidFormat(spec.to_string());
+    let end = spec.find(['+', '-']).unwrap_or(spec.len());
+    let (name, mut rest) = spec.split_at(end);
+    if !is_format_name(name) {
+        return Err(invalid());
+    }
+    let mut extensions = Vec::new();
+    while let Some(sign) = rest.chars().next() {
+        rest = &rest[1..];
+        let end = rest.find(['+', '-']).unwrap_or(rest.len());
+        let extension: MarkdownExtension = rest[..end].parse().map_err(|_| invalid())?;
+        rest = &rest[end..];
+        extensions.push(match sign {
+            '-' => MarkdownExtension::Disable(Box::new(extension)),
+            _ => extension,
+        });
+    }
+    Ok((name, extensions))
+}
+
+/// A single input document
+#[derive(PartialEq, Eq, Hash, Clone, Debug)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum InputSource {
+    /// a local file
+    File(PathBuf),
+    /// a remote document, fetched by pandoc itself
+    Url(URL),
+    /// all files matching the glob pattern, expanded in sorted order when executing
+    Glob(String),
+}
+
+#[derive(PartialEq, Eq, Hash, Clone, Debug)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub enum InputKind {
     Files(Vec<PathBuf>),
+    /// local files and remote documents, processed in the given order
+    Sources(Vec<InputSource>),
     /// passed to the pandoc executable through stdin
     Pipe(String),
+    /// connected to the pandoc executable as stdin, without reading it into memory first
+    #[cfg_attr(feature = "serde", serde(skip))]
+    Handle(InputHandle),
+    /// pandoc reads from the stdin of the current process
+    InheritStdin,
+}
+
+/// An open file (or anything else convertible to `Stdio`) used as pandoc's stdin.
+///
+/// The handle is shared between clones of a `Pandoc` configuration and can only be consumed by
+/// a single execution, later executions fail with `PandocError::NoInputSpecified`.
+#[derive(Clone)]
+pub struct InputHandle(std::sync::Arc<std::sync::Mutex<Option<std::process::Stdio>>>);
+
+impl InputHandle {
+    /// Wrap an open file, pipe or other handle.
+    pub fn new<T: Into<std::process::Stdio>>(handle: T) -> InputHandle {
+        InputHandle(std::sync::Arc::new(std::sync::Mutex::new(Some(
+            handle.into(),
+        ))))
+    }
+
+    fn take(&self) -> Option<std::process::Stdio> {
+        self.0.lock().unwrap_or_else(|e| e.into_inner()).take()
+    }
+}
+
+impl std::fmt::Debug for InputHandle {
+    fn fmt(&self, fmt: &mut std::fmt::Formatter) -> std::fmt::Result {
+        write!(fmt, "InputHandle")
+    }
+}
+
+/// Handles are equal if they are clones of the same handle.
+impl PartialEq for InputHandle {
+    fn eq(&self, other: &InputHandle) -> bool {
+        Arc::ptr_eq(&self.0, &other.0)
+    }
+}
+
+impl Eq for InputHandle {}
+
+impl std::hash::Hash for InputHandle {
+    fn hash<H: std::hash::Hasher>(&self, state: &mut H) {
+        Arc::as_ptr(&self.0).hash(state)
+    }
 }
 
 /// Specify whether to generate a file or pipe the output to stdout.
-#[derive(Clone, Debug)]
+#[derive(PartialEq, Eq, Hash, Clone, Debug)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub enum OutputKind {
     File(PathBuf),
     Pipe,
+    /// write to a newly created, uniquely named temporary file
+    ///
+    /// The string is the suffix of the file name and should contain the extension
+    /// (e.g. `".pdf"`). The file is returned as `PandocOutput::ToTempFile` and removed once
+    /// the returned guard is dropped.
+    TempFile(String),
+    /// write to the file and also return the output like `Pipe` does
+    Tee(PathBuf),
+    /// write several files into a directory, as done by the `chunkedhtml` writer
+    ///
+    /// The files written by pandoc are listed in `PandocOutput::ToDirectory`, files left in
+    /// the directory by earlier runs are not. Without an output format,
+    /// `OutputFormat::ChunkedHtml` is used.
+    Directory(PathBuf),
+    /// pandoc writes directly to the stdout of the current process
+    InheritStdout,
 }
 
 /// the argument builder
 #[derive(Default, Clone)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[cfg_attr(feature = "serde", serde(default))]
 pub struct Pandoc {
     input: Option<InputKind>,
     input_format: Option<(InputFormat, Vec<MarkdownExtension>)>,
     output: Option<OutputKind>,
     output_format: Option<(OutputFormat, Vec<MarkdownExtension>)>,
+    output_targets: Vec<(OutputFormat, OutputKind)>,
     latex_path_hint: Vec<PathBuf>,
     pandoc_path_hint: Vec<PathBuf>,
-    filters: Vec<Rc<dyn Fn(String) -> String>>,
-    args: Vec<(String, String)>,
+    #[cfg_attr(feature = "serde", serde(skip))]
+    filters: Vec<Filter>,
+    #[cfg_attr(feature = "serde", serde(skip))]
+    input_preprocessors: Vec<Arc<dyn Fn(String) -> String + Send + Sync>>,
+    #[cfg_attr(feature = "serde", serde(with = "os_args"))]
+    args: Vec<(String, OsString)>,
     options: Vec<PandocOption>,
     print_pandoc_cmdline: bool,
+    #[cfg_attr(feature = "serde", serde(skip))]
+    cmdline_callback: Option<CmdlineCallback>,
+    lossy_utf8: bool,
+    fail_on: Vec<WarningClass>,
+    infer_input_format: bool,
+    /// generated files passed to pandoc, removed once the last clone of the builder is gone
+    #[cfg_attr(feature = "serde", serde(skip))]
+    temp_files: Vec<std::sync::Arc<TempPath>>,
+    cache_dir: Option<PathBuf>,
+    reproducible: bool,
+    no_builtin_path_hints: bool,
+    timeout: Option<std::time::Duration>,
+    no_remote_inputs: bool,
+    filter_api_versions: Vec<(u32, u32)>,
+}
+
+/// The closures of filters, preprocessors and the command-line callback are only counted.
+impl std::fmt::Debug for Pandoc {
+    fn fmt(&self, fmt: &mut std::fmt::Formatter) -> std::fmt::Result {
+        fmt.debug_struct("Pandoc")
+            .field("input", &self.input)
+            .field("input_format", &self.input_format)
+            .field("output", &self.output)
+            .field("output_format", &self.output_format)
+            .field("output_targets", &self.output_targets)
+            .field("latex_path_hint", &self.latex_path_hint)
+            .field("pandoc_path_hint", &self.pandoc_path_hint)
+            .field("filters", &self.filters.len())
+            .field("input_preprocessors", &self.input_preprocessors.len())
+            .field("args", &self.args)
+            .field("options", &self.options)
+            .field("print_pandoc_cmdline", &self.print_pandoc_cmdline)
+            .field("cmdline_callback", &self.cmdline_callback.is_some())
+            .field("lossy_utf8", &self.lossy_utf8)
+            .field("fail_on", &self.fail_on)
+            .field("infer_input_format", &self.infer_input_format)
+            .field("temp_files", &self.temp_files)
+            .field("cache_dir", &self.cache_dir)
+            .field("reproducible", &self.reproducible)
+            .field("no_builtin_path_hints", &self.no_builtin_path_hints)
+            .field("timeout", &self.timeout)
+            .field("no_remote_inputs", &self.no_remote_inputs)
+            .field("filter_api_versions", &self.filter_api_versions)
+            .finish()
+    }
+}
+
+/// the address of a closure, so clones of a builder share the same closures
+fn closure_ptr<T: ?Sized>(closure: &Arc<T>) -> *const () {
+    Arc::as_ptr(closure) as *const ()
+}
+
+/// Configurations are equal if they run the same pandoc command, with the same closures.
+///
+/// Filters, preprocessors and the command-line callback are compared by their address, so
+/// only clones of a builder share them. The generated files are not compared, their paths
+/// are already part of the options.
+impl PartialEq for Pandoc {
+    fn eq(&self, other: &Pandoc) -> bool {
+        let closures = |pandoc: &Pandoc| -> (Vec<*const ()>, Vec<*const ()>, Option<*const ()>) {
+            (
+                pandoc.filters.iter().map(closure_ptr).collect(),
+                pandoc.input_preprocessors.iter().map(closure_ptr).collect(),
+                pandoc.cmdline_callback.as_ref().map(closure_ptr),
+            )
+        };
+        self.input == other.input
+            && self.input_format == other.input_format
+            && self.output == other.output
+            && self.output_format == other.output_format
+            && self.output_targets == other.output_targets
+            && self.latex_path_hint == other.latex_path_hint
+            && self.pandoc_path_hint == other.pandoc_path_hint
+            && self.args == other.args
+            && self.options == other.options
+            && self.print_pandoc_cmdline == other.print_pandoc_cmdline
+            && self.lossy_utf8 == other.lossy_utf8
+            && self.fail_on == other.fail_on
+            && self.infer_input_format == other.infer_input_format
+            && self.cache_dir == other.cache_dir
+            && self.reproducible == other.reproducible
+            && self.no_builtin_path_hints == other.no_builtin_path_hints
+            && self.timeout == other.timeout
+            && self.no_remote_inputs == other.no_remote_inputs
+            && self.filter_api_versions == other.filter_api_versions
+            && closures(self) == closures(other)
+    }
+}
+
+impl Eq for Pandoc {}
+
+impl std::hash::Hash for Pandoc {
+    fn hash<H: std::hash::Hasher>(&self, state: &mut H) {
+        self.input.hash(state);
+        self.input_format.hash(state);
+        self.output.hash(state);
+        self.output_format.hash(state);
+        self.output_targets.hash(state);
+        self.latex_path_hint.hash(state);
+        self.pandoc_path_hint.hash(state);
+        self.args.hash(state);
+        self.options.hash(state);
+        self.print_pandoc_cmdline.hash(state);
+        self.lossy_utf8.hash(state);
+        self.fail_on.hash(state);
+        self.infer_input_format.hash(state);
+        self.cache_dir.hash(state);
+        self.reproducible.hash(state);
+        self.no_builtin_path_hints.hash(state);
+        self.timeout.hash(state);
+        self.no_remote_inputs.hash(state);
+        self.filter_api_versions.hash(state);
+        let filters: Vec<_> = self.filters.iter().map(closure_ptr).collect();
+        filters.hash(state);
+        let preprocessors: Vec<_> = self.input_preprocessors.iter().map(closure_ptr).collect();
+        preprocessors.hash(state);
+        self.cmdline_callback.as_ref().map(closure_ptr).hash(state);
+    }
+}
+
+/// (de)serializes the raw arguments like strings, as they are usually valid UTF-8
+#[cfg(feature = "serde")]
+mod os_args {
+    use std::ffi::OsString;
+
+    pub fn serialize<S: serde::Serializer>(
+        args: &[(String, OsString)],
+        serializer: S,
+    ) -> Result<S::Ok, S::Error> {
+        use serde::ser::{Error, SerializeSeq};
+        let mut seq = serializer.serialize_seq(Some(args.len()))?;
+        for (key, value) in args {
+            let value = value
+                .to_str()
+                .ok_or_else(|| S::Error::custom("argument is not valid UTF-8"))?;
+            seq.serialize_element(&(key, value))?;
+        }
+        seq.end()
+    }
+
+    pub fn deserialize<'de, D: serde::Deserializer<'de>>(
+        deserializer: D,
+    ) -> Result<Vec<(String, OsString)>, D::Error> {
+        let args: Vec<(String, String)> = serde::Deserialize::deserialize(deserializer)?;
+        Ok(args
+            .into_iter()
+            .map(|(key, value)| (key, value.into()))
+            .collect())
+    }
+}
+
+/// receives the command-lines, see `Pandoc::set_cmdline_callback`
+type CmdlineCallback = Arc<dyn Fn(&str) + Send + Sync>;
+
+/// whether the dotted version number `version` is at least `minimum`
+fn version_at_least(version: &str, minimum: &[u32]) -> bool {
+    let version: Vec<u32> = version
+        .split('.')
+        .map(|part| part.parse().unwrap_or(0))
+        .collect();
+    version.as_slice() >= minimum
+}
+
+/// a filter transforming the JSON AST of the given `pandoc-api-version`, see
+/// `Pandoc::add_filter`
+type Filter = Arc<dyn Fn(&[u32], Vec<u8>) -> Result<Vec<u8>, PandocError> + Send + Sync>;
+
+/// run the filters in order, each one consuming the output of the previous one
+pub(crate) fn apply_filters(
+    filters: Vec<Filter>,
+    api_versions: &[(u32, u32)],
+    json: Vec<u8>,
+) -> Result<Vec<u8>, PandocError> {
+    let version = api_version(&json).unwrap_or_default();
+    let supported = api_versions
+        .iter()
+        .any(|&(major, minor)| version.starts_with(&[major, minor]));
+    if !api_versions.is_empty() && !supported {
+        return Err(PandocError::UnsupportedApiVersion(version));
+    }
+    filters
+        .into_iter()
+        .try_fold(json, |json, filter| filter(&version, json))
+}
+
+/// the `pandoc-api-version` of the JSON AST, e.g. `[1, 23, 1]`
+fn api_version(json: &[u8]) -> Option<Vec<u32>> {
+    let key = b"\"pandoc-api-version\"";
+    // pandoc writes the version first, so this doesn't scan the whole document
+    let start = json.windows(key.len()).position(|window| window == key)? + key.len();
+    let rest = &json[start..];
+    let open = rest.iter().position(|&b| b == b'[')?;
+    let close = rest.iter().position(|&b| b == b']')?;
+    std::str::from_utf8(rest.get(open + 1..close)?)
+        .ok()?
+        .split(',')
+        .map(|part| part.trim().parse().ok())
+        .collect()
+}
+
+/// the directories to search and the pandoc executable found in them
+struct ResolvedPaths {
+    search_path: OsString,
+    dirs: Vec<PathBuf>,
+    pandoc: Option<PathBuf>,
+}
+
+/// the path hints, whether the built-in hints are used, and the inherited `PATH`
+type PathCacheKey = (Vec<PathBuf>, Vec<PathBuf>, bool, Option<OsString>);
+
+fn path_cache() -> &'static Mutex<HashMap<PathCacheKey, Arc<ResolvedPaths>>> {
+    static CACHE: OnceLock<Mutex<HashMap<PathCacheKey, Arc<ResolvedPaths>>>> = OnceLock::new();
+    CACHE.get_or_init(Default::default)
+}
+
+/// the pandoc executable and a format
+type ExtensionCacheKey = (OsString, String);
+
+fn extension_cache() -> &'static Mutex<HashMap<ExtensionCacheKey, Arc<Vec<String>>>> {
+    static CACHE: OnceLock<Mutex<HashMap<ExtensionCacheKey, Arc<Vec<String>>>>> = OnceLock::new();
+    CACHE.get_or_init(Default::default)
+}
+
+/// the executable `program` in the first of `dirs` containing it
+fn find_program(dirs: &[PathBuf], program: &Path) -> Option<PathBuf> {
+    dirs.iter().find_map(|dir| {
+        let program = dir.join(program).with_extension(env::consts::EXE_EXTENSION);
+        Some(program).filter(|program| program.is_file())
+    })
+}
+
+/// Forget the locations of pandoc and the TeX distributions found so far.
+///
+/// The directories searched for pandoc and the programs it runs, including the scan for TeX
+/// distributions, are determined once per set of path hints and `PATH`. Call this after
+/// installing pandoc or a TeX distribution while the program is running. This also forgets
+/// the extensions the pandoc executables reported for their formats.
+pub fn clear_path_cache() {
+    path_cache()
+        .lock()
+        .unwrap_or_else(|e| e.into_inner())
+        .clear();
+    extension_cache()
+        .lock()
+        .unwrap_or_else(|e| e.into_inner())
+        .clear();
 }
 
 /// Convenience function to call Pandoc::new()
@@ -883,10 +2146,23 @@ impl Pandoc {
         self
     }
 
+    /// Only search the path hints and `PATH` for pandoc and the programs it runs.
+    ///
+    /// By default, well-known installation directories of pandoc and the TeX distributions
+    /// are searched, too. Hermetic build environments can disable them to make sure no
+    /// undeclared tool is picked up.
+    pub fn disable_builtin_path_hints(&mut self) -> &mut Pandoc {
+        self.no_builtin_path_hints = true;
+        self
+    }
+
     /// Add a path hint to search for the Pandoc executable.
     ///
     /// The supplied path is searched first for the Pandoc executable, then the environment variable `PATH`, then
     /// some hard-coded location hints.
+    ///
+    /// If the environment variable `PANDOC` is set to the path of an executable, that executable
+    /// is run instead, without any search.
     pub fn add_pandoc_path_hint<T: AsRef<Path> + ?Sized>(&mut self, path: &T) -> &mut Pandoc {
         self.pandoc_path_hint.push(path.as_ref().to_owned());
         self
@@ -901,25 +2177,73 @@ impl Pandoc {
         self
     }
 
+    /// Add an option of the document class, e.g. `11pt`, `twoside` or KOMA-Script's
+    /// `parskip=half`.
+    ///
+    /// Options accumulate, each is passed in the `classoption` variable of the LaTeX template.
+    pub fn add_class_option<T: AsRef<str> + ?Sized>(&mut self, option: &T) -> &mut Pandoc {
+        self.set_variable("classoption", option)
+    }
+
     /// Set whether Pandoc should print the used command-line
     ///
     /// If set to true, the command-line to execute pandoc (as a subprocess)
-    /// will be displayed on stdout.
+    /// will be displayed on stderr, so it does not mix with converted documents written to
+    /// stdout. With the `tracing` feature enabled, it is emitted as an info event instead. Use
+    /// [`set_cmdline_callback`](#method.set_cmdline_callback) to handle it yourself.
     pub fn set_show_cmdline(&mut self, flag: bool) -> &mut Pandoc {
         self.print_pandoc_cmdline = flag;
         self
     }
 
-    /// Set or overwrite the output format.
-    pub fn set_output_format(
-        &mut self,
-        format: OutputFormat,
-        extensions: Vec<MarkdownExtension>,
-    ) -> &mut Pandoc {
-        self.output_format = Some((format, extensions));
+    /// Pass the command-line of every pandoc invocation to `callback` instead of printing it.
+    ///
+    /// Implies [`set_show_cmdline(true)`](#method.set_show_cmdline).
+    pub fn set_cmdline_callback<F>(&mut self, callback: F) -> &mut Pandoc
+    where
+        F: 'static + Fn(&str) + Send + Sync,
+    {
+        self.print_pandoc_cmdline = true;
+        self.cmdline_callback = Some(Arc::new(callback));
+        self
+    }
+
+    /// Set whether invalid UTF-8 in pandoc's output should be replaced instead of failing
+    ///
+    /// By default, text output that is not valid UTF-8 results in a
+    /// `PandocError::BadUtf8Output` error carrying the raw bytes. If set to true, invalid
+    /// sequences are replaced with `U+FFFD REPLACEMENT CHARACTER` instead.
+    pub fn set_lossy_utf8(&mut self, flag: bool) -> &mut Pandoc {
+        self.lossy_utf8 = flag;
+        self
+    }
+
+    /// Treat warnings of the given class as errors.
+    ///
+    /// After pandoc ran successfully, its warnings are parsed from stderr. If any of them belongs
+    /// to a class passed to this function, `PandocError::Warnings` is returned instead of the
+    /// output. Unlike `--fail-if-warnings` this works with every pandoc version and can be
+    /// restricted to specific kinds of warnings. Can be called multiple times.
+    pub fn fail_on(&mut self, class: WarningClass) -> &mut Pandoc {
+        self.fail_on.push(class);
+        self
+    }
+
+    /// Set or overwrite the output format.
+    ///
+    /// Before running pandoc, the extensions are checked against the ones pandoc lists for
+    /// the format, see `PandocError::UnsupportedExtension`.
+    pub fn set_output_format(
+        &mut self,
+        format: OutputFormat,
+        extensions: Vec<MarkdownExtension>,
+    ) -> &mut Pandoc {
+        self.output_format = Some((format, extensions));
         self
     }
     /// Set or overwrite the input format
+    ///
+    /// The extensions are checked like the ones of `set_output_format`.
     pub fn set_input_format(
         &mut self,
         format: InputFormat,
@@ -929,6 +2253,17 @@ impl Pandoc {
         self
     }
 
+    /// Set whether the input format should be inferred from the input file extensions
+    ///
+    /// If set to true and no input format is set, the reader is chosen from the extensions of
+    /// the input files through `InputFormat::from_extension`, instead of letting pandoc fall
+    /// back to markdown for some inputs. Inputs whose extensions imply different readers result
+    /// in `PandocError::AmbiguousInputFormat` before pandoc is run.
+    pub fn set_infer_input_format(&mut self, flag: bool) -> &mut Pandoc {
+        self.infer_input_format = flag;
+        self
+    }
+
     /// Add additional input files
     ///
     /// The order of adding the files is the order in which they are processed, hence the order is
@@ -936,19 +2271,68 @@ impl Pandoc {
     /// This function does not work, if input has been already set to standard input using
     /// [`set_input`](#method.set_input_format).
     pub fn add_input<T: AsRef<Path> + ?Sized>(&mut self, filename: &T) -> &mut Pandoc {
-        let filename = filename.as_ref().to_owned();
-        match self.input {
-            Some(InputKind::Files(ref mut files)) => {
-                files.push(filename);
+        self.add_source(InputSource::File(filename.as_ref().to_owned()))
+    }
+
+    /// Add several input files at once, keeping their order.
+    ///
+    /// Equivalent to calling [`add_input`](#method.add_input) for every file.
+    pub fn add_inputs<I>(&mut self, filenames: I) -> &mut Pandoc
+    where
+        I: IntoIterator,
+        I::Item: AsRef<Path>,
+    {
+        for filename in filenames {
+            self.add_input(&filename);
+        }
+        self
+    }
+
+    /// Add a remote document as input, which is fetched by pandoc.
+    ///
+    /// URLs can be mixed with input files added through [`add_input`](#method.add_input), they
+    /// are processed in the order they were added.
+    /// This function does not work, if input has been already set to standard input using
+    /// [`set_input`](#method.set_input).
+    pub fn add_input_url<T: AsRef<str> + ?Sized>(&mut self, url: &T) -> &mut Pandoc {
+        self.add_source(InputSource::Url(url.as_ref().to_owned()))
+    }
+
+    /// Add all files matching a glob pattern (e.g. `"chapters/*.md"`) as input.
+    ///
+    /// The pattern is expanded when executing, the matching files are processed in sorted
+    /// order at the position the pattern was added.
+    /// This function does not work, if input has been already set to standard input using
+    /// [`set_input`](#method.set_input).
+    pub fn add_input_glob<T: AsRef<str> + ?Sized>(&mut self, pattern: &T) -> &mut Pandoc {
+        self.add_source(InputSource::Glob(pattern.as_ref().to_owned()))
+    }
+
+    fn add_source(&mut self, source: InputSource) -> &mut Pandoc {
+        let input = match (self.input.take(), source) {
+            (None, InputSource::File(file)) => InputKind::Files(vec![file]),
+            (None, source) => InputKind::Sources(vec![source]),
+            (Some(InputKind::Files(mut files)), InputSource::File(file)) => {
+                files.push(file);
+                InputKind::Files(files)
+            }
+            (Some(InputKind::Files(files)), source) => InputKind::Sources(
+                files
+                    .into_iter()
+                    .map(InputSource::File)
+                    .chain(std::iter::once(source))
+                    .collect(),
+            ),
+            (Some(InputKind::Sources(mut sources)), source) => {
+                sources.push(source);
+                InputKind::Sources(sources)
             }
-            Some(InputKind::Pipe(_)) => panic!(
+            (Some(_), _) => panic!(
                 "Input has been set to stdin already, \
                                             adding input file names is impossible"
             ),
-            None => {
-                self.input = Some(InputKind::Files(vec![filename]));
-            }
         };
+        self.input = Some(input);
         self
     }
 
@@ -969,12 +2353,33 @@ impl Pandoc {
         self
     }
 
+    /// Read the input from a document built in pandoc's AST, e.g. with a
+    /// [`DocumentBuilder`](doc/struct.DocumentBuilder.html).
+    ///
+    /// Sets the input to the JSON of the document and the input format to
+    /// `InputFormat::Json`.
+    #[cfg(feature = "ast")]
+    pub fn set_input_document(&mut self, document: &pandoc_ast::Pandoc) -> &mut Pandoc {
+        self.set_input(InputKind::Pipe(document.to_json()));
+        self.set_input_format(InputFormat::Json, Vec::new())
+    }
+
     /// Set or overwrite the output filename.
     pub fn set_output(&mut self, output: OutputKind) -> &mut Pandoc {
         self.output = Some(output);
         self
     }
 
+    /// Add an output target for [`execute_targets`](#method.execute_targets).
+    ///
+    /// Can be called multiple times to produce several outputs (e.g. HTML, PDF and EPUB) from
+    /// the same input. The output format and output kind set with `set_output_format` and
+    /// `set_output` are ignored by `execute_targets`.
+    pub fn add_output_target(&mut self, format: OutputFormat, output: OutputKind) -> &mut Pandoc {
+        self.output_targets.push((format, output));
+        self
+    }
+
     /// Set the file name of the bibliography database.
     pub fn set_bibliography<T: AsRef<Path> + ?Sized>(&mut self, filename: &T) -> &mut Pandoc {
         self.options
@@ -998,6 +2403,434 @@ impl Pandoc {
         self
     }
 
+    /// Enable the generation of a table of contents with the given depth, title and numbering.
+    ///
+    /// Replaces the table of contents options set before.
+    pub fn set_toc_config(&mut self, config: TocConfig) -> &mut Pandoc {
+        self.options.retain(|option| match *option {
+            PandocOption::TableOfContents | PandocOption::TableOfContentsDepth(_) => false,
+            PandocOption::Meta(ref key, _) => key != "toc-title",
+            _ => true,
+        });
+        self.options.push(PandocOption::TableOfContents);
+        if let Some(depth) = config.depth {
+            self.options.push(PandocOption::TableOfContentsDepth(depth));
+        }
+        if let Some(title) = config.title {
+            self.options
+                .push(PandocOption::Meta("toc-title".to_string(), Some(title)));
+        }
+        if config.numbered && !self.options.contains(&PandocOption::NumberSections) {
+            self.options.push(PandocOption::NumberSections);
+        }
+        self
+    }
+
+    /// Set the paper size, margins and orientation of LaTeX and ConTeXt output.
+    ///
+    /// The layout is passed in the template variables of the ConTeXt template if the output
+    /// format is `context` or ConTeXt is the PDF engine, and in the `papersize`, `geometry`
+    /// and `classoption` variables of the LaTeX template otherwise. Variables set by an
+    /// earlier layout, or by hand, are replaced.
+    pub fn set_page_layout(&mut self, layout: PageLayout) -> &mut Pandoc {
+        const SIDES: [&str; 4] = ["top", "right", "bottom", "left"];
+        self.options.retain(|option| match *option {
+            PandocOption::Var(ref key, ref value) => match key.as_str() {
+                "papersize" | "geometry" => false,
+                "classoption" => !matches!(value.as_deref(), Some("landscape") | Some("twocolumn")),
+                key => !SIDES.iter().any(|side| key == format!("margin-{}", side)),
+            },
+            _ => true,
+        });
+        let context = matches!(self.output_format, Some((OutputFormat::Context, _)))
+            || self.options.iter().any(|option| match *option {
+                PandocOption::PdfEngine(ref engine) => {
+                    engine.file_stem().and_then(|stem| stem.to_str()) == Some("context")
+                }
+                _ => false,
+            });
+        let margins = layout
+            .margins
+            .map(|margins| [margins.top, margins.right, margins.bottom, margins.left]);
+        if context {
+            if let Some(ref paper_size) = layout.paper_size {
+                self.set_variable("papersize", &paper_size.context_name());
+            }
+            if layout.landscape {
+                self.set_variable("papersize", "landscape");
+            }
+            for (side, margin) in SIDES.iter().zip(margins.iter().flatten()) {
+                self.set_variable(&format!("margin-{}", side), margin);
+            }
+        } else {
+            if let Some(ref paper_size) = layout.paper_size {
+                self.set_variable("papersize", &paper_size.to_string());
+            }
+            match margins {
+                Some(ref margins) if margins.iter().all(|margin| *margin == margins[0]) => {
+                    self.set_variable("geometry", &format!("margin={}", margins[0]));
+                }
+                Some(ref margins) => {
+                    for (side, margin) in SIDES.iter().zip(margins.iter()) {
+                        self.set_variable("geometry", &format!("{}={}", side, margin));
+                    }
+                }
+                None => {}
+            }
+            if layout.landscape {
+                self.set_variable("geometry", "landscape");
+            }
+            if layout.two_column {
+                self.set_variable("classoption", "twocolumn");
+            }
+        }
+        self
+    }
+
+    /// Set the fonts of LaTeX and ConTeXt output, replacing the fonts set before.
+    ///
+    /// Fonts other than the template's defaults need a PDF engine that loads system fonts:
+    /// fails with `PandocError::InvalidConfig` if `xelatex`, `lualatex`, `tectonic` or
+    /// `context` isn't set as `PandocOption::PdfEngine` beforehand, unless the output
+    /// format is `latex`, `beamer` or `context`.
+    pub fn set_fonts(&mut self, fonts: Fonts) -> Result<&mut Pandoc, PandocError> {
+        const UNICODE_ENGINES: [&str; 4] = ["xelatex", "lualatex", "tectonic", "context"];
+        let source_output = matches!(
+            self.output_format,
+            Some((OutputFormat::Latex, _))
+                | Some((OutputFormat::Beamer, _))
+                | Some((OutputFormat::Context, _))
+        );
+        if fonts.needs_unicode_engine() && !source_output {
+            let engine = self.options.iter().rev().find_map(|option| match *option {
+                PandocOption::PdfEngine(ref engine) => engine.file_stem()?.to_str(),
+                _ => None,
+            });
+            if !engine.is_some_and(|engine| UNICODE_ENGINES.contains(&engine)) {
+                return Err(PandocError::InvalidConfig(format!(
+                    "fonts can't be selected with the {} PDF engine, use xelatex or lualatex",
+                    engine.unwrap_or("default")
+                )));
+            }
+        }
+        let variables = fonts.variables();
+        self.options.retain(|option| match *option {
+            PandocOption::Var(ref key, _) => !matches!(
+                key.as_str(),
+                "mainfont" | "sansfont" | "monofont" | "mathfont" | "CJKmainfont" | "fontsize"
+            ),
+            _ => true,
+        });
+        for (key, value) in variables {
+            self.set_variable(key, value);
+        }
+        Ok(self)
+    }
+
+    /// Enable citation processing with the given bibliographies and style.
+    ///
+    /// Returns `PandocError::MissingInput` if one of the files can't be found. The `nocite`
+    /// entries are passed in a generated metadata file, as pandoc does not recognize
+    /// citations in metadata given on the command line.
+    pub fn set_citeproc(&mut self, config: CitationConfig) -> Result<&mut Pandoc, PandocError> {
+        let mut options = vec![PandocOption::Citeproc];
+        options.extend(
+            config
+                .bibliographies
+                .into_iter()
+                .map(PandocOption::Bibliography),
+        );
+        options.extend(config.csl.map(PandocOption::Csl));
+        if let Some(file) = options
+            .iter()
+            .filter_map(PandocOption::referenced_file)
+            .find(|file| !self.is_readable_resource(file))
+        {
+            return Err(PandocError::MissingInput(file.to_owned()));
+        }
+        if config.link_citations {
+            options.push(PandocOption::Meta(
+                "link-citations".to_string(),
+                Some("true".to_string()),
+            ));
+        }
+        if !config.nocite.is_empty() {
+            let keys: Vec<String> = config
+                .nocite
+                .iter()
+                .map(|key| format!("@{}", key))
+                .collect();
+            self.add_metadata_file(&format!("nocite: |\n  {}\n", keys.join(", ")))?;
+        }
+        self.options.extend(options);
+        Ok(self)
+    }
+
+    /// Number figures, tables, equations and sections with the pandoc-crossref filter.
+    ///
+    /// pandoc-crossref has to run before citation processing, otherwise references like
+    /// `@fig:plot` are treated as citations. The filter is therefore added before
+    /// `PandocOption::Citeproc`, regardless of whether [`set_citeproc`](#method.set_citeproc)
+    /// is called before or after this. Returns `PandocError::Unsupported` if the filter is not
+    /// installed.
+    pub fn enable_crossref(&mut self, config: CrossrefConfig) -> Result<&mut Pandoc, PandocError> {
+        let filter = match config.filter {
+            Some(ref filter) => Some(filter.clone()).filter(|filter| filter.is_file()),
+            None => find_program(&self.resolved_paths().dirs, Path::new("pandoc-crossref")),
+        };
+        let filter = filter.ok_or_else(|| {
+            PandocError::Unsupported("the pandoc-crossref filter is not installed".to_string())
+        })?;
+        let citeproc = self
+            .options
+            .iter()
+            .position(|option| *option == PandocOption::Citeproc)
+            .unwrap_or(self.options.len());
+        self.options.insert(citeproc, PandocOption::Filter(filter));
+        for (key, value) in config.metadata() {
+            self.options
+                .push(PandocOption::Meta(key.to_string(), Some(value)));
+        }
+        Ok(self)
+    }
+
+    /// Add a bibliography with the given CSL JSON entries, e.g. ones fetched with the
+    /// [`bib::fetch`](bib/fetch/index.html) functions.
+    ///
+    /// The entries are written to a generated bibliography file, which is removed once the
+    /// last clone of the builder is gone.
+    #[cfg(feature = "fetch")]
+    pub fn add_csl_bibliography(
+        &mut self,
+        entries: &[serde_json::Value],
+    ) -> Result<&mut Pandoc, PandocError> {
+        let file = TempPath::new(".json").map_err(PandocError::IoErr)?;
+        let json = serde_json::to_vec_pretty(entries)
+            .map_err(|e| PandocError::IoErr(std::io::Error::from(e)))?;
+        std::fs::write(&file, json).map_err(PandocError::IoErr)?;
+        self.options
+            .push(PandocOption::Bibliography(file.path().to_owned()));
+        self.temp_files.push(std::sync::Arc::new(file));
+        Ok(self)
+    }
+
+    /// Format the citations with the citation style `name` from the official style
+    /// repository, e.g. `ieee` or `apa`.
+    ///
+    /// The style is downloaded with [`bib::fetch::csl_style`](bib/fetch/fn.csl_style.html)
+    /// into the `csl` subdirectory of the [cache directory](#method.set_cache_dir), or of a
+    /// per-user cache directory if none is set. It replaces any style set before.
+    #[cfg(feature = "fetch")]
+    pub fn set_csl_style(&mut self, name: &str) -> Result<&mut Pandoc, PandocError> {
+        let cache = match self.cache_dir {
+            Some(ref dir) => dir.join("csl"),
+            None => bib::fetch::default_style_cache(),
+        };
+        let style = bib::fetch::csl_style(name, cache)?;
+        self.options
+            .retain(|option| !matches!(option, PandocOption::Csl(_)));
+        self.options.push(PandocOption::Csl(style));
+        Ok(self)
+    }
+
+    /// Add the snippets of `includes` to the header of standalone output.
+    ///
+    /// The snippets are written to a generated file passed with `--include-in-header`, after
+    /// the headers added before. Fails with `PandocError::InvalidConfig` if the output format
+    /// is set and writes its header in the other markup, e.g. LaTeX snippets for HTML output.
+    pub fn add_header_includes(
+        &mut self,
+        includes: &HeaderIncludes,
+    ) -> Result<&mut Pandoc, PandocError> {
+        if let Some((ref format, _)) = self.output_format {
+            if includes.conflicts_with(format) {
+                return Err(PandocError::InvalidConfig(format!(
+                    "{:?} header includes can't be used with {} output",
+                    includes.format(),
+                    format
+                )));
+            }
+        }
+        if includes.is_empty() {
+            return Ok(self);
+        }
+        let file = TempPath::new(includes.file_suffix()).map_err(PandocError::IoErr)?;
+        std::fs::write(&file, includes.contents()).map_err(PandocError::IoErr)?;
+        self.options
+            .push(PandocOption::IncludeInHeader(file.path().to_owned()));
+        self.temp_files.push(std::sync::Arc::new(file));
+        Ok(self)
+    }
+
+    /// Configure the HTML writers, HTML slide shows and EPUB.
+    ///
+    /// The stylesheets and files are added after the ones added before, the meta tags and
+    /// head fragments are passed like [`add_header_includes`](#method.add_header_includes).
+    /// Fails with `PandocError::InvalidConfig` if the output format is set to a LaTeX
+    /// format.
+    pub fn set_html_config(&mut self, config: HtmlConfig) -> Result<&mut Pandoc, PandocError> {
+        let mut head = HeaderIncludes::html();
+        for snippet in config.head_snippets() {
+            head.raw(&snippet);
+        }
+        self.add_header_includes(&head)?;
+        self.options
+            .extend(config.stylesheets.into_iter().map(PandocOption::Css));
+        if let Some(title) = config.toc_title {
+            self.set_variable("toc-title", &title);
+        }
+        if let Some(lang) = config.lang {
+            self.options
+                .push(PandocOption::Meta("lang".to_string(), Some(lang)));
+        }
+        if let Some(document_css) = config.document_css {
+            // pandoc parses `true` and `false` given with `--metadata` as booleans
+            self.options.push(PandocOption::Meta(
+                "document-css".to_string(),
+                Some(document_css.to_string()),
+            ));
+        }
+        self.options.extend(
+            config
+                .before_body
+                .into_iter()
+                .map(PandocOption::IncludeBeforeBody),
+        );
+        self.options.extend(
+            config
+                .after_body
+                .into_iter()
+                .map(PandocOption::IncludeAfterBody),
+        );
+        Ok(self)
+    }
+
+    /// pass `yaml` in a generated metadata file, for metadata that can't be given with `-M`
+    fn add_metadata_file(&mut self, yaml: &str) -> Result<(), PandocError> {
+        let file = TempPath::new(".yaml").map_err(PandocError::IoErr)?;
+        std::fs::write(&file, yaml).map_err(PandocError::IoErr)?;
+        self.options
+            .push(PandocOption::MetadataFile(file.path().to_owned()));
+        self.temp_files.push(std::sync::Arc::new(file));
+        Ok(())
+    }
+
+    /// run the input preprocessors, replacing input files by preprocessed temporary copies
+    pub(crate) fn apply_input_preprocessors(&mut self) -> Result<(), PandocError> {
+        let preprocessors = std::mem::take(&mut self.input_preprocessors);
+        if preprocessors.is_empty() {
+            return Ok(());
+        }
+        let apply = |text: String| preprocessors.iter().fold(text, |text, p| p(text));
+        let mut temp_files = Vec::new();
+        let mut copy = |file: &Path| -> Result<PathBuf, PandocError> {
+            let text = std::fs::read_to_string(file).map_err(PandocError::IoErr)?;
+            let suffix = file
+                .extension()
+                .map(|extension| format!(".{}", extension.to_string_lossy()))
+                .unwrap_or_default();
+            let temp = TempPath::new(&suffix).map_err(PandocError::IoErr)?;
+            std::fs::write(&temp, apply(text)).map_err(PandocError::IoErr)?;
+            let path = temp.path().to_owned();
+            temp_files.push(std::sync::Arc::new(temp));
+            Ok(path)
+        };
+        self.input = match self.input.take() {
+            Some(InputKind::Pipe(text)) => Some(InputKind::Pipe(apply(text))),
+            Some(InputKind::Files(files)) => Some(InputKind::Files(
+                files
+                    .iter()
+                    .map(|file| copy(file))
+                    .collect::<Result<_, _>>()?,
+            )),
+            Some(InputKind::Sources(sources)) => {
+                let mut copies = Vec::new();
+                for source in sources {
+                    match source {
+                        InputSource::File(file) => copies.push(InputSource::File(copy(&file)?)),
+                        InputSource::Glob(pattern) => {
+                            let mut matches = glob::glob(&pattern)
+                                .map_err(|_| PandocError::InvalidGlob(pattern.clone()))?
+                                .collect::<Result<Vec<_>, _>>()
+                                .map_err(|e| PandocError::IoErr(e.into()))?;
+                            matches.sort();
+                            for file in matches {
+                                copies.push(InputSource::File(copy(&file)?));
+                            }
+                        }
+                        url @ InputSource::Url(_) => copies.push(url),
+                    }
+                }
+                Some(InputKind::Sources(copies))
+            }
+            other => other,
+        };
+        self.temp_files.append(&mut temp_files);
+        Ok(())
+    }
+
+    /// replace the metadata `key` given with `-M` by `values`
+    fn set_meta(&mut self, key: &str, values: Vec<String>) -> &mut Pandoc {
+        self.options.retain(|option| match *option {
+            PandocOption::Meta(ref k, _) => k != key,
+            _ => true,
+        });
+        self.options.extend(
+            values
+                .into_iter()
+                .map(|value| PandocOption::Meta(key.to_string(), Some(value))),
+        );
+        self
+    }
+
+    /// Set the title of the document, overriding the title in the input.
+    pub fn set_title<T: AsRef<str> + ?Sized>(&mut self, title: &T) -> &mut Pandoc {
+        self.set_meta("title", vec![title.as_ref().to_owned()])
+    }
+
+    /// Set the authors of the document.
+    ///
+    /// Authors with affiliations or email addresses are passed in a generated metadata file, in
+    /// which case authors listed in the input take precedence, like for pandoc's
+    /// `--metadata-file`. Templates show the affiliations only if they support them.
+    pub fn set_authors(&mut self, authors: Vec<Author>) -> Result<&mut Pandoc, PandocError> {
+        match metadata::authors_yaml(&authors) {
+            Some(yaml) => {
+                self.set_meta("author", Vec::new());
+                self.add_metadata_file(&yaml)?;
+            }
+            None => {
+                self.set_meta(
+                    "author",
+                    authors.into_iter().map(|author| author.name).collect(),
+                );
+            }
+        }
+        Ok(self)
+    }
+
+    /// Set the date of the document, overriding the date in the input.
+    pub fn set_date<T: AsRef<str> + ?Sized>(&mut self, date: &T) -> &mut Pandoc {
+        self.set_meta("date", vec![date.as_ref().to_owned()])
+    }
+
+    /// Set the language of the document as BCP 47 tag, e.g. `en-GB`.
+    ///
+    /// Pandoc uses the language for hyphenation, localized strings like "Contents" and
+    /// the `lang` attribute of HTML output.
+    pub fn set_lang<T: AsRef<str> + ?Sized>(&mut self, lang: &T) -> &mut Pandoc {
+        self.set_meta("lang", vec![lang.as_ref().to_owned()])
+    }
+
+    /// Set the keywords of the document, used in the metadata of HTML, PDF and docx output.
+    pub fn set_keywords<I>(&mut self, keywords: I) -> &mut Pandoc
+    where
+        I: IntoIterator,
+        I::Item: Into<String>,
+    {
+        self.set_meta("keywords", keywords.into_iter().map(Into::into).collect())
+    }
+
     /// Treat top-level headers as chapters in LaTeX, ConTeXt, and DocBook output.
     pub fn set_chapters(&mut self) -> &mut Pandoc {
         self.options
@@ -1043,124 +2876,720 @@ impl Pandoc {
         self
     }
 
+    /// Cache the outputs of conversions in `dir`, skipping conversions that ran before.
+    ///
+    /// The cache key is a hash of the contents of the input files, of the files referenced
+    /// by the options, like templates and bibliographies, of all other options and of the
+    /// version of pandoc. Files the document itself references, like images, are not part of
+    /// the key. Conversions with filters or input preprocessors, of remote inputs or to other
+    /// outputs than files and pipes are not cached, neither are conversions pandoc warned
+    /// about. Entries are never removed, the directory
+    /// can be deleted at any time.
+    pub fn set_cache_dir<T: AsRef<Path> + ?Sized>(&mut self, dir: &T) -> &mut Pandoc {
+        self.cache_dir = Some(dir.as_ref().to_owned());
+        self
+    }
+
+    /// Produce the same output on every run with the same inputs.
+    ///
+    /// Runs pandoc and the PDF engines with `SOURCE_DATE_EPOCH` set, which pandoc uses for
+    /// the timestamps in docx, EPUB, ODT and PowerPoint files, and LaTeX for the dates in PDF
+    /// files. The `SOURCE_DATE_EPOCH` of the current process is passed on, it defaults to
+    /// 1970-01-01. Documents without a `date` get the date of `SOURCE_DATE_EPOCH`, so formats
+    /// defaulting to the current date, like EPUB, don't change daily.
+    pub fn set_reproducible(&mut self, flag: bool) -> &mut Pandoc {
+        self.reproducible = flag;
+        self
+    }
+
+    /// Kill pandoc if it runs longer than `timeout`, returning `PandocError::Timeout`.
+    ///
+    /// The timeout applies to every pandoc process of the conversion, the pass producing the
+    /// input of the filters has its own deadline. Programs started by pandoc, like PDF
+    /// engines, are not killed.
+    pub fn set_timeout(&mut self, timeout: std::time::Duration) -> &mut Pandoc {
+        self.timeout = Some(timeout);
+        self
+    }
+
+    /// Restrict the conversion for processing untrusted input.
+    ///
+    /// Following [pandoc's security notes](https://pandoc.org/MANUAL.html#a-note-on-security),
+    /// this
+    ///
+    /// * adds `PandocOption::Sandbox`, so pandoc reads no other files than the inputs and
+    ///   makes no network requests,
+    /// * limits the heap to 512 MB, unless a limit was set with
+    ///   `PandocRuntimeSystemOption::MaximumHeapMemory`,
+    /// * kills pandoc after 10 seconds, unless a timeout was set with
+    ///   [`set_timeout`](#method.set_timeout),
+    /// * removes all `PandocOption::ResourcePath` options,
+    /// * makes the execution fail with `PandocError::Unsupported` if a URL is added as input.
+    ///
+    /// The restrictions also apply to the pass producing the input of the filters. Runs the
+    /// installed pandoc to check its version, and returns `PandocError::Unsupported` if it
+    /// is too old to support `--sandbox`.
+    pub fn hardened_for_untrusted_input(&mut self) -> Result<&mut Pandoc, PandocError> {
+        let version = self.version()?;
+        if !version_at_least(&version, &[2, 15]) {
+            return Err(PandocError::Unsupported(format!(
+                "pandoc {} does not support --sandbox, 2.15 or later is required",
+                version
+            )));
+        }
+        self.options
+            .retain(|option| !matches!(option, PandocOption::ResourcePath(_)));
+        if !self.options.contains(&PandocOption::Sandbox) {
+            self.options.push(PandocOption::Sandbox);
+        }
+        let heap_limited = self.options.iter().any(|option| match *option {
+            PandocOption::RuntimeSystem(ref rts) => rts
+                .iter()
+                .any(|o| matches!(o, PandocRuntimeSystemOption::MaximumHeapMemory(_))),
+            _ => false,
+        });
+        if !heap_limited {
+            self.options.push(PandocOption::RuntimeSystem(vec![
+                PandocRuntimeSystemOption::MaximumHeapMemory("512M".to_string()),
+            ]));
+        }
+        self.timeout
+            .get_or_insert(std::time::Duration::from_secs(10));
+        self.no_remote_inputs = true;
+        Ok(self)
+    }
+
     /// Add a Pandoc filter.
     ///
     /// Pandoc parses any of the supported input formats to an abstract syntax tree (AST). If a
     /// filter is specified, it will receive a JSON representation of this AST and can transform it
     /// to its liking and add/modify/remove elements. The output is then passed back to Pandoc.
     ///
-    /// The provided filter function must live at least as long as the Pandoc instance,
-    /// which will typically be achieved by making it a function, or else a closure which
-    /// does not attempt to hold references to anything which isn't `'static`.
-    pub fn add_filter<F>(&mut self, filter: F) -> &mut Pandoc
-    where
-        F: 'static + Fn(String) -> String,
-    {
-        self.filters.push(Rc::new(filter));
-        self
+    /// The provided filter function must live at least as long as the Pandoc instance,
+    /// which will typically be achieved by making it a function, or else a closure which
+    /// does not attempt to hold references to anything which isn't `'static`. Like all
+    /// closures given to the builder, it has to be `Send + Sync`, so configurations can be
+    /// shared between threads.
+    pub fn add_filter<F>(&mut self, filter: F) -> &mut Pandoc
+    where
+        F: 'static + Fn(String) -> String + Send + Sync,
+    {
+        self.filters.push(Arc::new(move |_, json| {
+            Ok(filter(String::from_utf8(json)?).into_bytes())
+        }));
+        self
+    }
+
+    /// Add a Pandoc filter that is also given the `pandoc-api-version` of the AST, e.g.
+    /// `[1, 23, 1]`.
+    ///
+    /// The structure of the JSON AST changes between pandoc releases, the version allows
+    /// filters to handle the differences. See also
+    /// [`require_filter_api_version`](#method.require_filter_api_version).
+    pub fn add_versioned_filter<F>(&mut self, filter: F) -> &mut Pandoc
+    where
+        F: 'static + Fn(&[u32], String) -> String + Send + Sync,
+    {
+        self.filters.push(Arc::new(move |version, json| {
+            Ok(filter(version, String::from_utf8(json)?).into_bytes())
+        }));
+        self
+    }
+
+    /// Only run the filters on ASTs with the `pandoc-api-version` `major.minor`.
+    ///
+    /// Can be called multiple times to accept several versions. If the installed pandoc
+    /// produces an AST of another version, the conversion fails with
+    /// `PandocError::UnsupportedApiVersion` before any filter runs, instead of a filter
+    /// failing to deserialize the AST.
+    pub fn require_filter_api_version(&mut self, major: u32, minor: u32) -> &mut Pandoc {
+        self.filter_api_versions.push((major, minor));
+        self
+    }
+
+    /// Add a Pandoc filter reading the JSON AST from a reader and writing the result to a
+    /// writer.
+    ///
+    /// Works like [`add_filter`](#method.add_filter), but the filter reads the AST pandoc
+    /// produced and writes its result as bytes, so neither has to be valid UTF-8 or converted
+    /// into a `String`, and the filter can process the AST with a streaming JSON parser. Both
+    /// the AST and the filter's output are still held in memory in full:
+    ///
+    /// ```no_run
+    /// let mut pandoc = pandoc::new();
+    /// pandoc.add_stream_filter(|ast, out| std::io::copy(ast, out).map(drop));
+    /// ```
+    pub fn add_stream_filter<F>(&mut self, filter: F) -> &mut Pandoc
+    where
+        F: 'static + Fn(&mut dyn Read, &mut dyn Write) -> std::io::Result<()> + Send + Sync,
+    {
+        self.filters.push(Arc::new(move |_, json| {
+            let mut filtered = Vec::new();
+            filter(&mut json.as_slice(), &mut filtered).map_err(PandocError::IoErr)?;
+            Ok(filtered)
+        }));
+        self
+    }
+
+    /// Add a function transforming the input text before pandoc reads it.
+    ///
+    /// Unlike filters, preprocessors see the raw input, e.g. to expand custom shortcodes or to
+    /// strip front matter pandoc doesn't understand. They are applied in the order they were
+    /// added, to piped input and to every local input file. Files are replaced by
+    /// preprocessed temporary copies with the same extension, so the input files have to be
+    /// valid UTF-8, and images and other files they reference by relative paths are looked
+    /// up relative to the working directory (pandoc's default) and not to the input file.
+    pub fn add_input_preprocessor<F>(&mut self, preprocessor: F) -> &mut Pandoc
+    where
+        F: 'static + Fn(String) -> String + Send + Sync,
+    {
+        self.input_preprocessors.push(Arc::new(preprocessor));
+        self
+    }
+
+    /// Add a [PandocOption](PandocOption.t.html).
+    pub fn add_option(&mut self, option: PandocOption) -> &mut Pandoc {
+        self.options.push(option);
+        self
+    }
+
+    pub fn add_options(&mut self, options: &[PandocOption]) -> &mut Pandoc {
+        self.options.extend_from_slice(options);
+        self
+    }
+
+    /// `chunkedhtml` writes directories, unless the output file is a zip archive
+    fn infer_directory_output(&mut self) {
+        match (&self.output_format, self.output.take()) {
+            (Some((OutputFormat::ChunkedHtml, _)), Some(OutputKind::File(file)))
+                if file.extension() != Some("zip".as_ref()) =>
+            {
+                self.output = Some(OutputKind::Directory(file));
+            }
+            (None, Some(OutputKind::Directory(dir))) => {
+                self.output_format = Some((OutputFormat::ChunkedHtml, Vec::new()));
+                self.output = Some(OutputKind::Directory(dir));
+            }
+            (_, output) => self.output = output,
+        }
+    }
+
+    /// set the input format from the input file extensions, see `set_infer_input_format`
+    fn infer_input_format(&mut self) -> Result<(), PandocError> {
+        if !self.infer_input_format || self.input_format.is_some() {
+            return Ok(());
+        }
+        let files: Vec<&Path> = match self.input {
+            Some(InputKind::Files(ref files)) => files.iter().map(PathBuf::as_path).collect(),
+            Some(InputKind::Sources(ref sources)) => sources
+                .iter()
+                .filter_map(|source| match *source {
+                    InputSource::File(ref file) => Some(file.as_path()),
+                    InputSource::Glob(ref pattern) => Some(Path::new(pattern)),
+                    InputSource::Url(_) => None,
+                })
+                .collect(),
+            _ => Vec::new(),
+        };
+        let mut inferred: Option<(&Path, InputFormat)> = None;
+        for file in files {
+            let format = match file
+                .extension()
+                .and_then(OsStr::to_str)
+                .and_then(InputFormat::from_extension)
+            {
+                Some(format) => format,
+                None => continue,
+            };
+            match inferred {
+                None => inferred = Some((file, format)),
+                Some((first, ref first_format)) => {
+                    if first_format.to_string() != format.to_string() {
+                        return Err(PandocError::AmbiguousInputFormat(
+                            first.to_owned(),
+                            file.to_owned(),
+                        ));
+                    }
+                }
+            }
+        }
+        self.input_format = inferred.map(|(_, format)| (format, Vec::new()));
+        Ok(())
+    }
+
+    /// whether a file referenced by an option can be read, pandoc also searches the resource
+    /// path for these files
+    fn is_readable_resource(&self, file: &Path) -> bool {
+        let readable = |path: &Path| std::fs::File::open(path).is_ok();
+        readable(file)
+            || (file.is_relative()
+                && self
+                    .options
+                    .iter()
+                    .filter_map(|option| match *option {
+                        PandocOption::ResourcePath(ref paths) => Some(paths),
+                        _ => None,
+                    })
+                    .flatten()
+                    .any(|dir| readable(&dir.join(file))))
+    }
+
+    /// check that all input files and files referenced by options can be read, and the
+    /// extensions if an executor to run pandoc with is given
+    fn validate(&self, executor: Option<&dyn PandocExecutor>) -> Result<(), PandocError> {
+        if let (true, Some(InputKind::Sources(ref sources))) = (self.no_remote_inputs, &self.input)
+        {
+            if let Some(url) = sources.iter().find_map(|source| match *source {
+                InputSource::Url(ref url) => Some(url),
+                _ => None,
+            }) {
+                return Err(PandocError::Unsupported(format!(
+                    "the remote input {} is not allowed for untrusted input",
+                    url
+                )));
+            }
+        }
+        let readable = |path: &Path| std::fs::File::open(path).is_ok();
+        let inputs: Vec<&Path> = match self.input {
+            Some(InputKind::Files(ref files)) => files.iter().map(PathBuf::as_path).collect(),
+            Some(InputKind::Sources(ref sources)) => sources
+                .iter()
+                .filter_map(|source| match *source {
+                    InputSource::File(ref file) => Some(file.as_path()),
+                    _ => None,
+                })
+                .collect(),
+            _ => Vec::new(),
+        };
+        if let Some(input) = inputs.into_iter().find(|input| !readable(input)) {
+            return Err(PandocError::MissingInput(input.to_owned()));
+        }
+        if let Some(executor) = executor {
+            self.validate_extensions(executor)?;
+        }
+        if let Some((InputFormat::Lua(ref script), _)) = self.input_format {
+            if !readable(script) {
+                return Err(PandocError::MissingInput(script.clone()));
+            }
+        }
+        if let Some((OutputFormat::Lua(ref script), _)) = self.output_format {
+            // pandoc looks up bare names in the `custom` directory of its data directory
+            let script = Path::new(script);
+            if script.components().count() > 1 && !readable(script) {
+                return Err(PandocError::MissingInput(script.to_owned()));
+            }
+        }
+        for file in self
+            .options
+            .iter()
+            .filter_map(PandocOption::referenced_file)
+        {
+            if !self.is_readable_resource(file) {
+                return Err(PandocError::MissingInput(file.to_owned()));
+            }
+        }
+        Ok(())
+    }
+
+    /// check that the extensions of the reader and writer are ones pandoc lists for them
+    ///
+    /// The extensions are listed by running pandoc through `executor`. Pandoc's own error for
+    /// an invalid extension only names the format. If pandoc can't list the extensions, e.g.
+    /// because it is missing or for custom Lua formats, the extensions are passed on
+    /// unchecked.
+    fn validate_extensions(&self, executor: &dyn PandocExecutor) -> Result<(), PandocError> {
+        let input = match self.input_format {
+            Some((InputFormat::Lua(_), _)) | None => None,
+            Some((ref format, ref extensions)) => Some((format.to_string(), extensions)),
+        };
+        let output = match self.output_format {
+            Some((OutputFormat::Lua(_), _)) | None => None,
+            Some((ref format, ref extensions)) => Some((format.to_string(), extensions)),
+        };
+        for (format, extensions) in input.into_iter().chain(output) {
+            if extensions.is_empty() {
+                continue;
+            }
+            let supported = match self.list_extensions(&format, executor) {
+                Some(supported) => supported,
+                None => continue,
+            };
+            for extension in extensions {
+                let name = match *extension {
+                    MarkdownExtension::Disable(ref extension) => extension.to_string(),
+                    ref extension => extension.to_string(),
+                };
+                if !supported.contains(&name) {
+                    return Err(PandocError::UnsupportedExtension(name, format));
+                }
+            }
+        }
+        Ok(())
+    }
+
+    /// the names of the extensions pandoc lists for `format`, see `clear_path_cache`
+    fn list_extensions(
+        &self,
+        format: &str,
+        executor: &dyn PandocExecutor,
+    ) -> Option<Arc<Vec<String>>> {
+        let mut cmd = self.command();
+        let key = (cmd.get_program().to_owned(), format.to_string());
+        let cached = executor.caches_queries();
+        if let Some(extensions) = extension_cache()
+            .lock()
+            .unwrap_or_else(|e| e.into_inner())
+            .get(&key)
+            .filter(|_| cached)
+        {
+            return Some(Arc::clone(extensions));
+        }
+        cmd.arg(format!("--list-extensions={}", format))
+            .stdout(std::process::Stdio::piped())
+            .stderr(std::process::Stdio::piped());
+        let output = executor.execute(Invocation::new(cmd, None)).ok()?;
+        if !output.status.success() {
+            return None;
+        }
+        // one extension per line, prefixed with `+` if it's enabled by default and `-` if not
+        let extensions: Vec<String> = String::from_utf8_lossy(&output.stdout)
+            .lines()
+            .map(|line| line.trim().trim_start_matches(['+', '-']).to_string())
+            .filter(|name| !name.is_empty())
+            .collect();
+        if extensions.is_empty() {
+            return None;
+        }
+        let extensions = Arc::new(extensions);
+        if cached {
+            extension_cache()
+                .lock()
+                .unwrap_or_else(|e| e.into_inner())
+                .insert(key, Arc::clone(&extensions));
+        }
+        Some(extensions)
+    }
+
+    /// the input files, with glob patterns expanded
+    pub(crate) fn input_files(&self) -> Vec<PathBuf> {
+        let mut files: Vec<PathBuf> = Vec::new();
+        match self.input {
+            Some(InputKind::Files(ref inputs)) => files.extend(inputs.iter().cloned()),
+            Some(InputKind::Sources(ref sources)) => {
+                for source in sources {
+                    match *source {
+                        InputSource::File(ref file) => files.push(file.clone()),
+                        InputSource::Glob(ref pattern) => {
+                            files.extend(glob::glob(pattern).into_iter().flatten().flatten())
+                        }
+                        InputSource::Url(_) => {}
+                    }
+                }
+            }
+            _ => {}
+        }
+        files
+    }
+
+    /// the input files and the files referenced by the options
+    pub(crate) fn dependencies(&self) -> Vec<PathBuf> {
+        let mut files = self.input_files();
+        files.extend(
+            self.options
+                .iter()
+                .filter_map(PandocOption::referenced_file)
+                .map(Path::to_owned),
+        );
+        files
+    }
+
+    /// the search path and the pandoc executable for the path hints, see `clear_path_cache`
+    fn resolved_paths(&self) -> Arc<ResolvedPaths> {
+        let key = (
+            self.latex_path_hint.clone(),
+            self.pandoc_path_hint.clone(),
+            self.no_builtin_path_hints,
+            env::var_os("PATH"),
+        );
+        let mut cache = path_cache().lock().unwrap_or_else(|e| e.into_inner());
+        Arc::clone(
+            cache
+                .entry(key)
+                .or_insert_with(|| Arc::new(self.resolve_paths())),
+        )
+    }
+
+    /// The path hints come first, then the `PATH` of the current process, if it is set, and
+    /// then the built-in locations.
+    fn resolve_paths(&self) -> ResolvedPaths {
+        let inherited = env::var_os("PATH").unwrap_or_default();
+        let builtin = if self.no_builtin_path_hints {
+            Vec::new()
+        } else {
+            PANDOC_PATH
+                .iter()
+                .chain(LATEX_PATH.iter())
+                .map(PathBuf::from)
+                .chain(latex::tex_dirs())
+                .collect()
+        };
+        let dirs: Vec<PathBuf> = self
+            .latex_path_hint
+            .iter()
+            .chain(self.pandoc_path_hint.iter())
+            .cloned()
+            .chain(env::split_paths(&inherited))
+            .chain(builtin)
+            // directories containing the delimiter can't be part of `PATH`
+            .filter(|dir| env::join_paths(std::iter::once(dir)).is_ok())
+            .collect();
+        let pandoc = find_program(&dirs, Path::new("pandoc"));
+        ResolvedPaths {
+            search_path: env::join_paths(&dirs)
+                .expect("directories containing the delimiter were removed"),
+            dirs,
+            pandoc,
+        }
+    }
+
+    /// the `PATH` pandoc and the programs it starts are run with
+    fn search_path(&self) -> OsString {
+        self.resolved_paths().search_path.clone()
+    }
+
+    /// The executable of the PDF engine pandoc runs, if it can be found.
+    ///
+    /// This is the program set with `PandocOption::PdfEngine`, or pandoc's default
+    /// `pdflatex`, looked up in the same directories pandoc searches. Useful to log which
+    /// engine, and which TeX distribution, will produce the PDF.
+    pub fn resolve_pdf_engine(&self) -> Option<PathBuf> {
+        let engine = self
+            .options
+            .iter()
+            .rev()
+            .find_map(|option| match *option {
+                PandocOption::PdfEngine(ref engine) => Some(engine.as_path()),
+                _ => None,
+            })
+            .unwrap_or_else(|| Path::new("pdflatex"));
+        if engine.components().count() > 1 {
+            return Some(engine.to_owned()).filter(|engine| engine.is_file());
+        }
+        find_program(&self.resolved_paths().dirs, engine)
     }
 
-    /// Add a [PandocOption](PandocOption.t.html).
-    pub fn add_option(&mut self, option: PandocOption) -> &mut Pandoc {
-        self.options.push(option);
-        self
+    /// the pandoc command, without any arguments
+    fn command(&self) -> Command {
+        let paths = self.resolved_paths();
+        let program = env::var_os("PANDOC")
+            .filter(|program| !program.is_empty())
+            .or_else(|| paths.pandoc.clone().map(PathBuf::into_os_string))
+            // not installed, running it fails with `PandocError::PandocNotFound`
+            .unwrap_or_else(|| "pandoc".into());
+        let mut cmd = Command::new(program);
+        cmd.env("PATH", &paths.search_path);
+        if self.reproducible {
+            cmd.env("SOURCE_DATE_EPOCH", source_date_epoch().to_string());
+            // makes pdfTeX and LuaTeX use it for `\today`, too
+            cmd.env("FORCE_SOURCE_DATE", "1");
+        }
+        cmd
     }
 
-    pub fn add_options(&mut self, options: &[PandocOption]) -> &mut Pandoc {
-        self.options.extend_from_slice(options);
-        self
+    /// the version of the pandoc `command` runs, e.g. `3.1.2`
+    pub(crate) fn version(&self) -> Result<String, PandocError> {
+        let mut cmd = self.command();
+        cmd.arg("--version")
+            .stdout(std::process::Stdio::piped())
+            .stderr(std::process::Stdio::piped());
+        let output = ProcessExecutor.execute(Invocation::new(cmd, None))?;
+        let version = String::from_utf8_lossy(&output.stdout)
+            .lines()
+            .next()
+            .and_then(|first| first.split_whitespace().nth(1))
+            .map(str::to_owned);
+        match version {
+            Some(version) if output.status.success() => Ok(version),
+            _ => Err(PandocError::Err(output)),
+        }
     }
 
-    fn run(self) -> Result<Vec<u8>, PandocError> {
-        let mut cmd = Command::new("pandoc");
-        if let Some((ref format, ref extensions)) = self.input_format {
-            use std::fmt::Write;
-            let mut arg = format.to_string();
-            for extension in extensions {
-                write!(arg, "+{}", extension).unwrap();
+    /// run pandoc, returns its output and the full command line used
+    fn run(
+        self,
+        executor: &dyn PandocExecutor,
+    ) -> Result<(std::process::Output, Vec<OsString>), PandocError> {
+        let (cmd, input, context) = self.prepare(Some(executor))?;
+        context.run(cmd, input, executor)
+    }
+
+    /// validate the configuration and assemble the pandoc command, returns it with the data
+    /// for its stdin
+    fn prepare(
+        mut self,
+        executor: Option<&dyn PandocExecutor>,
+    ) -> Result<(Command, Option<Vec<u8>>, RunContext), PandocError> {
+        self.infer_input_format()?;
+        self.validate(executor)?;
+        self.apply_input_preprocessors()?;
+        let sandboxed = self.options.contains(&PandocOption::Sandbox);
+        let has_date = self.options.iter().any(|option| match *option {
+            PandocOption::Meta(ref key, _) => key == "date",
+            _ => false,
+        });
+        if self.reproducible && !has_date {
+            // the document's own date takes precedence over a metadata file
+            self.add_metadata_file(&format!("date: \"{}\"\n", iso_date(source_date_epoch())))?;
+        }
+        let mut cmd = self.command();
+        match self.input_format {
+            // the path of the reader does not have to be valid UTF-8
+            Some((InputFormat::Lua(ref script), ref extensions)) => {
+                let mut arg = script.as_os_str().to_owned();
+                arg.push(format_arg(&"", extensions));
+                cmd.arg("-f").arg(arg);
             }
-            cmd.arg("-f").arg(arg);
+            Some((ref format, ref extensions)) => {
+                cmd.arg("-f").arg(format_arg(format, extensions));
+            }
+            None => {}
         }
         for (key, val) in self.args {
-            cmd.arg(format!("--{}={}", key, val));
+            cmd.arg(path_arg(&format!("--{}=", key), &val));
         }
-        let path: String = Itertools::intersperse(
-            self.latex_path_hint
-                .iter()
-                .chain(self.pandoc_path_hint.iter())
-                .map(|p| p.to_str().expect("non-utf8 path"))
-                .chain(PANDOC_PATH.iter().cloned())
-                .chain(LATEX_PATH.iter().cloned())
-                .chain(
-                    [env::var("PATH").unwrap()]
-                        .iter()
-                        .map(std::borrow::Borrow::borrow),
-                ),
-            PATH_DELIMIT,
-        )
-        .collect();
-        cmd.env("PATH", path);
         let output = self.output.ok_or(PandocError::NoOutputSpecified)?;
+        let produces_pdf = match (&self.output_format, &output) {
+            (Some((OutputFormat::Pdf, _)), _) => true,
+            (None, OutputKind::File(ref file)) => file.extension() == Some("pdf".as_ref()),
+            _ => false,
+        };
         let input = self.input.ok_or(PandocError::NoInputSpecified)?;
         let input = match input {
             InputKind::Files(files) => {
                 for file in files {
-                    cmd.arg(file);
+                    cmd.arg(long_path(&file).as_os_str());
+                }
+                None
+            }
+            InputKind::Sources(sources) => {
+                let mut empty = true;
+                for source in sources {
+                    match source {
+                        InputSource::File(file) => {
+                            cmd.arg(long_path(&file).as_os_str());
+                        }
+                        InputSource::Url(url) => {
+                            cmd.arg(url);
+                        }
+                        InputSource::Glob(pattern) => {
+                            let mut files = glob::glob(&pattern)
+                                .map_err(|_| PandocError::InvalidGlob(pattern.clone()))?
+                                .collect::<Result<Vec<_>, _>>()
+                                .map_err(|e| PandocError::IoErr(e.into()))?;
+                            files.sort();
+                            if files.is_empty() {
+                                continue;
+                            }
+                            for file in files {
+                                cmd.arg(long_path(&file).as_os_str());
+                            }
+                        }
+                    }
+                    empty = false;
+                }
+                // pandoc would wait for input on stdin otherwise
+                if empty {
+                    return Err(PandocError::NoInputSpecified);
                 }
-                String::new()
+                None
             }
             InputKind::Pipe(text) => {
                 cmd.stdin(std::process::Stdio::piped());
-                text
+                Some(text.into_bytes())
+            }
+            InputKind::Handle(handle) => {
+                cmd.stdin(handle.take().ok_or(PandocError::NoInputSpecified)?);
+                None
+            }
+            InputKind::InheritStdin => {
+                cmd.stdin(std::process::Stdio::inherit());
+                None
             }
         };
         match output {
-            OutputKind::File(filename) => {
-                cmd.arg("-o").arg(filename);
+            OutputKind::File(filename) | OutputKind::Directory(filename) => {
+                cmd.arg("-o").arg(long_path(&filename).as_os_str());
             }
-            OutputKind::Pipe => {
+            // `execute` replaces temporary files by a `File` output, so this is only reachable
+            // through `generate_latex_template`, which only looks at stdout
+            OutputKind::Pipe | OutputKind::TempFile(_) | OutputKind::Tee(_) => {
                 match self.output_format {
-                    Some((OutputFormat::Pdf, ..)) => {
+                    Some((ref format, ..)) if format.is_binary() => {
                         cmd.arg("-o").arg("-").stdout(std::process::Stdio::piped())
                     }
                     _ => cmd.stdout(std::process::Stdio::piped()),
                 };
             }
+            OutputKind::InheritStdout => {
+                if let Some((ref format, ..)) = self.output_format {
+                    if format.is_binary() {
+                        cmd.arg("-o").arg("-");
+                    }
+                }
+                cmd.stdout(std::process::Stdio::inherit());
+            }
         }
 
         // always capture stderr
         cmd.stderr(std::process::Stdio::piped());
 
         if let Some((ref format, ref extensions)) = self.output_format {
-            use std::fmt::Write;
-            let mut arg = format.to_string();
-            for extension in extensions {
-                write!(arg, "+{}", extension).unwrap();
-            }
-            cmd.arg("-t").arg(arg);
+            cmd.arg("-t").arg(format_arg(format, extensions));
         }
 
         for opt in self.options {
             opt.apply(&mut cmd);
         }
-        if self.print_pandoc_cmdline {
-            println!("{:?}", cmd);
-        }
-        let mut child = cmd.spawn()?;
-        if let Some(ref mut stdin) = child.stdin {
-            stdin.write_all(input.as_bytes())?;
+        let context = RunContext {
+            timeout: self.timeout,
+            produces_pdf,
+            sandboxed,
+            print_pandoc_cmdline: self.print_pandoc_cmdline,
+            cmdline_callback: self.cmdline_callback,
+            _temp_files: self.temp_files,
+        };
+        Ok((cmd, input, context))
+    }
+
+    /// Check whether the installed pandoc can write `format` with `PandocOption::Sandbox`.
+    ///
+    /// Writers of formats like docx, odt and epub read files from pandoc's data directory,
+    /// which `--sandbox` only allows if pandoc was compiled with the `embed_data_files` flag,
+    /// as the official releases are. Otherwise sandboxed conversions to these formats fail
+    /// with `PandocError::SandboxUnsupported`. This converts a short document to `format` to
+    /// find out up front.
+    pub fn sandbox_supported(&self, format: &OutputFormat) -> Result<bool, PandocError> {
+        let mut probe = self.child();
+        probe
+            .set_input(InputKind::Pipe("probe".to_string()))
+            .set_input_format(InputFormat::Markdown, Vec::new())
+            .set_output_format(format.clone(), Vec::new())
+            .set_output(OutputKind::Pipe);
+        if !probe.options.contains(&PandocOption::Sandbox) {
+            probe.add_option(PandocOption::Sandbox);
         }
-        let o = child.wait_with_output()?;
-        if o.status.success() {
-            Ok(o.stdout)
-        } else {
-            Err(PandocError::Err(o))
+        match probe.execute() {
+            Ok(_) => Ok(true),
+            Err(PandocError::SandboxUnsupported(_)) => Ok(false),
+            Err(err) => Err(err),
         }
     }
 
     /// Add a raw command-line argument. You should generally use one of the
     /// convenience functions instead.
-    pub fn arg<T: AsRef<str> + ?Sized, U: AsRef<str> + ?Sized>(
+    ///
+    /// The value is passed as `--key=value`, it doesn't have to be valid UTF-8.
+    pub fn arg<T: AsRef<str> + ?Sized, U: AsRef<OsStr> + ?Sized>(
         &mut self,
         key: &T,
         value: &U,
@@ -1173,37 +3602,88 @@ impl Pandoc {
     /// generate a latex template from the given settings
     ///
     /// Warning: this function can panic in a lot of places.
+    #[deprecated(
+        note = "use `templates::default_template`, which reports errors instead of panicking"
+    )]
     pub fn generate_latex_template<T: AsRef<str> + ?Sized>(mut self, filename: &T) {
-        let mut format = None;
-        if let Some((ref f, ref ext)) = self.output_format {
-            let mut s = f.to_string();
-            for ext in ext {
-                use std::fmt::Write;
-                write!(&mut s, "+{}", ext).unwrap();
-            }
-            format = Some(s);
-        }
-        let format = format.unwrap();
+        let format = self
+            .output_format
+            .as_ref()
+            .map(|(format, extensions)| format_arg(format, extensions))
+            .unwrap();
         self.arg("print-default-template", &format);
-        let output = self.run().unwrap();
+        let output = self.run(&ProcessExecutor).unwrap().0.stdout;
         let mut file = std::fs::File::create(filename.as_ref()).unwrap();
         file.write_all(&output).unwrap();
     }
 
-    fn preprocess(&mut self) -> Result<(), PandocError> {
+    /// run the filters, returns pandoc's stderr output
+    fn preprocess(&mut self, executor: &dyn PandocExecutor) -> Result<Vec<u8>, PandocError> {
+        let (pre, filters) = match self.split_reader() {
+            Some(reader) => reader,
+            None => return Ok(Vec::new()),
+        };
+        let (o, _) = pre.run(executor)?;
+        let filtered = apply_filters(filters, &self.filter_api_versions, o.stdout)?;
+        self.input = Some(InputKind::Pipe(String::from_utf8(filtered)?));
+        Ok(o.stderr)
+    }
+
+    /// A configuration for another pandoc run on behalf of this one, like reading the input
+    /// for the filters.
+    ///
+    /// It finds pandoc the same way and runs under the same restrictions: the path hints, the
+    /// timeout, the rejection of remote inputs, and the sandbox, runtime system and data
+    /// directory options are copied.
+    pub(crate) fn child(&self) -> Pandoc {
+        let mut child = new();
+        child.pandoc_path_hint = self.pandoc_path_hint.clone();
+        child.latex_path_hint = self.latex_path_hint.clone();
+        child.no_builtin_path_hints = self.no_builtin_path_hints;
+        child.timeout = self.timeout;
+        child.no_remote_inputs = self.no_remote_inputs;
+        child.options = self
+            .options
+            .iter()
+            .filter(|option| {
+                matches!(
+                    option,
+                    PandocOption::Sandbox
+                        | PandocOption::RuntimeSystem(_)
+                        | PandocOption::DataDir(_)
+                )
+            })
+            .cloned()
+            .collect();
+        child
+    }
+
+    /// Move reading the input to a configuration writing the JSON AST, if there are filters.
+    ///
+    /// Returns that configuration and the filters, this configuration is left to read the
+    /// filtered AST, but without input.
+    fn split_reader(&mut self) -> Option<(Pandoc, Vec<Filter>)> {
         let filters = std::mem::take(&mut self.filters);
 
         if filters.is_empty() {
-            return Ok(());
+            return None;
         }
 
-        let mut pre = new();
-        pre.pandoc_path_hint = self.pandoc_path_hint.clone();
-        pre.latex_path_hint = self.latex_path_hint.clone();
+        // the reader has to run under the same restrictions as the writer, the options of the
+        // reader are moved, so transformations like the heading shift are not applied twice
+        let mut pre = self.child();
+        let (reader_options, options): (Vec<_>, Vec<_>) = std::mem::take(&mut self.options)
+            .into_iter()
+            .partition(PandocOption::affects_reader);
+        pre.options.extend(reader_options);
+        self.options = options;
+        pre.input_preprocessors = std::mem::take(&mut self.input_preprocessors);
         pre.output = Some(OutputKind::Pipe);
         pre.set_output_format(OutputFormat::Json, Vec::new());
         pre.input = self.input.take();
         pre.print_pandoc_cmdline = self.print_pandoc_cmdline;
+        pre.cmdline_callback = self.cmdline_callback.clone();
+        pre.infer_input_format = self.infer_input_format;
         match self.input_format.take() {
             None => self.input_format = Some((InputFormat::Json, Vec::new())),
             Some((fmt, ext)) => {
@@ -1211,12 +3691,7 @@ impl Pandoc {
                 self.input_format = Some((InputFormat::Json, Vec::new()));
             }
         }
-        let o = pre.run()?;
-        let o = String::from_utf8(o).unwrap();
-        // apply all filters
-        let filtered = filters.into_iter().fold(o, |acc, item| item(acc));
-        self.input = Some(InputKind::Pipe(filtered));
-        Ok(())
+        Some((pre, filters))
     }
 
     /// Execute the Pandoc configured command.
@@ -1226,53 +3701,863 @@ impl Pandoc {
     ///
     /// The `PandocOutput` variant returned depends on the `OutputKind`
     /// configured:
-    pub fn execute(mut self) -> Result<PandocOutput, PandocError> {
-        self.preprocess()?;
+    ///
+    /// When writing to a file, the file is checked to exist, to be non-empty and to have been
+    /// modified after pandoc exits, otherwise `PandocError::MissingOutput` is returned.
+    pub fn execute(self) -> Result<PandocOutput, PandocError> {
+        self.execute_with(&ProcessExecutor)
+    }
+
+    /// Assemble the command once, to convert many piped inputs with the same configuration.
+    ///
+    /// Validates the configuration, resolves the paths and builds the command line, like
+    /// [`execute`](#method.execute) does on every call, without running pandoc, so the
+    /// extensions of the formats are not checked. The configured input is replaced by
+    /// the input given to [`PreparedPandoc::execute`](struct.PreparedPandoc.html#method.execute),
+    /// but its file names are still used to infer the input format. The output has to be
+    /// unset or `OutputKind::Pipe`, the cache directory unset, otherwise
+    /// `PandocError::InvalidConfig` is returned.
+    ///
+    /// ```no_run
+    /// let mut pandoc = pandoc::new();
+    /// pandoc.set_input_format(pandoc::InputFormat::Markdown, Vec::new());
+    /// pandoc.set_output_format(pandoc::OutputFormat::Html5, Vec::new());
+    /// let prepared = pandoc.freeze().unwrap();
+    /// for comment in ["*first*", "**second**"] {
+    ///     let html = prepared.execute(comment).unwrap();
+    /// }
+    /// ```
+    pub fn freeze(self) -> Result<PreparedPandoc, PandocError> {
+        prepared::freeze(self)
+    }
+
+    /// Execute the Pandoc configured command with a custom executor.
+    ///
+    /// Works like [`execute`](#method.execute), but every pandoc invocation (including the
+    /// one producing the input of the filters) is handed to `executor` instead of being
+    /// spawned as a local process.
+    pub fn execute_with(self, executor: &impl PandocExecutor) -> Result<PandocOutput, PandocError> {
+        self.execute_report(executor).map(|(output, _)| output)
+    }
+
+    /// Execute the Pandoc configured command, rendering markdown to HTML without pandoc if it
+    /// is not installed.
+    ///
+    /// If pandoc can't be found and the conversion is from markdown to HTML, the input is
+    /// rendered with [pulldown-cmark](https://docs.rs/pulldown-cmark) instead. The fallback
+    /// only honors the `Standalone` option and the `title` metadata, so its output differs
+    /// from pandoc's. The returned `Converter` tells which of the two produced the output.
+    /// For all other conversions `PandocError::PandocNotFound` is returned as usual.
+    #[cfg(feature = "fallback")]
+    pub fn execute_or_fallback(self) -> Result<(PandocOutput, Converter), PandocError> {
+        let fallback = self.clone();
+        match self.execute() {
+            Err(PandocError::PandocNotFound) => match fallback::convert(fallback) {
+                Ok(output) => Ok((output, Converter::Fallback)),
+                Err(_) => Err(PandocError::PandocNotFound),
+            },
+            result => result.map(|output| (output, Converter::Pandoc)),
+        }
+    }
+
+    /// Execute the Pandoc configured command and parse the result into pandoc's AST.
+    ///
+    /// Replaces the configured output by pandoc's JSON format written to a buffer, and
+    /// deserializes it into the types of the [`pandoc_ast`](https://docs.rs/pandoc_ast) crate.
+    /// The AST types must match the `pandoc-api-version` of the installed pandoc, otherwise
+    /// `PandocError::InvalidAst` is returned.
+    #[cfg(feature = "ast")]
+    pub fn execute_to_ast(self) -> Result<pandoc_ast::Pandoc, PandocError> {
+        self.execute_to_ast_with(&ProcessExecutor)
+    }
+
+    /// Parse the result into pandoc's AST, running pandoc with a custom executor.
+    ///
+    /// Works like [`execute_to_ast`](#method.execute_to_ast), but every pandoc invocation is
+    /// handed to `executor`, like in [`execute_with`](#method.execute_with).
+    #[cfg(feature = "ast")]
+    pub fn execute_to_ast_with(
+        mut self,
+        executor: &impl PandocExecutor,
+    ) -> Result<pandoc_ast::Pandoc, PandocError> {
+        self.set_output_format(OutputFormat::Json, Vec::new());
+        self.set_output(OutputKind::Pipe);
+        match self.execute_with(executor)? {
+            PandocOutput::ToBuffer(json) => {
+                serde_json::from_str(&json).map_err(|e| PandocError::InvalidAst(e.to_string()))
+            }
+            _ => unreachable!("JSON output to a pipe is returned as a buffer"),
+        }
+    }
+
+    /// Read the input with the configured reader and return the document's metadata.
+    ///
+    /// Instead of converting the document, the metadata is rendered with a template
+    /// containing only pandoc's `$meta-json$` variable. Formatted values like the title are
+    /// returned as plain text, lists and maps as JSON arrays and objects. The configured
+    /// output is ignored, filters and metadata options are applied.
+    #[cfg(feature = "metadata")]
+    pub fn extract_metadata(mut self) -> Result<serde_json::Value, PandocError> {
+        let template = TempPath::new(".plain").map_err(PandocError::IoErr)?;
+        std::fs::write(&template, "$meta-json$\n").map_err(PandocError::IoErr)?;
+        self.options
+            .retain(|option| !matches!(option, PandocOption::Template(_)));
+        self.add_option(PandocOption::Standalone);
+        self.add_option(PandocOption::Template(template.path().to_owned()));
+        self.temp_files.push(std::sync::Arc::new(template));
+        self.output_targets.clear();
+        self.set_output_format(OutputFormat::Plain, Vec::new());
+        self.set_output(OutputKind::Pipe);
+        match self.execute()? {
+            PandocOutput::ToBuffer(json) => {
+                serde_json::from_str(&json).map_err(|e| PandocError::InvalidAst(e.to_string()))
+            }
+            _ => unreachable!("plain text output to a pipe is returned as a buffer"),
+        }
+    }
+
+    /// Read the document's metadata like [`extract_metadata`](#method.extract_metadata),
+    /// deserialized into `T`.
+    ///
+    /// ```no_run
+    /// #[derive(serde::Deserialize)]
+    /// struct Meta {
+    ///     title: Option<String>,
+    ///     #[serde(default)]
+    ///     author: Vec<String>,
+    ///     date: Option<String>,
+    /// }
+    ///
+    /// let mut pandoc = pandoc::new();
+    /// pandoc.add_input("upload.docx");
+    /// let meta: Meta = pandoc.extract_metadata_as().unwrap();
+    /// ```
+    #[cfg(feature = "metadata")]
+    pub fn extract_metadata_as<T: serde::de::DeserializeOwned>(self) -> Result<T, PandocError> {
+        serde_json::from_value(self.extract_metadata()?)
+            .map_err(|e| PandocError::InvalidAst(e.to_string()))
+    }
+
+    /// Read the input with the configured reader and return the document's headings as a
+    /// tree, see [`heading_tree`](fn.heading_tree.html).
+    ///
+    /// The identifiers don't include the `PandocOption::IdPrefix` the writers prepend.
+    #[cfg(feature = "ast")]
+    pub fn extract_toc(self) -> Result<Vec<Heading>, PandocError> {
+        self.execute_to_ast()
+            .map(|document| heading_tree(&document))
+    }
+
+    /// Read the input with the configured reader and count its words, headings, figures,
+    /// tables and code blocks, see [`document_stats`](fn.document_stats.html).
+    #[cfg(feature = "ast")]
+    pub fn stats(self) -> Result<DocumentStats, PandocError> {
+        self.execute_to_ast()
+            .map(|document| document_stats(&document))
+    }
+
+    /// Read the input with the configured reader and return its link targets and image
+    /// sources, see [`link_inventory`](fn.link_inventory.html).
+    ///
+    /// Enable `MarkdownExtension::Sourcepos` on a commonmark reader to get the positions of
+    /// the links in the input.
+    #[cfg(feature = "ast")]
+    pub fn extract_links(self) -> Result<Vec<DocumentLink>, PandocError> {
+        self.execute_to_ast().map(link_inventory)
+    }
+
+    /// Cut the document at the headings of `level` and above, and convert each section to
+    /// its own file.
+    ///
+    /// The files are written to the directory of the configured `OutputKind::Directory`,
+    /// named after the identifiers of the headings, with `index` for the part before the
+    /// first heading. Every section is converted with the configured writer and options and
+    /// has the metadata of the whole document. Returns the written files in document order.
+    ///
+    /// ```no_run
+    /// let mut pandoc = pandoc::new();
+    /// pandoc.add_input("manual.md");
+    /// pandoc.set_output(pandoc::OutputKind::Directory("kb".into()));
+    /// pandoc.set_output_format(pandoc::OutputFormat::Html5, Vec::new());
+    /// let pages = pandoc.split_by_heading(2).unwrap();
+    /// ```
+    #[cfg(feature = "ast")]
+    pub fn split_by_heading(mut self, level: u32) -> Result<Vec<PathBuf>, PandocError> {
+        let dir = match self.output {
+            Some(OutputKind::Directory(ref dir)) => dir.clone(),
+            _ => {
+                return Err(PandocError::InvalidConfig(
+                    "splitting a document needs an OutputKind::Directory output".to_string(),
+                ))
+            }
+        };
+        let document = self.clone().execute_to_ast()?;
+        std::fs::create_dir_all(&dir).map_err(PandocError::IoErr)?;
+        // the sections are read from the AST, the reader already applied the filters
+        self.filters.clear();
+        self.input_preprocessors.clear();
+        self.infer_input_format = false;
+        self.output_targets.clear();
+        let format = self
+            .output_format
+            .get_or_insert_with(|| (OutputFormat::Html5, Vec::new()))
+            .0
+            .clone();
+        let extension = split::file_extension(&format);
+        split::sections(document, level)
+            .into_iter()
+            .map(|(name, section)| {
+                let file = dir.join(format!("{}.{}", name, extension));
+                let mut pandoc = self.clone();
+                pandoc.set_input(InputKind::Pipe(section.to_json()));
+                pandoc.set_input_format(InputFormat::Json, Vec::new());
+                pandoc.set_output(OutputKind::File(file.clone()));
+                pandoc.execute()?;
+                Ok(file)
+            })
+            .collect()
+    }
+
+    /// Read the input with the configured reader and list the cited works that are missing
+    /// from the bibliographies.
+    ///
+    /// The bibliographies set with `PandocOption::Bibliography` or
+    /// [`set_citeproc`](#method.set_citeproc) are read with pandoc, so this works for all
+    /// bibliography formats pandoc supports. Entries in the `references` metadata of the
+    /// document count as present, too. Useful to catch typos in citation keys before a long
+    /// PDF build.
+    #[cfg(feature = "ast")]
+    pub fn citation_report(self) -> Result<CitationReport, PandocError> {
+        let bibliographies: Vec<PathBuf> = self
+            .options
+            .iter()
+            .filter_map(|option| match *option {
+                PandocOption::Bibliography(ref file) => Some(file.clone()),
+                _ => None,
+            })
+            .collect();
+        let mut known = std::collections::BTreeSet::new();
+        for file in &bibliographies {
+            known.extend(citations::bibliography_keys(&self, file)?);
+        }
+        let document = self.execute_to_ast()?;
+        known.extend(citations::reference_ids(&document));
+        let cited = cited_keys(&document);
+        let missing = cited.difference(&known).cloned().collect();
+        Ok(CitationReport { cited, missing })
+    }
+
+    /// Execute the Pandoc configured command and return its output as `Bytes`.
+    ///
+    /// Replaces the configured output by a buffer. The buffer is moved into the returned
+    /// [`Bytes`](https://docs.rs/bytes), so it can be handed to HTTP servers and shared
+    /// between consumers without copying.
+    #[cfg(feature = "bytes")]
+    pub fn execute_to_bytes(self) -> Result<bytes::Bytes, PandocError> {
+        self.execute_to_bytes_with(&ProcessExecutor)
+    }
+
+    /// Execute the Pandoc configured command with a custom executor and return its output
+    /// as `Bytes`.
+    ///
+    /// Works like [`execute_to_bytes`](#method.execute_to_bytes), with the invocations handed
+    /// to `executor` like [`execute_with`](#method.execute_with) does.
+    #[cfg(feature = "bytes")]
+    pub fn execute_to_bytes_with(
+        mut self,
+        executor: &impl PandocExecutor,
+    ) -> Result<bytes::Bytes, PandocError> {
+        self.set_output(OutputKind::Pipe);
+        match self.execute_with(executor)? {
+            PandocOutput::ToBuffer(text) => Ok(text.into()),
+            PandocOutput::ToBufferRaw(bytes) => Ok(bytes.into()),
+            _ => unreachable!("output to a pipe is returned as a buffer"),
+        }
+    }
+
+    /// Execute the conversion on a running pandoc server instead of spawning pandoc.
+    ///
+    /// See the [`server`](server/index.html) module for the limitations of this mode.
+    #[cfg(feature = "server")]
+    pub fn execute_on_server(
+        self,
+        server: &server::ServerClient,
+    ) -> Result<PandocOutput, PandocError> {
+        server.execute(self)
+    }
+
+    /// Execute the Pandoc configured command, extracting embedded media to a temporary
+    /// directory.
+    ///
+    /// Passes `--extract-media` with a newly created temporary directory, and returns the
+    /// extracted files next to the output. The directory is removed once the returned
+    /// `ExtractedMedia` is dropped, unless it is kept with `ExtractedMedia::keep`.
+    pub fn extract_media_to_temp(mut self) -> Result<(PandocOutput, ExtractedMedia), PandocError> {
+        let dir = TempDir::new().map_err(PandocError::IoErr)?;
+        self.add_option(PandocOption::ExtractMedia(dir.path().to_owned()));
+        let output = self.execute()?;
+        let files = list_files(&dir).map_err(PandocError::IoErr)?;
+        Ok((output, ExtractedMedia { dir, files }))
+    }
+
+    /// Execute the Pandoc configured command once per output target.
+    ///
+    /// The input is read and passed through the filters only once, producing pandoc's JSON
+    /// AST, which is then handed to the writer of every target added with
+    /// [`add_output_target`](#method.add_output_target). The outputs are returned in the order
+    /// the targets were added.
+    ///
+    /// The images of inputs like docx only exist in pandoc's memory while it reads them, so
+    /// unless `PandocOption::ExtractMedia` is given, they are extracted to a temporary
+    /// directory that is removed once all targets are written.
+    pub fn execute_targets(self) -> Result<Vec<PandocOutput>, PandocError> {
+        self.execute_targets_with(&ProcessExecutor)
+    }
+
+    /// Execute the Pandoc configured command once per output target with a custom executor.
+    ///
+    /// Works like [`execute_targets`](#method.execute_targets), but the reader invocation and
+    /// the writer invocations of all targets are handed to `executor`.
+    pub fn execute_targets_with(
+        mut self,
+        executor: &impl PandocExecutor,
+    ) -> Result<Vec<PandocOutput>, PandocError> {
+        let targets = std::mem::take(&mut self.output_targets);
+        if targets.is_empty() {
+            return Err(PandocError::NoOutputSpecified);
+        }
+        let mut media = None;
+        if targets.len() > 1 {
+            let extracts_media = self
+                .options
+                .iter()
+                .any(|option| matches!(option, PandocOption::ExtractMedia(_)));
+            if !extracts_media && self.binary_input() {
+                let dir = TempDir::new().map_err(PandocError::IoErr)?;
+                self.add_option(PandocOption::ExtractMedia(dir.path().to_owned()));
+                media = Some(dir);
+            }
+            // the identity filter makes `preprocess` run the reader, without any other effect
+            if self.filters.is_empty() {
+                self.add_filter(|json| json);
+            }
+            let stderr = self.preprocess(executor)?;
+            check_warnings(&self.fail_on, &stderr)?;
+        }
+        let outputs = targets
+            .into_iter()
+            .map(|(format, output)| {
+                let mut target = self.clone();
+                target.set_output_format(format, Vec::new());
+                target.set_output(output);
+                target.execute_with(executor)
+            })
+            .collect();
+        drop(media);
+        outputs
+    }
+
+    /// whether the input is in a format like docx, see `InputFormat::is_binary`
+    fn binary_input(&self) -> bool {
+        match self.input_format {
+            Some((ref format, _)) => format.is_binary(),
+            None => self.input_files().iter().any(|file| {
+                file.extension()
+                    .and_then(OsStr::to_str)
+                    .and_then(InputFormat::from_extension)
+                    .is_some_and(|format| format.is_binary())
+            }),
+        }
+    }
+
+    /// Execute the Pandoc configured command and report details of the execution.
+    ///
+    /// Works like [`execute`](#method.execute), but additionally returns an
+    /// [`ExecutionReport`](struct.ExecutionReport.html) with the duration, command line,
+    /// exit status and stderr output of the conversion.
+    pub fn execute_with_report(self) -> Result<(PandocOutput, ExecutionReport), PandocError> {
+        self.execute_report(&ProcessExecutor)
+    }
+
+    fn execute_report(
+        mut self,
+        executor: &dyn PandocExecutor,
+    ) -> Result<(PandocOutput, ExecutionReport), PandocError> {
+        #[cfg(feature = "tracing")]
+        let _span = tracing::debug_span!("pandoc").entered();
+        let start = std::time::Instant::now();
+        self.infer_directory_output();
+        let cache = match self.cache_dir {
+            Some(ref dir) => cache::CacheEntry::new(&self, dir, executor)?,
+            None => None,
+        };
         let output_format = self.output_format.clone();
         let output_kind = self.output.clone();
-        let output = self.run()?;
-
-        match output_kind {
-            Some(OutputKind::File(name)) => Ok(PandocOutput::ToFile(name)),
-            Some(OutputKind::Pipe) => match output_format {
-                Some((OutputFormat::Pdf | OutputFormat::Docx, ..)) => Ok(PandocOutput::ToBufferRaw(output)),
+        let lossy_utf8 = self.lossy_utf8;
+        let to_buffer = |output: Vec<u8>| {
+            buffer_output(
+                output,
+                output_format.as_ref().map(|(format, _)| format),
+                lossy_utf8,
+            )
+        };
+        if let Some(cached) = cache.as_ref().and_then(cache::CacheEntry::load) {
+            let output = match output_kind {
+                Some(OutputKind::File(name)) => {
+                    std::fs::write(&name, cached).map_err(PandocError::IoErr)?;
+                    PandocOutput::ToFile(name)
+                }
+                Some(OutputKind::TempFile(ref suffix)) => {
+                    let temp = TempPath::new(suffix).map_err(PandocError::IoErr)?;
+                    std::fs::write(&temp, cached).map_err(PandocError::IoErr)?;
+                    PandocOutput::ToTempFile(temp)
+                }
+                _ => to_buffer(cached)?,
+            };
+            let report = ExecutionReport {
+                duration: start.elapsed(),
+                args: Vec::new(),
+                status: std::process::ExitStatus::default(),
+                stderr: String::new(),
+            };
+            return Ok((output, report));
+        }
+        let mut stderr = self.preprocess(executor)?;
+        let fail_on = std::mem::take(&mut self.fail_on);
+        let temp_file = match self.output {
+            Some(OutputKind::TempFile(ref suffix)) => {
+                let temp = TempPath::new(suffix).map_err(PandocError::IoErr)?;
+                self.output = Some(OutputKind::File(temp.path().to_owned()));
+                Some(temp)
+            }
+            _ => None,
+        };
+        // files left by an earlier run must not pass for the output of this one
+        let earlier_output = match output_kind {
+            Some(OutputKind::File(ref name)) => modified(name),
+            _ => None,
+        };
+        let earlier_files: std::collections::HashMap<PathBuf, Option<std::time::SystemTime>> =
+            match output_kind {
+                Some(OutputKind::Directory(ref dir)) => list_files(dir)
+                    .unwrap_or_default()
+                    .into_iter()
+                    .map(|file| {
+                        let modified = modified(&file);
+                        (file, modified)
+                    })
+                    .collect(),
+                _ => Default::default(),
+            };
+        let (mut o, args) = self.run(executor)?;
+        stderr.append(&mut o.stderr);
+        check_warnings(&fail_on, &stderr)?;
+        let output = o.stdout;
+        let report = ExecutionReport {
+            duration: start.elapsed(),
+            args,
+            status: o.status,
+            stderr: String::from_utf8_lossy(&stderr).into_owned(),
+        };
 
-                _ => match String::from_utf8(output) {
-                    Ok(string) => Ok(PandocOutput::ToBuffer(string)),
-                    Err(err) => Err(PandocError::from(err.utf8_error())),
-                },
+        let output = match output_kind {
+            Some(OutputKind::File(name)) => match std::fs::metadata(&name) {
+                Ok(ref meta)
+                    if meta.len() > 0
+                        && (earlier_output.is_none() || meta.modified().ok() != earlier_output) =>
+                {
+                    Ok(PandocOutput::ToFile(name))
+                }
+                _ => Err(PandocError::MissingOutput(name)),
+            },
+            Some(OutputKind::TempFile(_)) => {
+                let temp = temp_file.expect("temporary file is created before running pandoc");
+                match std::fs::metadata(&temp) {
+                    Ok(ref meta) if meta.len() > 0 => Ok(PandocOutput::ToTempFile(temp)),
+                    _ => Err(PandocError::MissingOutput(temp.path().to_owned())),
+                }
+            }
+            Some(OutputKind::Directory(dir)) => match list_files(&dir) {
+                Ok(files) => {
+                    let written: Vec<PathBuf> = files
+                        .into_iter()
+                        .filter(|file| match earlier_files.get(file) {
+                            Some(earlier) => modified(file) != *earlier,
+                            None => true,
+                        })
+                        .collect();
+                    if written.is_empty() {
+                        Err(PandocError::MissingOutput(dir))
+                    } else {
+                        Ok(PandocOutput::ToDirectory(dir, written))
+                    }
+                }
+                Err(err) => Err(PandocError::IoErr(err)),
             },
+            Some(OutputKind::Pipe) => to_buffer(output),
+            Some(OutputKind::InheritStdout) => Ok(PandocOutput::ToStdout),
+            Some(OutputKind::Tee(file)) => {
+                std::fs::write(file, &output).map_err(PandocError::IoErr)?;
+                to_buffer(output)
+            }
             None => Err(PandocError::NoOutputSpecified),
+        }?;
+        // runs with warnings are not cached, so the warnings are checked against `fail_on`
+        // on every run
+        match cache {
+            Some(cache) if parse_warnings(&report.stderr).is_empty() => {
+                cache.store_output(&output)?
+            }
+            _ => {}
+        }
+        Ok((output, report))
+    }
+}
+
+/// Media extracted by [`Pandoc::extract_media_to_temp`](struct.Pandoc.html#method.extract_media_to_temp)
+#[derive(Debug)]
+pub struct ExtractedMedia {
+    dir: TempDir,
+    files: Vec<PathBuf>,
+}
+
+impl ExtractedMedia {
+    /// The temporary directory containing the media.
+    pub fn dir(&self) -> &Path {
+        self.dir.path()
+    }
+
+    /// The extracted media files, sorted by path.
+    pub fn files(&self) -> &[PathBuf] {
+        &self.files
+    }
+
+    /// Keep the directory on disk instead of removing it on drop, returning the file list.
+    pub fn keep(self) -> Vec<PathBuf> {
+        self.dir.keep();
+        self.files
+    }
+}
+
+/// all files below `dir`, recursively and sorted by path
+fn list_files(dir: &Path) -> std::io::Result<Vec<PathBuf>> {
+    let mut files = Vec::new();
+    for entry in std::fs::read_dir(dir)? {
+        let path = entry?.path();
+        if path.is_dir() {
+            files.extend(list_files(&path)?);
+        } else {
+            files.push(path);
+        }
+    }
+    files.sort();
+    Ok(files)
+}
+
+/// the UTC date `secs` seconds after the unix epoch as `YYYY-MM-DD`
+pub(crate) fn iso_date(secs: u64) -> String {
+    // Howard Hinnant's civil_from_days
+    let z = (secs / 86_400) as i64 + 719_468;
+    let era = z.div_euclid(146_097);
+    let doe = z - era * 146_097;
+    let yoe = (doe - doe / 1_460 + doe / 36_524 - doe / 146_096) / 365;
+    let doy = doe - (365 * yoe + yoe / 4 - yoe / 100);
+    let mp = (5 * doy + 2) / 153;
+    let day = doy - (153 * mp + 2) / 5 + 1;
+    let month = if mp < 10 { mp + 3 } else { mp - 9 };
+    let year = yoe + era * 400 + if month <= 2 { 1 } else { 0 };
+    format!("{:04}-{:02}-{:02}", year, month, day)
+}
+
+/// the modification time of `path`, if it exists
+fn modified(path: &Path) -> Option<std::time::SystemTime> {
+    std::fs::metadata(path)
+        .and_then(|meta| meta.modified())
+        .ok()
+}
+
+/// the `SOURCE_DATE_EPOCH` of reproducible builds, the epoch itself if it is not set
+fn source_date_epoch() -> u64 {
+    env::var("SOURCE_DATE_EPOCH")
+        .ok()
+        .and_then(|epoch| epoch.trim().parse().ok())
+        .unwrap_or(0)
+}
+
+/// fail if pandoc's stderr contains warnings of any of the given classes
+fn check_warnings(fail_on: &[WarningClass], stderr: &[u8]) -> Result<(), PandocError> {
+    if fail_on.is_empty() {
+        return Ok(());
+    }
+    let warnings: Vec<_> = parse_warnings(&String::from_utf8_lossy(stderr))
+        .into_iter()
+        .filter(|warning| fail_on.contains(&warning.class))
+        .collect();
+    if warnings.is_empty() {
+        Ok(())
+    } else {
+        Err(PandocError::Warnings(warnings))
+    }
+}
+
+/// the output of pandoc captured from stdout, as text unless the format is binary
+fn buffer_output(
+    output: Vec<u8>,
+    format: Option<&OutputFormat>,
+    lossy_utf8: bool,
+) -> Result<PandocOutput, PandocError> {
+    match format {
+        Some(format) if format.is_binary() => Ok(PandocOutput::ToBufferRaw(output)),
+        _ => match String::from_utf8(output) {
+            Ok(string) => Ok(PandocOutput::ToBuffer(string)),
+            Err(err) if lossy_utf8 => Ok(PandocOutput::ToBuffer(
+                String::from_utf8_lossy(err.as_bytes()).into_owned(),
+            )),
+            Err(err) => Err(PandocError::from(err)),
+        },
+    }
+}
+
+/// how an assembled pandoc command is run and its failures are reported
+#[derive(Clone)]
+struct RunContext {
+    timeout: Option<std::time::Duration>,
+    produces_pdf: bool,
+    sandboxed: bool,
+    print_pandoc_cmdline: bool,
+    cmdline_callback: Option<CmdlineCallback>,
+    /// generated files passed to pandoc, which have to exist until it exits
+    _temp_files: Vec<std::sync::Arc<TempPath>>,
+}
+
+impl RunContext {
+    /// run pandoc, returns its output and the full command line used
+    fn run(
+        &self,
+        cmd: Command,
+        input: Option<Vec<u8>>,
+        executor: &dyn PandocExecutor,
+    ) -> Result<(std::process::Output, Vec<OsString>), PandocError> {
+        if self.print_pandoc_cmdline {
+            match self.cmdline_callback {
+                Some(ref callback) => callback(&format!("{:?}", cmd)),
+                #[cfg(feature = "tracing")]
+                None => tracing::info!("{:?}", cmd),
+                #[cfg(not(feature = "tracing"))]
+                None => eprintln!("{:?}", cmd),
+            }
+        }
+        #[cfg(feature = "tracing")]
+        tracing::debug!(command = ?cmd, "running pandoc");
+        #[cfg(feature = "tracing")]
+        let start = std::time::Instant::now();
+        let command_line = std::iter::once(cmd.get_program())
+            .chain(cmd.get_args())
+            .map(OsStr::to_owned)
+            .collect();
+        let o = executor.execute(Invocation::new(cmd, input).with_timeout(self.timeout))?;
+        #[cfg(feature = "tracing")]
+        tracing::debug!(
+            status = %o.status,
+            duration = ?start.elapsed(),
+            stderr = %String::from_utf8_lossy(&o.stderr),
+            "pandoc finished"
+        );
+        if o.status.success() {
+            Ok((o, command_line))
+        } else if self.produces_pdf {
+            match PdfError::from_stderr(&String::from_utf8_lossy(&o.stderr)) {
+                Some(pdf) => Err(PandocError::PdfErr(o, Box::new(pdf))),
+                None => Err(PandocError::Err(o)),
+            }
+        } else if self.sandboxed
+            && String::from_utf8_lossy(&o.stderr).contains("Could not find data file")
+        {
+            let message = String::from_utf8_lossy(&o.stderr).trim().to_string();
+            Err(PandocError::SandboxUnsupported(message))
+        } else {
+            Err(PandocError::Err(o))
         }
     }
 }
 
+/// Details about a successful pandoc execution, see
+/// [`Pandoc::execute_with_report`](struct.Pandoc.html#method.execute_with_report).
+#[derive(Clone, Debug)]
+#[non_exhaustive]
+pub struct ExecutionReport {
+    /// wall-clock time of the whole execution, including the filter pass
+    pub duration: std::time::Duration,
+    /// the command line of the (last) pandoc invocation, starting with the executable
+    pub args: Vec<OsString>,
+    /// exit status of pandoc
+    pub status: std::process::ExitStatus,
+    /// everything pandoc printed to stderr, including the filter pass
+    pub stderr: String,
+}
+
 /// The output from Pandoc: the file written to, or a buffer with its output.
 pub enum PandocOutput {
     /// The results of the pandoc operation are stored in `Path`
     ToFile(PathBuf),
+    /// The results of the pandoc operation are stored in a temporary file, which is removed
+    /// when the `TempPath` is dropped
+    ToTempFile(TempPath),
+    /// The results of the pandoc operation are stored in the directory `PathBuf`, consisting of
+    /// the listed files
+    ToDirectory(PathBuf, Vec<PathBuf>),
     /// The results of the pandoc operation are returned as a `String` (constructed from the UTF-8
     /// stream returned by pandoc). This will be the case for text-based formats.
     ToBuffer(String),
     /// The results of the pandoc operation are returned as a `Vec<u8>`. This will be the case for
     /// binary formats such as PDF.
     ToBufferRaw(Vec<u8>),
+    /// The results of the pandoc operation were written to the stdout of the current process.
+    ToStdout,
 }
 
 /// Possible errors that can occur before or during pandoc execution
+#[non_exhaustive]
 pub enum PandocError {
     /// conversion from UTF-8 failed; includes valid-up-to byte count.
     BadUtf8Conversion(usize),
+    /// pandoc's output is not valid UTF-8; includes the raw output and the valid-up-to byte
+    /// count.
+    BadUtf8Output(std::string::FromUtf8Error),
     /// some kind of IO-Error
     IoErr(std::io::Error),
     /// pandoc execution failed, provide output from stderr
     Err(std::process::Output),
+    /// PDF generation failed, provide output from stderr and the analysis of the engine log
+    PdfErr(std::process::Output, Box<PdfError>),
     /// forgot to specify an output file
     NoOutputSpecified,
     /// forgot to specify any input files
     NoInputSpecified,
     /// pandoc executable not found
     PandocNotFound,
+    /// pandoc reported success, but the output file is missing or empty
+    MissingOutput(PathBuf),
+    /// pandoc reported warnings of a class selected with `Pandoc::fail_on`
+    Warnings(Vec<Warning>),
+    /// an input glob pattern is malformed
+    InvalidGlob(String),
+    /// an input file, or a file referenced by an option, does not exist or can't be read
+    MissingInput(PathBuf),
+    /// the extensions of the two input files imply different input formats
+    AmbiguousInputFormat(PathBuf, PathBuf),
+    /// a configuration could not be parsed, contains a description of the problem
+    InvalidConfig(String),
+    /// the chosen backend can't handle an option, input or output of the configuration
+    Unsupported(String),
+    /// the pandoc server failed or could not be reached, contains its message
+    ServerErr(String),
+    /// pandoc's JSON output could not be deserialized into the AST or metadata types
+    InvalidAst(String),
+    /// a format or extension name could not be parsed, contains the offending string
+    InvalidFormat(String),
+    /// none of the PDF engines that were probed is installed, contains their names
+    PdfEngineNotFound(Vec<String>),
+    /// pandoc was killed after running longer than the timeout
+    Timeout(std::time::Duration),
+    /// a sandboxed conversion failed because pandoc can't read its data files, contains
+    /// pandoc's message, see `Pandoc::sandbox_supported`
+    SandboxUnsupported(String),
+    /// an extension does not apply to the reader or writer it was requested for, contains
+    /// the extension and the format
+    UnsupportedExtension(String, String),
+    /// the `pandoc-api-version` of the AST is not one required with
+    /// `Pandoc::require_filter_api_version`, contains the version
+    UnsupportedApiVersion(Vec<u32>),
+    /// downloading a bibliography or citation style failed, contains the reason
+    FetchFailed(String),
+}
+
+/// The category of a [`PandocError`](enum.PandocError.html), without any attached data.
+///
+/// Obtained through [`PandocError::kind`](enum.PandocError.html#method.kind), this allows
+/// matching on the kind of failure without destructuring the error itself.
+#[derive(PartialEq, Eq, Hash, Copy, Clone, Debug)]
+#[non_exhaustive]
+pub enum ErrorKind {
+    /// see `PandocError::BadUtf8Conversion` and `PandocError::BadUtf8Output`
+    BadUtf8Conversion,
+    /// see `PandocError::IoErr`
+    Io,
+    /// see `PandocError::Err`
+    PandocFailed,
+    /// see `PandocError::PdfErr`
+    PdfEngineFailed,
+    /// see `PandocError::NoOutputSpecified`
+    NoOutputSpecified,
+    /// see `PandocError::NoInputSpecified`
+    NoInputSpecified,
+    /// see `PandocError::PandocNotFound`
+    PandocNotFound,
+    /// see `PandocError::MissingOutput`
+    MissingOutput,
+    /// see `PandocError::Warnings`
+    Warnings,
+    /// see `PandocError::InvalidGlob`
+    InvalidGlob,
+    /// see `PandocError::MissingInput`
+    MissingInput,
+    /// see `PandocError::AmbiguousInputFormat`
+    AmbiguousInputFormat,
+    /// see `PandocError::InvalidConfig`
+    InvalidConfig,
+    /// see `PandocError::Unsupported`
+    Unsupported,
+    /// see `PandocError::ServerErr`
+    ServerFailed,
+    /// see `PandocError::InvalidAst`
+    InvalidAst,
+    /// see `PandocError::InvalidFormat`
+    InvalidFormat,
+    /// see `PandocError::PdfEngineNotFound`
+    PdfEngineNotFound,
+    /// see `PandocError::Timeout`
+    Timeout,
+    /// see `PandocError::SandboxUnsupported`
+    SandboxUnsupported,
+    /// see `PandocError::UnsupportedExtension`
+    UnsupportedExtension,
+    /// see `PandocError::UnsupportedApiVersion`
+    UnsupportedApiVersion,
+    /// see `PandocError::FetchFailed`
+    FetchFailed,
+}
+
+impl PandocError {
+    /// Get the category of this error.
+    pub fn kind(&self) -> ErrorKind {
+        match *self {
+            PandocError::BadUtf8Conversion(_) => ErrorKind::BadUtf8Conversion,
+            PandocError::BadUtf8Output(_) => ErrorKind::BadUtf8Conversion,
+            PandocError::IoErr(_) => ErrorKind::Io,
+            PandocError::Err(_) => ErrorKind::PandocFailed,
+            PandocError::PdfErr(..) => ErrorKind::PdfEngineFailed,
+            PandocError::NoOutputSpecified => ErrorKind::NoOutputSpecified,
+            PandocError::NoInputSpecified => ErrorKind::NoInputSpecified,
+            PandocError::PandocNotFound => ErrorKind::PandocNotFound,
+            PandocError::MissingOutput(_) => ErrorKind::MissingOutput,
+            PandocError::Warnings(_) => ErrorKind::Warnings,
+            PandocError::InvalidGlob(_) => ErrorKind::InvalidGlob,
+            PandocError::MissingInput(_) => ErrorKind::MissingInput,
+            PandocError::AmbiguousInputFormat(..) => ErrorKind::AmbiguousInputFormat,
+            PandocError::InvalidConfig(_) => ErrorKind::InvalidConfig,
+            PandocError::Unsupported(_) => ErrorKind::Unsupported,
+            PandocError::ServerErr(_) => ErrorKind::ServerFailed,
+            PandocError::InvalidAst(_) => ErrorKind::InvalidAst,
+            PandocError::InvalidFormat(_) => ErrorKind::InvalidFormat,
+            PandocError::PdfEngineNotFound(_) => ErrorKind::PdfEngineNotFound,
+            PandocError::Timeout(_) => ErrorKind::Timeout,
+            PandocError::SandboxUnsupported(_) => ErrorKind::SandboxUnsupported,
+            PandocError::UnsupportedExtension(..) => ErrorKind::UnsupportedExtension,
+            PandocError::UnsupportedApiVersion(_) => ErrorKind::UnsupportedApiVersion,
+            PandocError::FetchFailed(_) => ErrorKind::FetchFailed,
+        }
+    }
 }
 
 impl std::convert::From<std::io::Error> for PandocError {
@@ -1284,6 +4569,12 @@ impl std::convert::From<std::io::Error> for PandocError {
     }
 }
 
+impl std::convert::From<std::string::FromUtf8Error> for PandocError {
+    fn from(error: std::string::FromUtf8Error) -> Self {
+        PandocError::BadUtf8Output(error)
+    }
+}
+
 impl std::convert::From<std::str::Utf8Error> for PandocError {
     fn from(error: std::str::Utf8Error) -> Self {
         PandocError::BadUtf8Conversion(error.valid_up_to())
@@ -1299,16 +4590,84 @@ impl std::fmt::Debug for PandocError {
                 write!(fmt, "stdout: {}", String::from_utf8_lossy(&e.stdout))?;
                 write!(fmt, "stderr: {}", String::from_utf8_lossy(&e.stderr))
             }
+            PandocError::PdfErr(ref e, ref pdf) => {
+                write!(fmt, "PDF generation failed:\n{}", pdf)?;
+                write!(fmt, "exit_code: {:?}", e.status.code())?;
+                write!(fmt, "stderr: {}", String::from_utf8_lossy(&e.stderr))
+            }
             PandocError::NoOutputSpecified => write!(fmt, "No output file was specified"),
             PandocError::NoInputSpecified => write!(fmt, "No input files were specified"),
             PandocError::PandocNotFound => {
                 write!(fmt, "Pandoc not found, did you forget to install pandoc?")
             }
+            PandocError::MissingOutput(ref path) => write!(
+                fmt,
+                "Pandoc did not produce the output file {}",
+                path.display()
+            ),
+            PandocError::MissingInput(ref path) => {
+                write!(
+                    fmt,
+                    "Input file {} does not exist or can't be read",
+                    path.display()
+                )
+            }
+            PandocError::AmbiguousInputFormat(ref first, ref second) => write!(
+                fmt,
+                "Input files {} and {} imply different input formats",
+                first.display(),
+                second.display()
+            ),
+            PandocError::InvalidConfig(ref e) => write!(fmt, "Invalid configuration: {}", e),
+            PandocError::Unsupported(ref e) => write!(fmt, "{}", e),
+            PandocError::ServerErr(ref e) => write!(fmt, "Pandoc server failed: {}", e),
+            PandocError::InvalidAst(ref e) => write!(fmt, "Invalid pandoc AST: {}", e),
+            PandocError::InvalidFormat(ref spec) => write!(fmt, "Invalid format {:?}", spec),
+            PandocError::PdfEngineNotFound(ref engines) => write!(
+                fmt,
+                "No PDF engine found, install one of {}",
+                engines.join(", ")
+            ),
+            PandocError::InvalidGlob(ref pattern) => {
+                write!(fmt, "Invalid input glob pattern {:?}", pattern)
+            }
+            PandocError::Timeout(timeout) => {
+                write!(fmt, "Pandoc was killed after running for {:?}", timeout)
+            }
+            PandocError::SandboxUnsupported(ref e) => write!(
+                fmt,
+                "Pandoc can't read its data files in sandbox mode, it needs to be compiled \
+                 with the embed_data_files flag: {}",
+                e
+            ),
+            PandocError::UnsupportedExtension(ref extension, ref format) => write!(
+                fmt,
+                "The extension {} is not supported by the format {}",
+                extension, format
+            ),
+            PandocError::UnsupportedApiVersion(ref version) => write!(
+                fmt,
+                "The filters don't support the pandoc-api-version {:?} of the installed pandoc",
+                version
+            ),
+            PandocError::FetchFailed(ref e) => write!(fmt, "Download failed: {}", e),
+            PandocError::Warnings(ref warnings) => {
+                write!(fmt, "Pandoc reported warnings treated as errors:")?;
+                for warning in warnings {
+                    write!(fmt, "\n{}", warning)?;
+                }
+                Ok(())
+            }
             PandocError::BadUtf8Conversion(byte) => write!(
                 fmt,
                 "UTF-8 conversion of pandoc output failed after byte {}.",
                 byte
             ),
+            PandocError::BadUtf8Output(ref e) => write!(
+                fmt,
+                "UTF-8 conversion of pandoc output failed after byte {}.",
+                e.utf8_error().valid_up_to()
+            ),
         }
     }
 }
@@ -1320,9 +4679,11 @@ impl std::fmt::Display for PandocError {
 }
 
 impl std::error::Error for PandocError {
-    fn cause(&self) -> Option<&dyn std::error::Error> {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
         match *self {
             PandocError::IoErr(ref e) => Some(e),
+            PandocError::BadUtf8Output(ref e) => Some(e),
+            PandocError::PdfErr(_, ref pdf) => Some(&**pdf),
             _ => None,
         }
     }
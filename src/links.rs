@@ -0,0 +1,146 @@
+//! the links and images of a document
+
+use crate::outline::stringify;
+use pandoc_ast::{Attr, Block, Inline, MutVisitor};
+
+/// Whether a [`DocumentLink`] is a hyperlink or an image.
+#[derive(PartialEq, Eq, Copy, Clone, Debug)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum LinkKind {
+    /// a hyperlink, whose target is a URL
+    Link,
+    /// an image, whose target is its source
+    Image,
+}
+
+/// Where an element is in the input, taken from the `data-pos` attributes the
+/// `MarkdownExtension::Sourcepos` extension makes the commonmark readers add.
+#[derive(PartialEq, Eq, Clone, Debug)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct SourcePosition {
+    /// the input file, if pandoc read more than one
+    pub file: Option<String>,
+    /// the line the element starts on, 1-based
+    pub start_line: u32,
+    /// the column the element starts at, 1-based
+    pub start_column: u32,
+    /// the line the element ends on, 1-based
+    pub end_line: u32,
+    /// the column after the end of the element, 1-based
+    pub end_column: u32,
+}
+
+impl SourcePosition {
+    /// parse a `data-pos` value like `1:3-1:20` or `doc.md@1:3-1:20`
+    fn parse(pos: &str) -> Option<SourcePosition> {
+        let (file, range) = match pos.rsplit_once('@') {
+            Some((file, range)) => (Some(file.to_string()), range),
+            None => (None, pos),
+        };
+        // elements continued on other lines have one range per line
+        let range = range.split(';').next()?;
+        let (start, end) = range.split_once('-')?;
+        let point = |point: &str| -> Option<(u32, u32)> {
+            let (line, column) = point.split_once(':')?;
+            Some((line.parse().ok()?, column.parse().ok()?))
+        };
+        let (start_line, start_column) = point(start)?;
+        let (end_line, end_column) = point(end)?;
+        Some(SourcePosition {
+            file,
+            start_line,
+            start_column,
+            end_line,
+            end_column,
+        })
+    }
+}
+
+/// A link target or image source of a document, see
+/// [`Pandoc::extract_links`](struct.Pandoc.html#method.extract_links).
+#[derive(PartialEq, Eq, Clone, Debug)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct DocumentLink {
+    /// whether this is a link or an image
+    pub kind: LinkKind,
+    /// the URL of a link or the source of an image, as written in the document
+    pub target: String,
+    /// the title of the link or image, empty if it has none
+    pub title: String,
+    /// the link text or the alt text of the image, without formatting
+    pub text: String,
+    /// the position of the link, or of the innermost element containing it, if the reader
+    /// recorded source positions
+    pub position: Option<SourcePosition>,
+}
+
+/// The links and images of `document`, including the ones in the metadata, in document
+/// order.
+pub fn link_inventory(mut document: pandoc_ast::Pandoc) -> Vec<DocumentLink> {
+    let mut collector = Collector::default();
+    collector.walk_pandoc(&mut document);
+    collector.links
+}
+
+#[derive(Default)]
+struct Collector {
+    links: Vec<DocumentLink>,
+    /// the `data-pos` of the innermost element seen so far
+    position: Option<SourcePosition>,
+}
+
+fn position(attr: &Attr) -> Option<SourcePosition> {
+    attr.2
+        .iter()
+        .find(|(key, _)| key == "data-pos")
+        .and_then(|(_, pos)| SourcePosition::parse(pos))
+}
+
+impl Collector {
+    /// walk the contents of an element with the attributes `attr`
+    fn enter<F: FnOnce(&mut Collector)>(&mut self, attr: &Attr, walk: F) {
+        let outer = match position(attr) {
+            Some(position) => self.position.replace(position),
+            None => self.position.clone(),
+        };
+        walk(self);
+        self.position = outer;
+    }
+}
+
+impl MutVisitor for Collector {
+    fn visit_block(&mut self, block: &mut Block) {
+        match *block {
+            Block::Div(ref attr, _) | Block::Header(_, ref attr, _) => {
+                let attr = attr.clone();
+                self.enter(&attr, |this| this.walk_block(block))
+            }
+            _ => self.walk_block(block),
+        }
+    }
+
+    fn visit_inline(&mut self, inline: &mut Inline) {
+        match *inline {
+            Inline::Link(ref attr, ref text, (ref target, ref title))
+            | Inline::Image(ref attr, ref text, (ref target, ref title)) => {
+                self.links.push(DocumentLink {
+                    kind: match *inline {
+                        Inline::Image(..) => LinkKind::Image,
+                        _ => LinkKind::Link,
+                    },
+                    target: target.clone(),
+                    title: title.clone(),
+                    text: stringify(text),
+                    position: position(attr).or_else(|| self.position.clone()),
+                });
+                let attr = attr.clone();
+                self.enter(&attr, |this| this.walk_inline(inline))
+            }
+            Inline::Span(ref attr, _) => {
+                let attr = attr.clone();
+                self.enter(&attr, |this| this.walk_inline(inline))
+            }
+            _ => self.walk_inline(inline),
+        }
+    }
+}
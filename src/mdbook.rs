@@ -0,0 +1,210 @@
+//! mdBook backend adapter
+//!
+//! mdBook invokes alternative backends with a JSON description of the book on stdin. This
+//! module parses that description and maps the `[output.pandoc]` table of `book.toml` onto a
+//! [`Pandoc`](../struct.Pandoc.html) builder, so a backend binary can be as short as
+//!
+//! ```no_run
+//! let ctx = pandoc::mdbook::RenderContext::from_stdin().unwrap();
+//! pandoc::mdbook::render(&ctx).unwrap();
+//! ```
+//!
+//! with a `book.toml` like
+//!
+//! ```toml
+//! [output.pandoc]
+//! command = "my-pandoc-backend"
+//! output-file = "book.pdf"
+//! pdf-engine = "xelatex"
+//! toc = true
+//! ```
+//!
+//! Supported keys of `[output.pandoc]` are `output-file` (default `book.pdf`, relative to
+//! the backend's destination directory), `format`, `pdf-engine`, `template`, `toc`,
+//! `number-sections` and the `variables` table. The book's title, authors and language are
+//! passed as metadata.
+
+use crate::{InputFormat, InputKind, OutputFormat, OutputKind};
+use crate::{Pandoc, PandocError, PandocOption, PandocOutput};
+use serde::Deserialize;
+use std::collections::BTreeMap;
+use std::path::{Path, PathBuf};
+
+/// The description of the book mdBook passes to a backend on stdin.
+#[derive(Deserialize, Clone, Debug)]
+pub struct RenderContext {
+    /// the directory containing `book.toml`
+    pub root: PathBuf,
+    /// the directory the backend should write its output to
+    pub destination: PathBuf,
+    /// the book's chapters
+    pub book: Book,
+    /// the full `book.toml` configuration
+    pub config: serde_json::Value,
+}
+
+/// The chapters of a book
+#[derive(Deserialize, Clone, Debug)]
+pub struct Book {
+    /// the top-level entries of the table of contents
+    pub sections: Vec<BookItem>,
+}
+
+/// An entry of the table of contents
+#[derive(Deserialize, Clone, Debug)]
+pub enum BookItem {
+    /// a chapter, possibly with nested chapters
+    Chapter(Chapter),
+    /// a separator line
+    Separator,
+    /// the title of a part of the book
+    PartTitle(String),
+}
+
+/// A single chapter
+#[derive(Deserialize, Clone, Debug)]
+pub struct Chapter {
+    /// the chapter's title
+    pub name: String,
+    /// the markdown content, after mdBook's preprocessors ran
+    pub content: String,
+    /// the chapter's nested chapters
+    #[serde(default)]
+    pub sub_items: Vec<BookItem>,
+    /// the path of the chapter's source relative to the source directory, `None` for drafts
+    pub path: Option<PathBuf>,
+}
+
+/// The `[output.pandoc]` table of `book.toml`
+#[derive(Deserialize, Clone, Debug, Default)]
+#[serde(default, rename_all = "kebab-case")]
+struct Config {
+    output_file: Option<PathBuf>,
+    format: Option<String>,
+    pdf_engine: Option<PathBuf>,
+    template: Option<PathBuf>,
+    toc: bool,
+    number_sections: bool,
+    variables: BTreeMap<String, String>,
+}
+
+impl RenderContext {
+    /// Parse the render context from a reader.
+    pub fn from_reader<R: std::io::Read>(reader: R) -> Result<RenderContext, PandocError> {
+        serde_json::from_reader(reader).map_err(|e| PandocError::InvalidConfig(e.to_string()))
+    }
+
+    /// Parse the render context mdBook passes on stdin.
+    pub fn from_stdin() -> Result<RenderContext, PandocError> {
+        RenderContext::from_reader(std::io::stdin().lock())
+    }
+
+    /// the source directory of the book
+    fn src_dir(&self) -> PathBuf {
+        let src = self.config["book"]["src"].as_str().unwrap_or("src");
+        self.root.join(src)
+    }
+}
+
+fn collect_chapters<'a>(items: &'a [BookItem], chapters: &mut Vec<&'a Chapter>) {
+    for item in items {
+        if let BookItem::Chapter(ref chapter) = *item {
+            if chapter.path.is_some() {
+                chapters.push(chapter);
+            }
+            collect_chapters(&chapter.sub_items, chapters);
+        }
+    }
+}
+
+/// Create a `Pandoc` builder converting the book according to its `[output.pandoc]` table.
+pub fn to_pandoc(ctx: &RenderContext) -> Result<Pandoc, PandocError> {
+    let config: Config = match ctx.config["output"].get("pandoc") {
+        Some(table) => serde_json::from_value(table.clone())
+            .map_err(|e| PandocError::InvalidConfig(e.to_string()))?,
+        None => Config::default(),
+    };
+    let mut chapters = Vec::new();
+    collect_chapters(&ctx.book.sections, &mut chapters);
+    let content = chapters
+        .iter()
+        .map(|chapter| chapter.content.as_str())
+        .collect::<Vec<_>>()
+        .join("\n\n");
+
+    let src_dir = ctx.src_dir();
+    let mut resource_path = vec![src_dir.clone()];
+    for chapter in &chapters {
+        if let Some(dir) = chapter.path.as_ref().and_then(|path| path.parent()) {
+            let dir = src_dir.join(dir);
+            if !resource_path.contains(&dir) {
+                resource_path.push(dir);
+            }
+        }
+    }
+
+    let mut pandoc = Pandoc::new();
+    pandoc.set_input(InputKind::Pipe(content));
+    pandoc.set_input_format(InputFormat::Markdown, Vec::new());
+    let output_file = config
+        .output_file
+        .unwrap_or_else(|| PathBuf::from("book.pdf"));
+    pandoc.set_output(OutputKind::File(ctx.destination.join(output_file)));
+    if let Some(format) = config.format {
+        pandoc.set_output_format(OutputFormat::Other(format), Vec::new());
+    }
+    pandoc.add_option(PandocOption::ResourcePath(resource_path));
+    pandoc.add_option(PandocOption::Standalone);
+    if let Some(engine) = config.pdf_engine {
+        pandoc.add_option(PandocOption::PdfEngine(engine));
+    }
+    if let Some(template) = config.template {
+        pandoc.add_option(PandocOption::Template(resolve(&ctx.root, template)));
+    }
+    if config.toc {
+        pandoc.set_toc();
+    }
+    if config.number_sections {
+        pandoc.set_number_sections();
+    }
+    let book = &ctx.config["book"];
+    if let Some(title) = book["title"].as_str() {
+        pandoc.add_option(PandocOption::Meta(
+            "title".to_string(),
+            Some(title.to_string()),
+        ));
+    }
+    for author in book["authors"].as_array().into_iter().flatten() {
+        if let Some(author) = author.as_str() {
+            pandoc.add_option(PandocOption::Meta(
+                "author".to_string(),
+                Some(author.to_string()),
+            ));
+        }
+    }
+    if let Some(lang) = book["language"].as_str() {
+        pandoc.add_option(PandocOption::Meta(
+            "lang".to_string(),
+            Some(lang.to_string()),
+        ));
+    }
+    for (key, value) in config.variables {
+        pandoc.set_variable(&key, &value);
+    }
+    Ok(pandoc)
+}
+
+/// relative paths in `book.toml` are relative to the book's root
+fn resolve(root: &Path, path: PathBuf) -> PathBuf {
+    if path.is_relative() {
+        root.join(path)
+    } else {
+        path
+    }
+}
+
+/// Convert the book according to its `[output.pandoc]` table.
+pub fn render(ctx: &RenderContext) -> Result<PandocOutput, PandocError> {
+    std::fs::create_dir_all(&ctx.destination).map_err(PandocError::IoErr)?;
+    to_pandoc(ctx)?.execute()
+}
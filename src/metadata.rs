@@ -0,0 +1,68 @@
+//! typed document metadata
+
+/// An author of the document, see
+/// [`Pandoc::set_authors`](struct.Pandoc.html#method.set_authors).
+#[derive(PartialEq, Eq, Clone, Debug, Default)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[cfg_attr(feature = "serde", serde(default))]
+pub struct Author {
+    /// the author's name, the `author` metadata, or its `name` field if any other field is set
+    pub name: String,
+    /// the institutions the author belongs to, the `affiliation` list of the author
+    pub affiliations: Vec<String>,
+    /// the author's email address, the `email` field of the author
+    pub email: Option<String>,
+}
+
+impl Author {
+    /// An author without affiliations or email address.
+    pub fn new<T: AsRef<str> + ?Sized>(name: &T) -> Author {
+        Author {
+            name: name.as_ref().to_owned(),
+            ..Author::default()
+        }
+    }
+
+    fn is_plain(&self) -> bool {
+        self.affiliations.is_empty() && self.email.is_none()
+    }
+}
+
+/// quote a string for YAML, JSON strings being valid YAML
+fn quote(text: &str) -> String {
+    let mut quoted = String::with_capacity(text.len() + 2);
+    quoted.push('"');
+    for c in text.chars() {
+        match c {
+            '"' => quoted.push_str("\\\""),
+            '\\' => quoted.push_str("\\\\"),
+            '\n' => quoted.push_str("\\n"),
+            '\t' => quoted.push_str("\\t"),
+            c if c.is_control() => quoted.push_str(&format!("\\u{:04x}", c as u32)),
+            c => quoted.push(c),
+        }
+    }
+    quoted.push('"');
+    quoted
+}
+
+/// The YAML metadata for `authors`, or `None` if they can be passed with `--metadata`.
+pub(crate) fn authors_yaml(authors: &[Author]) -> Option<String> {
+    if authors.iter().all(Author::is_plain) {
+        return None;
+    }
+    let mut yaml = String::from("author:\n");
+    for author in authors {
+        yaml.push_str(&format!("- name: {}\n", quote(&author.name)));
+        if !author.affiliations.is_empty() {
+            yaml.push_str("  affiliation:\n");
+            for affiliation in &author.affiliations {
+                yaml.push_str(&format!("  - {}\n", quote(affiliation)));
+            }
+        }
+        if let Some(ref email) = author.email {
+            yaml.push_str(&format!("  email: {}\n", quote(email)));
+        }
+    }
+    Some(yaml)
+}
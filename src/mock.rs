@@ -0,0 +1,202 @@
+//! test double for pipelines built on this crate
+//!
+//! [`MockExecutor`] records every invocation instead of running pandoc and answers with canned
+//! outputs, so conversions can be unit-tested on machines without pandoc:
+//!
+//! ```
+//! use pandoc::mock::MockExecutor;
+//!
+//! let mock = MockExecutor::new();
+//! mock.push_output("<p>hello</p>\n");
+//!
+//! let mut pandoc = pandoc::new();
+//! pandoc.set_input(pandoc::InputKind::Pipe("hello".to_string()));
+//! pandoc.set_output(pandoc::OutputKind::Pipe);
+//! pandoc.set_output_format(pandoc::OutputFormat::Html, Vec::new());
+//! match pandoc.execute_with(&mock).unwrap() {
+//!     pandoc::PandocOutput::ToBuffer(html) => assert_eq!(html, "<p>hello</p>\n"),
+//!     _ => unreachable!(),
+//! }
+//!
+//! let calls = mock.calls();
+//! assert_eq!(calls[0].stdin.as_deref(), Some(&b"hello"[..]));
+//! assert!(calls[0].args.iter().any(|arg| arg == "html"));
+//! ```
+
+use crate::{Invocation, PandocError, PandocExecutor};
+use std::collections::{HashMap, VecDeque};
+use std::ffi::OsString;
+use std::path::PathBuf;
+use std::process::{ExitStatus, Output};
+use std::sync::Mutex;
+
+/// An invocation recorded by [`MockExecutor`]
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct RecordedCall {
+    /// the program that would have been run
+    pub program: OsString,
+    /// the command-line arguments, without the program
+    pub args: Vec<OsString>,
+    /// the data that would have been written to stdin
+    pub stdin: Option<Vec<u8>>,
+}
+
+impl RecordedCall {
+    /// The file pandoc would have written to with `-o`, if any.
+    pub fn output_file(&self) -> Option<PathBuf> {
+        let pos = self.args.iter().rposition(|arg| arg == "-o")?;
+        match self.args.get(pos + 1) {
+            Some(file) if file != "-" => Some(PathBuf::from(file)),
+            _ => None,
+        }
+    }
+}
+
+/// A canned answer of [`MockExecutor`]
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct MockResponse {
+    /// the exit code of the simulated pandoc process
+    pub code: i32,
+    /// the output, written to stdout or to the output file given with `-o`
+    pub output: Vec<u8>,
+    /// the stderr output, e.g. warnings
+    pub stderr: Vec<u8>,
+}
+
+/// An executor that records invocations and answers them with canned responses.
+///
+/// Responses are returned in the order they were pushed. Once all are used up, every
+/// invocation succeeds with empty output. Like pandoc, the mock writes the output to the file
+/// named by `-o` if there is one, so file outputs pass the existence checks of `execute`.
+///
+/// The `--list-extensions` queries checking the extensions of the formats are answered
+/// separately, with the extensions set with [`set_extensions`](#method.set_extensions). They
+/// neither use up a response nor are they recorded, and without extensions set for the
+/// format, any extension is passed on unchecked.
+#[derive(Debug, Default)]
+pub struct MockExecutor {
+    responses: Mutex<VecDeque<MockResponse>>,
+    calls: Mutex<Vec<RecordedCall>>,
+    extensions: Mutex<HashMap<String, Vec<String>>>,
+}
+
+impl MockExecutor {
+    /// Create a mock without any canned responses.
+    pub fn new() -> MockExecutor {
+        MockExecutor::default()
+    }
+
+    /// Queue a response.
+    pub fn push_response(&self, response: MockResponse) -> &Self {
+        self.responses.lock().unwrap().push_back(response);
+        self
+    }
+
+    /// Queue a successful run producing `output`.
+    pub fn push_output<T: Into<Vec<u8>>>(&self, output: T) -> &Self {
+        self.push_response(MockResponse {
+            code: 0,
+            output: output.into(),
+            stderr: Vec::new(),
+        })
+    }
+
+    /// Queue a successful run producing `output` and printing `stderr`, e.g. warnings.
+    pub fn push_output_with_stderr<T: Into<Vec<u8>>, U: Into<Vec<u8>>>(
+        &self,
+        output: T,
+        stderr: U,
+    ) -> &Self {
+        self.push_response(MockResponse {
+            code: 0,
+            output: output.into(),
+            stderr: stderr.into(),
+        })
+    }
+
+    /// Queue a failed run with the exit code `code` and the error message `stderr`.
+    pub fn push_failure<T: Into<Vec<u8>>>(&self, code: i32, stderr: T) -> &Self {
+        self.push_response(MockResponse {
+            code,
+            output: Vec::new(),
+            stderr: stderr.into(),
+        })
+    }
+
+    /// Answer the `--list-extensions` query for `format` with `extensions`, the names
+    /// without `+` or `-`.
+    pub fn set_extensions(&self, format: &str, extensions: &[&str]) -> &Self {
+        let extensions = extensions.iter().map(|name| name.to_string()).collect();
+        self.extensions
+            .lock()
+            .unwrap()
+            .insert(format.to_string(), extensions);
+        self
+    }
+
+    /// The invocations recorded so far, oldest first.
+    pub fn calls(&self) -> Vec<RecordedCall> {
+        self.calls.lock().unwrap().clone()
+    }
+}
+
+impl PandocExecutor for MockExecutor {
+    fn execute(&self, invocation: Invocation) -> Result<Output, PandocError> {
+        let query = invocation
+            .args()
+            .find_map(|arg| arg.to_str()?.strip_prefix("--list-extensions="));
+        if let Some(format) = query {
+            let extensions = self.extensions.lock().unwrap();
+            let stdout = match extensions.get(format) {
+                Some(names) => names.iter().map(|name| format!("+{}\n", name)).collect(),
+                None => String::new(),
+            };
+            return Ok(Output {
+                status: exit_status(0),
+                stdout: stdout.into_bytes(),
+                stderr: Vec::new(),
+            });
+        }
+        let call = RecordedCall {
+            program: invocation.program().to_owned(),
+            args: invocation.args().map(ToOwned::to_owned).collect(),
+            stdin: invocation.input().map(ToOwned::to_owned),
+        };
+        let response = self
+            .responses
+            .lock()
+            .unwrap()
+            .pop_front()
+            .unwrap_or(MockResponse {
+                code: 0,
+                output: Vec::new(),
+                stderr: Vec::new(),
+            });
+        let mut stdout = response.output;
+        if response.code == 0 {
+            if let Some(file) = call.output_file() {
+                std::fs::write(file, &stdout).map_err(PandocError::IoErr)?;
+                stdout.clear();
+            }
+        }
+        self.calls.lock().unwrap().push(call);
+        Ok(Output {
+            status: exit_status(response.code),
+            stdout,
+            stderr: response.stderr,
+        })
+    }
+}
+
+#[cfg(unix)]
+fn exit_status(code: i32) -> ExitStatus {
+    use std::os::unix::process::ExitStatusExt;
+    // the raw value is a wait status, with the exit code in the second byte
+    ExitStatus::from_raw((code & 0xff) << 8)
+}
+
+#[cfg(windows)]
+fn exit_status(code: i32) -> ExitStatus {
+    use std::os::windows::process::ExitStatusExt;
+    ExitStatus::from_raw(code as u32)
+}
@@ -0,0 +1,92 @@
+//! the heading hierarchy of a document
+
+use pandoc_ast::{Block, Inline};
+
+/// A heading of the document with the headings nested below it, see
+/// [`Pandoc::extract_toc`](struct.Pandoc.html#method.extract_toc).
+#[derive(PartialEq, Eq, Clone, Debug)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct Heading {
+    /// the level, 1 for top-level headings
+    pub level: u32,
+    /// the text of the heading, without formatting
+    pub text: String,
+    /// the identifier pandoc generated or the document gave the heading, used for links
+    pub identifier: String,
+    /// the headings of a higher level up to the next heading of this level or lower
+    pub children: Vec<Heading>,
+}
+
+/// The plain text of `inlines`, like pandoc's `stringify`, footnotes are left out.
+pub(crate) fn stringify(inlines: &[Inline]) -> String {
+    let mut text = String::new();
+    push_text(&mut text, inlines);
+    text
+}
+
+fn push_text(text: &mut String, inlines: &[Inline]) {
+    for inline in inlines {
+        match *inline {
+            Inline::Str(ref s) | Inline::Code(_, ref s) | Inline::Math(_, ref s) => {
+                text.push_str(s)
+            }
+            Inline::Space | Inline::SoftBreak | Inline::LineBreak => text.push(' '),
+            Inline::Emph(ref inner)
+            | Inline::Underline(ref inner)
+            | Inline::Strong(ref inner)
+            | Inline::Strikeout(ref inner)
+            | Inline::Superscript(ref inner)
+            | Inline::Subscript(ref inner)
+            | Inline::SmallCaps(ref inner)
+            | Inline::Cite(_, ref inner)
+            | Inline::Link(_, ref inner, _)
+            | Inline::Image(_, ref inner, _)
+            | Inline::Span(_, ref inner) => push_text(text, inner),
+            Inline::Quoted(_, ref inner) => {
+                text.push('"');
+                push_text(text, inner);
+                text.push('"');
+            }
+            Inline::RawInline(..) | Inline::Note(_) => {}
+        }
+    }
+}
+
+/// The headings of `document` as a tree.
+///
+/// Like pandoc's table of contents, this includes headings in divs but not in block quotes
+/// or lists, and skips headings with the `unlisted` class.
+pub fn heading_tree(document: &pandoc_ast::Pandoc) -> Vec<Heading> {
+    let mut tree = Vec::new();
+    collect(&mut tree, &document.blocks);
+    tree
+}
+
+fn collect(tree: &mut Vec<Heading>, blocks: &[Block]) {
+    for block in blocks {
+        match *block {
+            Block::Header(level, (ref identifier, ref classes, _), ref inlines) => {
+                if classes.iter().any(|class| class == "unlisted") {
+                    continue;
+                }
+                let heading = Heading {
+                    level: level as u32,
+                    text: stringify(inlines),
+                    identifier: identifier.clone(),
+                    children: Vec::new(),
+                };
+                insert(tree, heading);
+            }
+            Block::Div(_, ref blocks) => collect(tree, blocks),
+            _ => {}
+        }
+    }
+}
+
+/// add `heading` below the last heading of a lower level
+fn insert(tree: &mut Vec<Heading>, heading: Heading) {
+    match tree.last_mut() {
+        Some(last) if last.level < heading.level => insert(&mut last.children, heading),
+        _ => tree.push(heading),
+    }
+}
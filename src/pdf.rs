@@ -0,0 +1,148 @@
+//! analysis of failed PDF engine runs
+
+/// Details extracted from the LaTeX or typst log pandoc prints when PDF generation fails.
+///
+/// The raw log is rarely helpful to end users, so the known failure patterns are turned into
+/// short, actionable [`hints`](#structfield.hints).
+#[derive(PartialEq, Eq, Clone, Debug, Default)]
+#[non_exhaustive]
+pub struct PdfError {
+    /// error lines of the engine (starting with `!` or `error:`) in the order they were reported
+    pub errors: Vec<String>,
+    /// LaTeX packages (`.sty`) and classes (`.cls`) that could not be found, without extension,
+    /// or typst packages like `@preview/cetz:0.2.2`
+    pub missing_packages: Vec<String>,
+    /// other files (fonts, images, includes) that could not be found
+    pub missing_files: Vec<String>,
+    /// the PDF engine executable, if pandoc could not find it
+    pub missing_engine: Option<String>,
+    /// human readable suggestions on how to fix the failure
+    pub hints: Vec<String>,
+}
+
+impl PdfError {
+    /// Parse the stderr output of a failed pandoc PDF run.
+    ///
+    /// Returns `None` if the output contains nothing that looks like a PDF engine failure.
+    pub fn from_stderr(stderr: &str) -> Option<PdfError> {
+        let mut pdf = PdfError::default();
+        for line in stderr.lines().map(str::trim) {
+            if let Some((engine, _)) =
+                line.split_once(" not found. Please select a different --pdf-engine")
+            {
+                pdf.missing_engine = Some(engine.to_string());
+                pdf.hints.push(format!(
+                    "the PDF engine {0} is not installed or not in PATH, install {0} or \
+                     select another engine with PandocOption::PdfEngine",
+                    engine
+                ));
+                continue;
+            }
+            if let Some(message) = line.strip_prefix("error: ") {
+                pdf.errors.push(line.to_string());
+                pdf.typst_error(message);
+                continue;
+            }
+            if let Some(font) = line.strip_prefix("warning: unknown font family: ") {
+                pdf.missing_files.push(font.to_string());
+                pdf.hints.push(format!(
+                    "the font {} is not installed, install it or choose a different font",
+                    font
+                ));
+                continue;
+            }
+            if !line.starts_with('!') {
+                continue;
+            }
+            pdf.errors.push(line.to_string());
+            if let Some(file) = between(line, "File `", "' not found") {
+                match file.rsplit_once('.') {
+                    Some((package, "sty")) | Some((package, "cls")) => {
+                        pdf.missing_packages.push(package.to_string());
+                        pdf.hints.push(format!(
+                            "missing LaTeX package {0} — run `tlmgr install {0}` (TeX Live) \
+                             or `mpm --install={0}` (MiKTeX)",
+                            package
+                        ));
+                    }
+                    _ => {
+                        pdf.missing_files.push(file.to_string());
+                        pdf.hints.push(format!("missing file {}", file));
+                    }
+                }
+            } else if let Some(font) = between(line, "The font \"", "\" cannot be found") {
+                pdf.missing_files.push(font.to_string());
+                pdf.hints.push(format!(
+                    "the font {} is not installed, install it or choose a different font",
+                    font
+                ));
+            } else if line.contains("Metric (TFM) file not found") {
+                pdf.hints.push(
+                    "a font used by the document is missing from the TeX installation, \
+                     install the font package or use xelatex/lualatex with a system font"
+                        .to_string(),
+                );
+            } else if line.contains("not set up for use with LaTeX") {
+                pdf.hints.push(
+                    "the document contains unicode characters pdflatex cannot handle, \
+                     select xelatex or lualatex with PandocOption::PdfEngine"
+                        .to_string(),
+                );
+            } else if line.contains("Undefined control sequence") {
+                pdf.hints.push(
+                    "an unknown LaTeX command was used, check raw LaTeX in the input and \
+                     the template"
+                        .to_string(),
+                );
+            }
+        }
+        if pdf.errors.is_empty() && pdf.missing_engine.is_none() {
+            None
+        } else {
+            Some(pdf)
+        }
+    }
+
+    /// analyze an `error:` line of typst
+    fn typst_error(&mut self, message: &str) {
+        if let Some(file) = between(message, "file not found (searched at ", ")") {
+            self.missing_files.push(file.to_string());
+            self.hints.push(format!("missing file {}", file));
+        } else if let Some(package) = between(message, "package not found (searched for ", ")") {
+            self.missing_packages.push(package.to_string());
+            self.hints.push(format!(
+                "the typst package {} does not exist, check its name and version",
+                package
+            ));
+        } else if message.starts_with("failed to download package") {
+            self.hints.push(
+                "typst could not download a package, check the network connection or \
+                 install the package into typst's package directory"
+                    .to_string(),
+            );
+        }
+    }
+}
+
+impl std::fmt::Display for PdfError {
+    fn fmt(&self, fmt: &mut std::fmt::Formatter) -> std::fmt::Result {
+        if self.hints.is_empty() {
+            for error in &self.errors {
+                writeln!(fmt, "{}", error)?;
+            }
+        }
+        for hint in &self.hints {
+            writeln!(fmt, "{}", hint)?;
+        }
+        Ok(())
+    }
+}
+
+impl std::error::Error for PdfError {}
+
+/// the part of `s` between `start` and `end`
+fn between<'a>(s: &'a str, start: &str, end: &str) -> Option<&'a str> {
+    let from = s.find(start)? + start.len();
+    let len = s[from..].find(end)?;
+    Some(&s[from..from + len])
+}
@@ -0,0 +1,217 @@
+//! bounded-concurrency execution of many conversions
+
+use crate::{Pandoc, PandocError, PandocOutput};
+use std::future::Future;
+use std::pin::Pin;
+use std::sync::mpsc::{sync_channel, Receiver, SyncSender, TrySendError};
+use std::sync::{Arc, Condvar, Mutex};
+use std::task::{Context, Poll, Waker};
+use std::thread::JoinHandle;
+
+type Job = Box<dyn FnOnce() + Send>;
+type JobResult = std::thread::Result<Result<PandocOutput, PandocError>>;
+
+/// A fixed number of worker threads running conversions from a bounded queue.
+///
+/// At most `workers` pandoc processes (plus the PDF engines they start) run at the same time.
+/// Once `capacity` jobs are waiting, [`submit`](#method.submit) blocks until a worker picks up
+/// a job, and [`try_submit`](#method.try_submit) hands the job back.
+///
+/// Jobs are closures creating the configuration on the worker thread, which can also move
+/// in a configured `Pandoc`:
+///
+/// ```no_run
+/// let pool = pandoc::ConversionPool::new(4, 16);
+/// let mut base = pandoc::new();
+/// base.add_option(pandoc::PandocOption::Standalone);
+/// let handles: Vec<_> = ["a.md", "b.md"]
+///     .iter()
+///     .map(|input| {
+///         let mut pandoc = base.clone();
+///         pool.submit(move || {
+///             pandoc.add_input(input);
+///             pandoc.set_output(pandoc::OutputKind::File(format!("{}.html", input).into()));
+///             pandoc
+///         })
+///     })
+///     .collect();
+/// for handle in handles {
+///     handle.wait().unwrap();
+/// }
+/// ```
+///
+/// Dropping the pool waits for all queued jobs to finish.
+#[derive(Debug)]
+pub struct ConversionPool {
+    sender: Option<SyncSender<Job>>,
+    workers: Vec<JoinHandle<()>>,
+}
+
+#[derive(Default)]
+struct State {
+    result: Option<JobResult>,
+    waker: Option<Waker>,
+}
+
+#[derive(Default)]
+struct Slot {
+    state: Mutex<State>,
+    done: Condvar,
+}
+
+/// Handle to the result of a job submitted to a [`ConversionPool`].
+///
+/// The result can be waited for with [`wait`](#method.wait), or the handle can be `.await`ed
+/// from async code.
+pub struct JobHandle {
+    slot: Arc<Slot>,
+}
+
+impl std::fmt::Debug for JobHandle {
+    fn fmt(&self, fmt: &mut std::fmt::Formatter) -> std::fmt::Result {
+        fmt.debug_struct("JobHandle")
+            .field("finished", &self.is_finished())
+            .finish()
+    }
+}
+
+impl ConversionPool {
+    /// Start `workers` worker threads with a queue for `capacity` waiting jobs.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `workers` is zero.
+    pub fn new(workers: usize, capacity: usize) -> ConversionPool {
+        assert!(workers > 0, "a conversion pool needs at least one worker");
+        let (sender, receiver) = sync_channel::<Job>(capacity);
+        let receiver = Arc::new(Mutex::new(receiver));
+        let workers = (0..workers)
+            .map(|i| {
+                let receiver = Arc::clone(&receiver);
+                std::thread::Builder::new()
+                    .name(format!("pandoc-worker-{}", i))
+                    .spawn(move || work(&receiver))
+                    .expect("failed to spawn pandoc worker thread")
+            })
+            .collect();
+        ConversionPool {
+            sender: Some(sender),
+            workers,
+        }
+    }
+
+    fn job<F>(build: F) -> (Job, JobHandle)
+    where
+        F: FnOnce() -> Pandoc + Send + 'static,
+    {
+        let slot = Arc::new(Slot::default());
+        let handle = JobHandle {
+            slot: Arc::clone(&slot),
+        };
+        let job = Box::new(move || {
+            let result =
+                std::panic::catch_unwind(std::panic::AssertUnwindSafe(|| build().execute()));
+            let mut state = slot.state.lock().unwrap();
+            state.result = Some(result);
+            if let Some(waker) = state.waker.take() {
+                waker.wake();
+            }
+            slot.done.notify_all();
+        });
+        (job, handle)
+    }
+
+    /// Queue a conversion, blocking while the queue is full.
+    pub fn submit<F>(&self, build: F) -> JobHandle
+    where
+        F: FnOnce() -> Pandoc + Send + 'static,
+    {
+        let (job, handle) = ConversionPool::job(build);
+        self.sender
+            .as_ref()
+            .expect("sender is only taken on drop")
+            .send(job)
+            .expect("pandoc workers exited");
+        handle
+    }
+
+    /// Queue a conversion if there is room in the queue, otherwise return the job.
+    pub fn try_submit<F>(&self, build: F) -> Result<JobHandle, F>
+    where
+        F: FnOnce() -> Pandoc + Send + 'static,
+    {
+        let sender = self.sender.as_ref().expect("sender is only taken on drop");
+        // keep access to the closure, so it can be handed back if the queue is full
+        let build = Arc::new(Mutex::new(Some(build)));
+        let queued = Arc::clone(&build);
+        let (job, handle) = ConversionPool::job(move || {
+            let build = queued.lock().unwrap().take().expect("job runs once");
+            build()
+        });
+        match sender.try_send(job) {
+            Ok(()) => Ok(handle),
+            Err(TrySendError::Full(_)) | Err(TrySendError::Disconnected(_)) => {
+                let build = build.lock().unwrap().take();
+                Err(build.expect("rejected job did not run"))
+            }
+        }
+    }
+}
+
+fn work(receiver: &Mutex<Receiver<Job>>) {
+    loop {
+        // the lock is released before running the job
+        let job = receiver.lock().unwrap().recv();
+        match job {
+            Ok(job) => job(),
+            Err(_) => return,
+        }
+    }
+}
+
+impl Drop for ConversionPool {
+    fn drop(&mut self) {
+        // closing the channel makes the workers exit once the queue is drained
+        self.sender.take();
+        for worker in self.workers.drain(..) {
+            let _ = worker.join();
+        }
+    }
+}
+
+impl JobHandle {
+    /// Whether the conversion has finished.
+    pub fn is_finished(&self) -> bool {
+        self.slot.state.lock().unwrap().result.is_some()
+    }
+
+    /// Block until the conversion has finished and return its result.
+    ///
+    /// If creating the configuration panicked, the panic is resumed on the calling thread.
+    pub fn wait(self) -> Result<PandocOutput, PandocError> {
+        let mut state = self.slot.state.lock().unwrap();
+        loop {
+            if let Some(result) = state.result.take() {
+                return result.unwrap_or_else(|panic| std::panic::resume_unwind(panic));
+            }
+            state = self.slot.done.wait(state).unwrap();
+        }
+    }
+}
+
+impl Future for JobHandle {
+    type Output = Result<PandocOutput, PandocError>;
+
+    fn poll(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Self::Output> {
+        let mut state = self.slot.state.lock().unwrap();
+        match state.result.take() {
+            Some(result) => {
+                Poll::Ready(result.unwrap_or_else(|panic| std::panic::resume_unwind(panic)))
+            }
+            None => {
+                state.waker = Some(cx.waker().clone());
+                Poll::Pending
+            }
+        }
+    }
+}
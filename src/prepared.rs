@@ -0,0 +1,149 @@
+//! configurations assembled once and run for many inputs
+
+use crate::{
+    apply_filters, buffer_output, check_warnings, Filter, InputKind, OutputFormat, OutputKind,
+    Pandoc, PandocError, PandocExecutor, PandocOutput, ProcessExecutor, RunContext, WarningClass,
+};
+use std::ffi::OsString;
+use std::process::{Command, Stdio};
+use std::sync::Arc;
+
+/// A pandoc command assembled from a configuration, see
+/// [`Pandoc::freeze`](struct.Pandoc.html#method.freeze).
+///
+/// Runs the same command for every input, without validating the configuration and
+/// resolving the paths again. Can be shared between threads.
+#[derive(Clone)]
+pub struct PreparedPandoc {
+    program: OsString,
+    args: Vec<OsString>,
+    envs: Vec<(OsString, Option<OsString>)>,
+    context: RunContext,
+    preprocessors: Vec<Arc<dyn Fn(String) -> String + Send + Sync>>,
+    /// the command writing the JSON AST for the filters, and the filters
+    reader: Option<(Box<PreparedPandoc>, Vec<Filter>)>,
+    filter_api_versions: Vec<(u32, u32)>,
+    output_format: Option<OutputFormat>,
+    lossy_utf8: bool,
+    fail_on: Vec<WarningClass>,
+}
+
+impl std::fmt::Debug for PreparedPandoc {
+    fn fmt(&self, fmt: &mut std::fmt::Formatter) -> std::fmt::Result {
+        fmt.debug_struct("PreparedPandoc")
+            .field("program", &self.program)
+            .field("args", &self.args)
+            .field("reader", &self.reader.as_ref().map(|(reader, _)| reader))
+            .finish()
+    }
+}
+
+/// assemble the command of `pandoc` for piped inputs, see `Pandoc::freeze`
+pub(crate) fn freeze(mut pandoc: Pandoc) -> Result<PreparedPandoc, PandocError> {
+    if pandoc.cache_dir.is_some() {
+        return Err(PandocError::InvalidConfig(
+            "prepared commands can't use the cache".to_string(),
+        ));
+    }
+    match pandoc.output {
+        None | Some(OutputKind::Pipe) => pandoc.output = Some(OutputKind::Pipe),
+        _ => {
+            return Err(PandocError::InvalidConfig(
+                "prepared commands write to a pipe".to_string(),
+            ))
+        }
+    }
+    // the format can still be inferred from the configured input files
+    pandoc.infer_input_format()?;
+    pandoc.input = Some(InputKind::Pipe(String::new()));
+    let reader = match pandoc.split_reader() {
+        Some((reader, filters)) => {
+            pandoc.input = Some(InputKind::Pipe(String::new()));
+            Some((Box::new(freeze(reader)?), filters))
+        }
+        None => None,
+    };
+    let preprocessors = std::mem::take(&mut pandoc.input_preprocessors);
+    let filter_api_versions = pandoc.filter_api_versions.clone();
+    let output_format = pandoc
+        .output_format
+        .as_ref()
+        .map(|(format, _)| format.clone());
+    let lossy_utf8 = pandoc.lossy_utf8;
+    let fail_on = std::mem::take(&mut pandoc.fail_on);
+    let (cmd, _, context) = pandoc.prepare(None)?;
+    Ok(PreparedPandoc {
+        program: cmd.get_program().to_owned(),
+        args: cmd.get_args().map(ToOwned::to_owned).collect(),
+        envs: cmd
+            .get_envs()
+            .map(|(key, value)| (key.to_owned(), value.map(ToOwned::to_owned)))
+            .collect(),
+        context,
+        preprocessors,
+        reader,
+        filter_api_versions,
+        output_format,
+        lossy_utf8,
+        fail_on,
+    })
+}
+
+impl PreparedPandoc {
+    /// The command line, starting with the executable.
+    pub fn command_line(&self) -> Vec<OsString> {
+        std::iter::once(self.program.clone())
+            .chain(self.args.iter().cloned())
+            .collect()
+    }
+
+    /// Convert `input`, returning pandoc's output as a buffer.
+    pub fn execute(&self, input: &str) -> Result<PandocOutput, PandocError> {
+        self.execute_with(input, &ProcessExecutor)
+    }
+
+    /// Convert `input` with a custom executor, see
+    /// [`Pandoc::execute_with`](struct.Pandoc.html#method.execute_with).
+    pub fn execute_with(
+        &self,
+        input: &str,
+        executor: &impl PandocExecutor,
+    ) -> Result<PandocOutput, PandocError> {
+        let (output, stderr) = self.run(input.to_string(), executor)?;
+        check_warnings(&self.fail_on, &stderr)?;
+        buffer_output(output, self.output_format.as_ref(), self.lossy_utf8)
+    }
+
+    /// run the reader and the filters, if any, then the command, returns its stdout and
+    /// the stderr of all commands
+    fn run(
+        &self,
+        input: String,
+        executor: &dyn PandocExecutor,
+    ) -> Result<(Vec<u8>, Vec<u8>), PandocError> {
+        let input = self.preprocessors.iter().fold(input, |text, p| p(text));
+        let mut stderr = Vec::new();
+        let input = match self.reader {
+            Some((ref reader, ref filters)) => {
+                let (json, mut reader_stderr) = reader.run(input, executor)?;
+                stderr.append(&mut reader_stderr);
+                apply_filters(filters.clone(), &self.filter_api_versions, json)?
+            }
+            None => input.into_bytes(),
+        };
+        let mut cmd = Command::new(&self.program);
+        cmd.args(&self.args);
+        for (key, value) in &self.envs {
+            match *value {
+                Some(ref value) => cmd.env(key, value),
+                None => cmd.env_remove(key),
+            };
+        }
+        cmd.stdin(Stdio::piped())
+            .stdout(Stdio::piped())
+            .stderr(Stdio::piped());
+        let (mut o, _) = self.context.run(cmd, Some(input), executor)?;
+        stderr.append(&mut o.stderr);
+        Ok((o.stdout, stderr))
+    }
+}
@@ -0,0 +1,413 @@
+//! preconfigured builders for common kinds of documents
+//!
+//! The presets return an ordinary [`Pandoc`](../struct.Pandoc.html) builder, which only lacks
+//! the input and output, and can be adjusted further before executing it.
+
+use crate::{OutputFormat, OutputKind, Pandoc, PandocError, PandocOption};
+use std::path::{Path, PathBuf};
+use std::process::{Command, Stdio};
+
+/// A single HTML5 file with all images, stylesheets and scripts embedded.
+///
+/// `title` is used for the `<title>` element if the document has no `title` metadata of its
+/// own, which HTML requires. Pandoc's default stylesheet is included unless a stylesheet is
+/// added with `PandocOption::Css`. A table of contents can be added with `set_toc`:
+///
+/// ```no_run
+/// let mut pandoc = pandoc::presets::standalone_html("Report");
+/// pandoc
+///     .set_toc()
+///     .add_option(pandoc::PandocOption::Css("report.css".to_string()))
+///     .add_input("report.md")
+///     .set_output(pandoc::OutputKind::File("report.html".into()));
+/// pandoc.execute().unwrap();
+/// ```
+pub fn standalone_html<T: AsRef<str> + ?Sized>(title: &T) -> Pandoc {
+    let mut pandoc = Pandoc::new();
+    pandoc
+        .set_output_format(OutputFormat::Html5, Vec::new())
+        .add_option(PandocOption::Standalone)
+        .add_option(PandocOption::EmbedResources)
+        .add_option(PandocOption::Meta(
+            "pagetitle".to_string(),
+            Some(title.as_ref().to_string()),
+        ));
+    pandoc
+}
+
+/// Builder for EPUB books.
+///
+/// Collects the chapters, the cover and the styling of the book, and produces a `Pandoc`
+/// builder writing the EPUB file:
+///
+/// ```no_run
+/// let mut book = pandoc::presets::EpubBuilder::new("My Book");
+/// book.add_author("Jane Doe")
+///     .set_language("en-US")
+///     .add_chapter("intro.md")
+///     .add_chapter("usage.md")
+///     .set_cover_image("cover.png")
+///     .add_stylesheet("book.css")
+///     .set_chapter_level(2);
+/// book.build("my-book.epub").execute().unwrap();
+/// ```
+#[derive(Clone, Debug, Default)]
+pub struct EpubBuilder {
+    title: String,
+    authors: Vec<String>,
+    language: Option<String>,
+    date: Option<String>,
+    publisher: Option<String>,
+    rights: Option<String>,
+    chapters: Vec<PathBuf>,
+    cover_image: Option<PathBuf>,
+    stylesheets: Vec<PathBuf>,
+    fonts: Vec<PathBuf>,
+    chapter_level: Option<u32>,
+    toc: bool,
+}
+
+impl EpubBuilder {
+    /// Start a book with the given title.
+    pub fn new<T: AsRef<str> + ?Sized>(title: &T) -> EpubBuilder {
+        EpubBuilder {
+            title: title.as_ref().to_string(),
+            ..EpubBuilder::default()
+        }
+    }
+
+    /// Add an author, in the order they should be listed.
+    pub fn add_author<T: AsRef<str> + ?Sized>(&mut self, author: &T) -> &mut EpubBuilder {
+        self.authors.push(author.as_ref().to_string());
+        self
+    }
+
+    /// Set the language of the book as BCP 47 tag, pandoc defaults to `en-US`.
+    pub fn set_language<T: AsRef<str> + ?Sized>(&mut self, language: &T) -> &mut EpubBuilder {
+        self.language = Some(language.as_ref().to_string());
+        self
+    }
+
+    /// Set the publication date, preferably as `YYYY-MM-DD`.
+    pub fn set_date<T: AsRef<str> + ?Sized>(&mut self, date: &T) -> &mut EpubBuilder {
+        self.date = Some(date.as_ref().to_string());
+        self
+    }
+
+    /// Set the publisher.
+    pub fn set_publisher<T: AsRef<str> + ?Sized>(&mut self, publisher: &T) -> &mut EpubBuilder {
+        self.publisher = Some(publisher.as_ref().to_string());
+        self
+    }
+
+    /// Set the copyright statement.
+    pub fn set_rights<T: AsRef<str> + ?Sized>(&mut self, rights: &T) -> &mut EpubBuilder {
+        self.rights = Some(rights.as_ref().to_string());
+        self
+    }
+
+    /// Add a chapter file, chapters are concatenated in the order they were added.
+    pub fn add_chapter<T: AsRef<Path> + ?Sized>(&mut self, file: &T) -> &mut EpubBuilder {
+        self.chapters.push(file.as_ref().to_owned());
+        self
+    }
+
+    /// Set the cover image.
+    pub fn set_cover_image<T: AsRef<Path> + ?Sized>(&mut self, image: &T) -> &mut EpubBuilder {
+        self.cover_image = Some(image.as_ref().to_owned());
+        self
+    }
+
+    /// Add a stylesheet, replacing pandoc's default EPUB stylesheet.
+    pub fn add_stylesheet<T: AsRef<Path> + ?Sized>(&mut self, css: &T) -> &mut EpubBuilder {
+        self.stylesheets.push(css.as_ref().to_owned());
+        self
+    }
+
+    /// Embed a font file, it still has to be referenced by `@font-face` in a stylesheet.
+    pub fn add_font<T: AsRef<Path> + ?Sized>(&mut self, font: &T) -> &mut EpubBuilder {
+        self.fonts.push(font.as_ref().to_owned());
+        self
+    }
+
+    /// Split the book into separate files at headings of this level, pandoc defaults to 1.
+    pub fn set_chapter_level(&mut self, level: u32) -> &mut EpubBuilder {
+        self.chapter_level = Some(level);
+        self
+    }
+
+    /// Add a table of contents page at the beginning of the book.
+    ///
+    /// The navigation document of the EPUB is always generated.
+    pub fn set_toc(&mut self) -> &mut EpubBuilder {
+        self.toc = true;
+        self
+    }
+
+    /// Create the `Pandoc` builder writing the book to `output`.
+    pub fn build<T: AsRef<Path> + ?Sized>(&self, output: &T) -> Pandoc {
+        let mut pandoc = Pandoc::new();
+        pandoc.add_inputs(&self.chapters);
+        pandoc.set_output_format(OutputFormat::Epub, Vec::new());
+        pandoc.set_output(OutputKind::File(output.as_ref().to_owned()));
+        let mut meta = |key: &str, value: &str| {
+            pandoc.add_option(PandocOption::Meta(key.to_string(), Some(value.to_string())));
+        };
+        meta("title", &self.title);
+        for author in &self.authors {
+            meta("author", author);
+        }
+        let optional = [
+            ("lang", &self.language),
+            ("date", &self.date),
+            ("publisher", &self.publisher),
+            ("rights", &self.rights),
+        ];
+        for (key, value) in optional.iter() {
+            if let Some(value) = value {
+                meta(key, value);
+            }
+        }
+        if let Some(ref image) = self.cover_image {
+            pandoc.add_option(PandocOption::EpubCoverImage(image.clone()));
+        }
+        for css in &self.stylesheets {
+            pandoc.add_option(PandocOption::Css(css.display().to_string()));
+        }
+        for font in &self.fonts {
+            pandoc.add_option(PandocOption::EpubEmbedFont(font.clone()));
+        }
+        if let Some(level) = self.chapter_level {
+            pandoc.add_option(PandocOption::EpubChapterLevel(level));
+        }
+        if self.toc {
+            pandoc.set_toc();
+        }
+        pandoc
+    }
+}
+
+/// The slide show formats supported by [`slides`](fn.slides.html)
+#[derive(PartialEq, Eq, Copy, Clone, Debug)]
+#[non_exhaustive]
+pub enum SlideEngine {
+    /// reveal.js HTML slides
+    Revealjs,
+    /// LaTeX beamer, producing a PDF if the output file ends in `.pdf`
+    Beamer,
+    /// PowerPoint
+    Pptx,
+}
+
+/// Builder for slide shows, created by [`slides`](fn.slides.html).
+#[derive(Clone, Debug)]
+pub struct Slides {
+    engine: SlideEngine,
+    slide_level: Option<u32>,
+    incremental: bool,
+    theme: Option<String>,
+    color_theme: Option<String>,
+}
+
+/// Start a slide show for the given engine.
+///
+/// The engine specific settings are taken care of, e.g. the theme is passed as variable to
+/// reveal.js and beamer, but as reference document to PowerPoint:
+///
+/// ```no_run
+/// use pandoc::presets::{slides, SlideEngine};
+///
+/// let mut pandoc = slides(SlideEngine::Beamer)
+///     .set_slide_level(2)
+///     .set_incremental()
+///     .set_theme("metropolis")
+///     .build("talk.pdf");
+/// pandoc.add_input("talk.md");
+/// pandoc.execute().unwrap();
+/// ```
+pub fn slides(engine: SlideEngine) -> Slides {
+    Slides {
+        engine,
+        slide_level: None,
+        incremental: false,
+        theme: None,
+        color_theme: None,
+    }
+}
+
+impl Slides {
+    /// Headings of this level start a new slide, higher levels create title slides.
+    ///
+    /// By default pandoc uses the highest heading level that is followed by content.
+    pub fn set_slide_level(&mut self, level: u32) -> &mut Slides {
+        self.slide_level = Some(level);
+        self
+    }
+
+    /// Reveal list items one by one.
+    pub fn set_incremental(&mut self) -> &mut Slides {
+        self.incremental = true;
+        self
+    }
+
+    /// Set the theme: the name of a reveal.js or beamer theme, or the path of the reference
+    /// presentation for PowerPoint.
+    pub fn set_theme<T: AsRef<str> + ?Sized>(&mut self, theme: &T) -> &mut Slides {
+        self.theme = Some(theme.as_ref().to_string());
+        self
+    }
+
+    /// Set the beamer color theme, ignored by the other engines.
+    pub fn set_color_theme<T: AsRef<str> + ?Sized>(&mut self, theme: &T) -> &mut Slides {
+        self.color_theme = Some(theme.as_ref().to_string());
+        self
+    }
+
+    /// Create the `Pandoc` builder writing the slides to `output`.
+    pub fn build<T: AsRef<Path> + ?Sized>(&self, output: &T) -> Pandoc {
+        let mut pandoc = Pandoc::new();
+        let format = match self.engine {
+            SlideEngine::Revealjs => OutputFormat::Revealjs,
+            SlideEngine::Beamer => OutputFormat::Beamer,
+            SlideEngine::Pptx => OutputFormat::Pptx,
+        };
+        pandoc.set_output_format(format, Vec::new());
+        pandoc.set_output(OutputKind::File(output.as_ref().to_owned()));
+        pandoc.add_option(PandocOption::Standalone);
+        if let Some(level) = self.slide_level {
+            pandoc.set_slide_level(level);
+        }
+        if self.incremental {
+            pandoc.add_option(PandocOption::Incremental);
+        }
+        match (self.engine, &self.theme) {
+            (_, None) => {}
+            (SlideEngine::Pptx, Some(theme)) => {
+                pandoc.add_option(PandocOption::ReferenceDoc(PathBuf::from(theme)));
+            }
+            (_, Some(theme)) => {
+                pandoc.set_variable("theme", theme);
+            }
+        }
+        if let (SlideEngine::Beamer, Some(theme)) = (self.engine, &self.color_theme) {
+            pandoc.set_variable("colortheme", theme);
+        }
+        pandoc
+    }
+}
+
+/// the PDF engines probed by [`pdf`](fn.pdf.html), in order of preference
+const PDF_ENGINES: &[&str] = &["tectonic", "xelatex", "pdflatex", "wkhtmltopdf"];
+
+/// the stdout of `program`, if it runs successfully with the search path of `pandoc`
+fn probe(pandoc: &Pandoc, program: &str, args: &[&str]) -> Option<Vec<u8>> {
+    Command::new(program)
+        .args(args)
+        .env("PATH", pandoc.search_path())
+        .stdin(Stdio::null())
+        .stderr(Stdio::null())
+        .output()
+        .ok()
+        .filter(|output| output.status.success())
+        .map(|output| output.stdout)
+}
+
+/// PDF output with the first installed engine of tectonic, xelatex, pdflatex and wkhtmltopdf.
+///
+/// The engines are looked up in the same places pandoc looks for them. The page margins are
+/// set to 2.5cm, and with xelatex the DejaVu fonts are used if installed, as they cover many
+/// more scripts than the default Latin Modern fonts. The output file has to end in `.pdf`.
+///
+/// Returns `PandocError::PdfEngineNotFound` if none of the engines is installed.
+pub fn pdf() -> Result<Pandoc, PandocError> {
+    let mut pandoc = Pandoc::new();
+    let engine = PDF_ENGINES
+        .iter()
+        .find(|engine| probe(&pandoc, engine, &["--version"]).is_some())
+        .ok_or_else(|| {
+            PandocError::PdfEngineNotFound(PDF_ENGINES.iter().map(|e| e.to_string()).collect())
+        })?;
+    pandoc.add_option(PandocOption::PdfEngine(PathBuf::from(engine)));
+    match *engine {
+        "wkhtmltopdf" => {
+            for side in &["top", "bottom", "left", "right"] {
+                pandoc.set_variable(&format!("margin-{}", side), "25mm");
+            }
+        }
+        _ => {
+            pandoc.set_variable("geometry", "margin=2.5cm");
+        }
+    }
+    if *engine == "xelatex" {
+        let fonts = [
+            ("mainfont", "DejaVu Serif"),
+            ("sansfont", "DejaVu Sans"),
+            ("monofont", "DejaVu Sans Mono"),
+        ];
+        for (variable, font) in fonts.iter() {
+            let installed = probe(&pandoc, "fc-list", &[font]);
+            if matches!(installed, Some(ref out) if !out.is_empty()) {
+                pandoc.set_variable(variable, font);
+            }
+        }
+    }
+    Ok(pandoc)
+}
+
+/// the header man pages of `section` traditionally have
+fn man_header(section: u32) -> Option<&'static str> {
+    Some(match section {
+        1 => "General Commands Manual",
+        2 => "System Calls Manual",
+        3 => "Library Functions Manual",
+        4 => "Kernel Interfaces Manual",
+        5 => "File Formats Manual",
+        6 => "Games Manual",
+        7 => "Miscellaneous Information Manual",
+        8 => "System Manager's Manual",
+        9 => "Kernel Developer's Manual",
+        _ => return None,
+    })
+}
+
+/// A standalone man page in `section`, e.g. 1 for commands.
+///
+/// `title` is the name of the page, usually the command name in upper case, and `date` the
+/// date shown in the footer. The centered header is the traditional name of the section. The
+/// footer usually names the program and its version, which can be set with the `footer`
+/// metadata:
+///
+/// ```no_run
+/// let mut pandoc = pandoc::presets::man_page(1, "MYTOOL", "2024-01-31");
+/// pandoc
+///     .add_option(pandoc::PandocOption::Meta(
+///         "footer".to_string(),
+///         Some(format!("mytool {}", env!("CARGO_PKG_VERSION"))),
+///     ))
+///     .add_input("mytool.1.md")
+///     .set_output(pandoc::OutputKind::File("mytool.1".into()));
+/// pandoc.execute().unwrap();
+/// ```
+pub fn man_page<T: AsRef<str> + ?Sized, U: AsRef<str> + ?Sized>(
+    section: u32,
+    title: &T,
+    date: &U,
+) -> Pandoc {
+    let mut pandoc = Pandoc::new();
+    pandoc
+        .set_output_format(OutputFormat::Man, Vec::new())
+        .add_option(PandocOption::Standalone)
+        .set_title(title)
+        .set_date(date)
+        .add_option(PandocOption::Meta(
+            "section".to_string(),
+            Some(section.to_string()),
+        ));
+    if let Some(header) = man_header(section) {
+        pandoc.add_option(PandocOption::Meta(
+            "header".to_string(),
+            Some(header.to_string()),
+        ));
+    }
+    pandoc
+}
@@ -0,0 +1,555 @@
+//! client for a running `pandoc --server`
+//!
+//! Instead of spawning a pandoc process per conversion, the configuration of a
+//! [`Pandoc`](../struct.Pandoc.html) builder is sent as JSON to pandoc's HTTP server mode.
+//! The server has no access to the file system, so input files and templates are read and
+//! sent along, and output files are written by this crate. Options the server does not
+//! understand result in `PandocError::Unsupported`, as do the settings that only apply to a
+//! spawned pandoc: raw arguments added with [`Pandoc::arg`](../struct.Pandoc.html#method.arg),
+//! the timeout, reproducible output and the conversion cache. Binary inputs like docx can't be
+//! concatenated, so only a single one can be sent. The extensions of the formats are not
+//! checked against the server's list of supported extensions.
+//!
+//! A [`PandocServer`](struct.PandocServer.html) runs the server as a child process of the
+//! current program, and falls back to spawning pandoc for conversions the server can't do.
+
+use crate::{format_arg, InputFormat, InputKind, InputSource, OutputFormat, OutputKind};
+use crate::{Pandoc, PandocError, PandocOption, PandocOutput, TempPath, Tld};
+use base64::Engine;
+use serde_json::{json, Map, Value};
+use std::io::Write;
+use std::process::{Child, Stdio};
+use std::sync::Mutex;
+use std::time::{Duration, Instant};
+
+/// Client for a pandoc server, see
+/// [`Pandoc::execute_on_server`](../struct.Pandoc.html#method.execute_on_server).
+#[derive(Clone, Debug)]
+pub struct ServerClient {
+    url: String,
+}
+
+/// the server's answer to a conversion request
+struct Response {
+    output: Vec<u8>,
+    /// messages formatted like pandoc's stderr output
+    messages: String,
+}
+
+fn unsupported(what: impl std::fmt::Debug) -> PandocError {
+    PandocError::Unsupported(format!("{:?} is not supported by pandoc server", what))
+}
+
+/// insert `key: value` into the nested object `map[outer]`
+fn insert_nested(map: &mut Map<String, Value>, outer: &str, key: &str, value: &Option<String>) {
+    let value = value
+        .as_ref()
+        .map_or(Value::Bool(true), |v| Value::from(v.as_str()));
+    map.entry(outer)
+        .or_insert_with(|| Value::Object(Map::new()))
+        .as_object_mut()
+        .expect("nested options are objects")
+        .insert(key.to_string(), value);
+}
+
+fn math_method(method: &str, url: &Option<String>) -> Value {
+    match *url {
+        Some(ref url) => json!({ "method": method, "url": url }),
+        None => Value::from(method),
+    }
+}
+
+/// translate the options to the keys the server (and pandoc's defaults files) understand
+fn options_to_json(options: &[PandocOption]) -> Result<Map<String, Value>, PandocError> {
+    use crate::PandocOption::*;
+    let mut map = Map::new();
+    for option in options {
+        let (key, value) = match *option {
+            Standalone => ("standalone", Value::Bool(true)),
+            EmbedResources => ("embed-resources", Value::Bool(true)),
+            Template(ref p) => (
+                "template",
+                Value::from(std::fs::read_to_string(p).map_err(PandocError::IoErr)?),
+            ),
+            Meta(ref k, ref v) => {
+                insert_nested(&mut map, "metadata", k, v);
+                continue;
+            }
+            Var(ref k, ref v) => {
+                insert_nested(&mut map, "variables", k, v);
+                continue;
+            }
+            TableOfContents => ("table-of-contents", Value::Bool(true)),
+            TableOfContentsDepth(n) => ("toc-depth", Value::from(n)),
+            NumberSections => ("number-sections", Value::Bool(true)),
+            NumberOffset(ref nums) => ("number-offset", Value::from(nums.clone())),
+            ShiftHeadingLevelBy(n) => ("shift-heading-level-by", Value::from(n)),
+            IndentedCodeClasses(ref s) => (
+                "indented-code-classes",
+                Value::from(s.split(',').map(str::trim).collect::<Vec<_>>()),
+            ),
+            PreserveTabs => ("preserve-tabs", Value::Bool(true)),
+            TabStop(n) => ("tab-stop", Value::from(n)),
+            TrackChanges(ref v) => ("track-changes", Value::from(v.to_string())),
+            NoWrap => ("wrap", Value::from("none")),
+            Columns(n) => ("columns", Value::from(n)),
+            NoHighlight => ("highlight-style", Value::Null),
+            HighlightStyle(ref s) => ("highlight-style", Value::from(s.as_str())),
+            Ascii => ("ascii", Value::Bool(true)),
+            ReferenceLinks => ("reference-links", Value::Bool(true)),
+            AtxHeaders => ("markdown-headings", Value::from("atx")),
+            TopLevelDivision(ref tld) => (
+                "top-level-division",
+                Value::from(match *tld {
+                    Tld::Chapter => "chapter",
+                    Tld::Section => "section",
+                    Tld::Part => "part",
+                }),
+            ),
+            Listings => ("listings", Value::Bool(true)),
+            Incremental => ("incremental", Value::Bool(true)),
+            SlideLevel(n) => ("slide-level", Value::from(n)),
+            SectionDivs => ("section-divs", Value::Bool(true)),
+            HtmlQTags => ("html-q-tags", Value::Bool(true)),
+            DefaultImageExtension(ref s) => ("default-image-extension", Value::from(s.as_str())),
+            EmailObfuscation(o) => ("email-obfuscation", Value::from(o.to_string())),
+            IdPrefix(ref s) => ("identifier-prefix", Value::from(s.as_str())),
+            TitlePrefix(ref s) => ("title-prefix", Value::from(s.as_str())),
+            EpubChapterLevel(n) => ("epub-chapter-level", Value::from(n)),
+            Citeproc => ("citeproc", Value::Bool(true)),
+            Natbib => ("cite-method", Value::from("natbib")),
+            Biblatex => ("cite-method", Value::from("biblatex")),
+            MathML(_) => ("html-math-method", Value::from("mathml")),
+            MathJax(ref url) => ("html-math-method", math_method("mathjax", url)),
+            Katex(ref url) => ("html-math-method", math_method("katex", url)),
+            WebTex(ref url) => ("html-math-method", math_method("webtex", url)),
+            GladTex => ("html-math-method", Value::from("gladtex")),
+            EOL(ref eol) => ("eol", Value::from(eol.as_str())),
+            IpynbOutput(o) => ("ipynb-output", Value::from(o.to_string())),
+            // the server always runs sandboxed
+            Sandbox => continue,
+            ref other => return Err(unsupported(other)),
+        };
+        map.insert(key.to_string(), value);
+    }
+    Ok(map)
+}
+
+impl ServerClient {
+    /// Create a client for the server at `url`, e.g. `"http://localhost:3030"`.
+    pub fn new<T: AsRef<str> + ?Sized>(url: &T) -> ServerClient {
+        ServerClient {
+            url: url.as_ref().trim_end_matches('/').to_owned(),
+        }
+    }
+
+    /// The url of the server.
+    pub fn url(&self) -> &str {
+        &self.url
+    }
+
+    /// Query the version of the pandoc server.
+    pub fn version(&self) -> Result<String, PandocError> {
+        let response = ureq::get(&format!("{}/version", self.url))
+            .call()
+            .map_err(server_error)?;
+        let version = response.into_string().map_err(PandocError::IoErr)?;
+        Ok(version.trim().to_string())
+    }
+
+    fn request(&self, body: &Value) -> Result<Response, PandocError> {
+        let response = ureq::post(&self.url)
+            .set("Content-Type", "application/json")
+            .set("Accept", "application/json")
+            .send_string(&body.to_string())
+            .map_err(server_error)?;
+        let response: Value = serde_json::from_reader(response.into_reader())
+            .map_err(|e| PandocError::ServerErr(e.to_string()))?;
+        let messages = response["messages"]
+            .as_array()
+            .into_iter()
+            .flatten()
+            .map(|message| {
+                format!(
+                    "[{}] {}\n",
+                    message["verbosity"].as_str().unwrap_or("WARNING"),
+                    message["message"].as_str().unwrap_or_default()
+                )
+            })
+            .collect();
+        let output = match response["output"] {
+            Value::String(ref output) if response["base64"] == Value::Bool(true) => {
+                base64::engine::general_purpose::STANDARD
+                    .decode(output)
+                    .map_err(|e| PandocError::ServerErr(e.to_string()))?
+            }
+            Value::String(ref output) => output.clone().into_bytes(),
+            // older servers answer errors with status 200 and an `error` field
+            _ => {
+                return Err(PandocError::ServerErr(
+                    response["error"]
+                        .as_str()
+                        .unwrap_or("no output")
+                        .to_string(),
+                ))
+            }
+        };
+        Ok(Response { output, messages })
+    }
+
+    /// Whether [`execute`](#method.execute) can run the conversion configured in `pandoc`,
+    /// otherwise it returns `PandocError::Unsupported`.
+    pub fn supports(pandoc: &Pandoc) -> bool {
+        if process_only_setting(pandoc).is_some() {
+            return false;
+        }
+        if let Some((InputFormat::Lua(_), _)) = pandoc.input_format {
+            return false;
+        }
+        if let Some((OutputFormat::Lua(_), _)) = pandoc.output_format {
+            return false;
+        }
+        let input = match pandoc.input {
+            Some(InputKind::Sources(ref sources)) => sources
+                .iter()
+                .all(|source| !matches!(*source, InputSource::Url(_))),
+            Some(InputKind::Handle(_)) => false,
+            _ => true,
+        };
+        let output = matches!(
+            pandoc.output,
+            None | Some(OutputKind::File(_))
+                | Some(OutputKind::TempFile(_))
+                | Some(OutputKind::Tee(_))
+                | Some(OutputKind::Pipe)
+                | Some(OutputKind::InheritStdout)
+        );
+        input && output && options_to_json(&pandoc.options).is_ok()
+    }
+
+    /// Run the conversion configured in `pandoc` on the server.
+    pub fn execute(&self, mut pandoc: Pandoc) -> Result<PandocOutput, PandocError> {
+        if let Some(setting) = process_only_setting(&pandoc) {
+            return Err(PandocError::Unsupported(format!(
+                "{} not supported by pandoc server",
+                setting
+            )));
+        }
+        pandoc.infer_input_format()?;
+        pandoc.validate(None)?;
+        pandoc.apply_input_preprocessors()?;
+        if let Some((InputFormat::Lua(ref script), _)) = pandoc.input_format {
+            return Err(unsupported(script));
+        }
+        if let Some((OutputFormat::Lua(ref script), _)) = pandoc.output_format {
+            return Err(unsupported(script));
+        }
+        let binary_input =
+            matches!(pandoc.input_format, Some((ref format, _)) if format.is_binary());
+        let text = match pandoc.input.take() {
+            None => return Err(PandocError::NoInputSpecified),
+            Some(InputKind::Pipe(text)) => text.into_bytes(),
+            Some(InputKind::InheritStdin) => {
+                let mut text = Vec::new();
+                std::io::Read::read_to_end(&mut std::io::stdin(), &mut text)
+                    .map_err(PandocError::IoErr)?;
+                text
+            }
+            Some(InputKind::Files(files)) => read_files(files, binary_input)?,
+            Some(InputKind::Sources(sources)) => {
+                let mut files = Vec::new();
+                for source in sources {
+                    match source {
+                        InputSource::File(file) => files.push(file),
+                        InputSource::Glob(pattern) => {
+                            let mut matches = glob::glob(&pattern)
+                                .map_err(|_| PandocError::InvalidGlob(pattern.clone()))?
+                                .collect::<Result<Vec<_>, _>>()
+                                .map_err(|e| PandocError::IoErr(e.into()))?;
+                            matches.sort();
+                            files.extend(matches);
+                        }
+                        url @ InputSource::Url(_) => return Err(unsupported(url)),
+                    }
+                }
+                read_files(files, binary_input)?
+            }
+            Some(other) => return Err(unsupported(other)),
+        };
+        let mut text = if binary_input {
+            base64::engine::general_purpose::STANDARD.encode(text)
+        } else {
+            String::from_utf8(text)?
+        };
+        let mut from = pandoc
+            .input_format
+            .as_ref()
+            .map(|(format, extensions)| format_arg(format, extensions));
+        let mut messages = String::new();
+
+        let filters = std::mem::take(&mut pandoc.filters);
+        if !filters.is_empty() {
+            let mut body = json!({ "text": text, "to": "json" });
+            if let Some(ref from) = from {
+                body["from"] = Value::from(from.as_str());
+            }
+            let response = self.request(&body)?;
+            messages.push_str(&response.messages);
+            text = String::from_utf8(crate::apply_filters(
+                filters,
+                &pandoc.filter_api_versions,
+                response.output,
+            )?)?;
+            from = Some("json".to_string());
+        }
+
+        let mut body = options_to_json(&pandoc.options)?;
+        body.insert("text".to_string(), Value::from(text));
+        if let Some(from) = from {
+            body.insert("from".to_string(), Value::from(from));
+        }
+        if let Some((ref format, ref extensions)) = pandoc.output_format {
+            body.insert(
+                "to".to_string(),
+                Value::from(format_arg(format, extensions)),
+            );
+        }
+        let response = self.request(&Value::Object(body))?;
+        messages.push_str(&response.messages);
+        crate::check_warnings(&pandoc.fail_on, messages.as_bytes())?;
+
+        let output = response.output;
+        let binary = matches!(pandoc.output_format, Some((ref format, _)) if format.is_binary());
+        let lossy_utf8 = pandoc.lossy_utf8;
+        let to_buffer = |output: Vec<u8>| {
+            if binary {
+                return Ok(PandocOutput::ToBufferRaw(output));
+            }
+            match String::from_utf8(output) {
+                Ok(string) => Ok(PandocOutput::ToBuffer(string)),
+                Err(err) if lossy_utf8 => Ok(PandocOutput::ToBuffer(
+                    String::from_utf8_lossy(err.as_bytes()).into_owned(),
+                )),
+                Err(err) => Err(PandocError::from(err)),
+            }
+        };
+        match pandoc.output.ok_or(PandocError::NoOutputSpecified)? {
+            OutputKind::File(file) => {
+                std::fs::write(&file, output).map_err(PandocError::IoErr)?;
+                Ok(PandocOutput::ToFile(file))
+            }
+            OutputKind::TempFile(suffix) => {
+                let temp = TempPath::new(&suffix).map_err(PandocError::IoErr)?;
+                std::fs::write(&temp, output).map_err(PandocError::IoErr)?;
+                Ok(PandocOutput::ToTempFile(temp))
+            }
+            OutputKind::Tee(file) => {
+                std::fs::write(file, &output).map_err(PandocError::IoErr)?;
+                to_buffer(output)
+            }
+            OutputKind::Pipe => to_buffer(output),
+            OutputKind::InheritStdout => {
+                std::io::stdout()
+                    .write_all(&output)
+                    .map_err(PandocError::IoErr)?;
+                Ok(PandocOutput::ToStdout)
+            }
+            other => Err(unsupported(other)),
+        }
+    }
+}
+
+/// concatenate the input files like pandoc does, binary formats can't be concatenated
+fn read_files(files: Vec<std::path::PathBuf>, binary: bool) -> Result<Vec<u8>, PandocError> {
+    if binary && files.len() > 1 {
+        return Err(PandocError::InvalidConfig(format!(
+            "pandoc server can only convert a single binary input, got {}",
+            files.len()
+        )));
+    }
+    let mut text = Vec::new();
+    for file in files {
+        if !text.is_empty() {
+            text.extend_from_slice(b"\n\n");
+        }
+        text.extend(std::fs::read(file).map_err(PandocError::IoErr)?);
+    }
+    Ok(text)
+}
+
+fn server_error(error: ureq::Error) -> PandocError {
+    match error {
+        ureq::Error::Status(code, response) => {
+            let message = response.into_string().unwrap_or_default();
+            PandocError::ServerErr(format!("status {}: {}", code, message.trim()))
+        }
+        ureq::Error::Transport(transport) => PandocError::ServerErr(transport.to_string()),
+    }
+}
+
+/// the first setting of `pandoc` that only applies when spawning pandoc, if any
+fn process_only_setting(pandoc: &Pandoc) -> Option<&'static str> {
+    if !pandoc.args.is_empty() {
+        Some("raw arguments are")
+    } else if pandoc.timeout.is_some() {
+        Some("a timeout is")
+    } else if pandoc.reproducible {
+        Some("reproducible output is")
+    } else if pandoc.cache_dir.is_some() {
+        Some("caching is")
+    } else {
+        None
+    }
+}
+
+/// A pandoc server running as a child process of the current program.
+///
+/// Starting pandoc takes much longer than most conversions of short documents, so converting
+/// many small documents is a lot faster on a long-lived server. Conversions the server
+/// can't do (see the [module documentation](index.html)) are run by spawning pandoc as
+/// usual:
+///
+/// ```no_run
+/// let server = pandoc::server::PandocServer::launch().unwrap();
+/// let mut pandoc = pandoc::new();
+/// pandoc.set_input(pandoc::InputKind::Pipe("*hello*".to_string()));
+/// pandoc.set_output(pandoc::OutputKind::Pipe);
+/// server.execute(pandoc).unwrap();
+/// ```
+///
+/// The server is started with `pandoc server`, which requires pandoc 3.0 or later built with
+/// server support. It is restarted if it exits, and killed when the `PandocServer` is
+/// dropped.
+#[derive(Debug)]
+pub struct PandocServer {
+    /// finds the pandoc the server is started with
+    pandoc: Pandoc,
+    timeout: u64,
+    running: Mutex<Running>,
+}
+
+#[derive(Debug)]
+struct Running {
+    child: Child,
+    client: ServerClient,
+}
+
+impl PandocServer {
+    /// How long to wait for a newly started server to answer.
+    const STARTUP_TIMEOUT: Duration = Duration::from_secs(10);
+
+    /// Start a server with pandoc's default timeout of 2 seconds per conversion.
+    pub fn launch() -> Result<PandocServer, PandocError> {
+        PandocServer::launch_with_timeout(2)
+    }
+
+    /// Start a server cancelling conversions taking longer than `timeout` seconds.
+    pub fn launch_with_timeout(timeout: u64) -> Result<PandocServer, PandocError> {
+        PandocServer::start(&Pandoc::new(), timeout)
+    }
+
+    /// Start a server with the pandoc that `pandoc` would run, found with its path hints,
+    /// and pandoc's default timeout of 2 seconds per conversion.
+    pub fn launch_with(pandoc: &Pandoc) -> Result<PandocServer, PandocError> {
+        PandocServer::start(pandoc, 2)
+    }
+
+    fn start(pandoc: &Pandoc, timeout: u64) -> Result<PandocServer, PandocError> {
+        let pandoc = pandoc.child();
+        let running = Running::start(&pandoc, timeout)?;
+        Ok(PandocServer {
+            pandoc,
+            timeout,
+            running: Mutex::new(running),
+        })
+    }
+
+    /// A client for the server, valid until the server is restarted.
+    pub fn client(&self) -> ServerClient {
+        self.running().client.clone()
+    }
+
+    /// Whether the server is running and answering requests.
+    pub fn is_healthy(&self) -> bool {
+        let client = {
+            let mut running = self.running();
+            if !matches!(running.child.try_wait(), Ok(None)) {
+                return false;
+            }
+            running.client.clone()
+        };
+        client.version().is_ok()
+    }
+
+    /// Run the conversion configured in `pandoc` on the server, or by spawning pandoc if the
+    /// server does not support it.
+    ///
+    /// A server that exited since the last conversion is restarted first.
+    pub fn execute(&self, pandoc: Pandoc) -> Result<PandocOutput, PandocError> {
+        if !ServerClient::supports(&pandoc) {
+            return pandoc.execute();
+        }
+        let client = {
+            let mut running = self.running();
+            if !matches!(running.child.try_wait(), Ok(None)) {
+                *running = Running::start(&self.pandoc, self.timeout)?;
+            }
+            running.client.clone()
+        };
+        client.execute(pandoc)
+    }
+
+    fn running(&self) -> std::sync::MutexGuard<'_, Running> {
+        self.running.lock().unwrap_or_else(|e| e.into_inner())
+    }
+}
+
+impl Running {
+    fn start(pandoc: &Pandoc, timeout: u64) -> Result<Running, PandocError> {
+        // let the system pick a free port for the server
+        let port = std::net::TcpListener::bind("127.0.0.1:0")
+            .and_then(|listener| listener.local_addr())
+            .map_err(PandocError::IoErr)?
+            .port();
+        let mut command = pandoc.command();
+        command
+            .arg("server")
+            .arg("--port")
+            .arg(port.to_string())
+            .arg("--timeout")
+            .arg(timeout.to_string())
+            .stdin(Stdio::null())
+            .stdout(Stdio::null())
+            .stderr(Stdio::null());
+        let mut running = Running {
+            child: command.spawn()?,
+            client: ServerClient::new(&format!("http://127.0.0.1:{}", port)),
+        };
+        let start = Instant::now();
+        loop {
+            if let Some(status) = running.child.try_wait().map_err(PandocError::IoErr)? {
+                return Err(PandocError::ServerErr(format!(
+                    "pandoc server exited on startup: {}",
+                    status
+                )));
+            }
+            if running.client.version().is_ok() {
+                return Ok(running);
+            }
+            if start.elapsed() > PandocServer::STARTUP_TIMEOUT {
+                // dropping `running` kills the server
+                return Err(PandocError::ServerErr(
+                    "pandoc server did not answer after starting".to_string(),
+                ));
+            }
+            std::thread::sleep(Duration::from_millis(50));
+        }
+    }
+}
+
+impl Drop for Running {
+    fn drop(&mut self) {
+        let _ = self.child.kill();
+        let _ = self.child.wait();
+    }
+}
@@ -0,0 +1,108 @@
+//! cutting a document into one document per section
+
+use crate::outline::stringify;
+use crate::OutputFormat;
+use pandoc_ast::Block;
+
+/// the extension of the files `format` is written to
+pub(crate) fn file_extension(format: &OutputFormat) -> String {
+    use crate::OutputFormat::*;
+    match *format {
+        Markdown | MarkdownStrict | MarkdownPhpextra | Gfm | Commonmark | CommonmarkX | Markua => {
+            "md".to_string()
+        }
+        #[allow(deprecated)]
+        MarkdownGithub => "md".to_string(),
+        Html | Html5 | Slidy | Slideous | Dzslides | Revealjs | S5 => "html".to_string(),
+        Latex | Beamer | Context => "tex".to_string(),
+        Plain | Lua(_) => "txt".to_string(),
+        Man => "1".to_string(),
+        MediaWiki => "wiki".to_string(),
+        Texinfo => "texi".to_string(),
+        Asciidoc => "adoc".to_string(),
+        Typst => "typ".to_string(),
+        Epub3 => "epub".to_string(),
+        BibTeX | BibLaTeX => "bib".to_string(),
+        CslJson => "json".to_string(),
+        Docbook | Docbook4 | Docbook5 | Jats | JatsArchiving | JatsPublishing
+        | JatsArticleAuthoring | Tei | OpenDocument => "xml".to_string(),
+        Other(ref name) | OtherBinary(ref name) => name
+            .split(['+', '-'])
+            .next()
+            .unwrap_or_default()
+            .to_string(),
+        ref format => format.to_string(),
+    }
+}
+
+/// a file name for a heading without identifier, like pandoc's `auto_identifiers`
+fn slugify(text: &str) -> String {
+    let mut slug = String::new();
+    for c in text.chars().flat_map(char::to_lowercase) {
+        if c.is_alphanumeric() || c == '_' || c == '-' || c == '.' {
+            slug.push(c);
+        } else if c.is_whitespace() && !slug.ends_with('-') {
+            slug.push('-');
+        }
+    }
+    slug.trim_matches('-').to_string()
+}
+
+/// Cut `document` at the headings of `level` and above, each section keeping the metadata.
+///
+/// Returns the sections with a name unique among them: the identifier of the heading, or
+/// `index` for the blocks before the first heading.
+pub(crate) fn sections(
+    document: pandoc_ast::Pandoc,
+    level: u32,
+) -> Vec<(String, pandoc_ast::Pandoc)> {
+    let pandoc_ast::Pandoc {
+        meta,
+        blocks,
+        pandoc_api_version,
+    } = document;
+    let mut sections: Vec<(String, Vec<Block>)> = Vec::new();
+    for block in blocks {
+        match block {
+            Block::Header(heading_level, (ref identifier, _, _), ref inlines)
+                if heading_level as u32 <= level =>
+            {
+                let slug = match slugify(identifier) {
+                    slug if !slug.is_empty() => slug,
+                    _ => slugify(&stringify(inlines)),
+                };
+                let slug = if slug.is_empty() {
+                    "section".to_string()
+                } else {
+                    slug
+                };
+                sections.push((slug, vec![block]));
+            }
+            block => match sections.last_mut() {
+                Some((_, blocks)) => blocks.push(block),
+                None => sections.push(("index".to_string(), vec![block])),
+            },
+        }
+    }
+
+    let mut names: Vec<String> = Vec::new();
+    sections
+        .into_iter()
+        .map(|(slug, blocks)| {
+            // repeated names get a number, like repeated identifiers in pandoc
+            let mut name = slug.clone();
+            let mut n = 0;
+            while names.contains(&name) {
+                n += 1;
+                name = format!("{}-{}", slug, n);
+            }
+            names.push(name.clone());
+            let section = pandoc_ast::Pandoc {
+                meta: meta.clone(),
+                blocks,
+                pandoc_api_version: pandoc_api_version.clone(),
+            };
+            (name, section)
+        })
+        .collect()
+}
@@ -0,0 +1,146 @@
+//! word counts and other statistics of a document
+
+use pandoc_ast::{Block, Inline, Row};
+use std::time::Duration;
+
+/// Counts of the contents of a document, see
+/// [`Pandoc::stats`](struct.Pandoc.html#method.stats).
+#[derive(PartialEq, Eq, Clone, Debug, Default)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[non_exhaustive]
+pub struct DocumentStats {
+    /// the words of the text, including headings, captions, tables and footnotes, but not
+    /// code blocks
+    pub words: usize,
+    /// the characters of the words, without whitespace
+    pub characters: usize,
+    /// the headings of all levels
+    pub headings: usize,
+    /// the figures, but not images outside of figures
+    pub figures: usize,
+    /// the tables, including the ones in figures
+    pub tables: usize,
+    /// the code blocks, but not inline code
+    pub code_blocks: usize,
+}
+
+impl DocumentStats {
+    /// The time it takes to read the words at `words_per_minute`.
+    pub fn reading_time_at(&self, words_per_minute: u32) -> Duration {
+        let words_per_minute = u64::from(words_per_minute.max(1));
+        Duration::from_secs(self.words as u64 * 60 / words_per_minute)
+    }
+
+    /// The time it takes to read the words at 200 words per minute, a common estimate for
+    /// reading on screen.
+    pub fn reading_time(&self) -> Duration {
+        self.reading_time_at(200)
+    }
+}
+
+/// Count the words, headings, figures, tables and code blocks of `document`.
+pub fn document_stats(document: &pandoc_ast::Pandoc) -> DocumentStats {
+    let mut counter = Counter::default();
+    counter.blocks(&document.blocks);
+    counter.stats.words = counter.text.split_whitespace().count();
+    counter.stats.characters = counter.text.chars().filter(|c| !c.is_whitespace()).count();
+    counter.stats
+}
+
+#[derive(Default)]
+struct Counter {
+    stats: DocumentStats,
+    /// the text of the document, with whitespace between words of different blocks
+    text: String,
+}
+
+impl Counter {
+    fn blocks(&mut self, blocks: &[Block]) {
+        for block in blocks {
+            self.block(block);
+        }
+    }
+
+    fn block(&mut self, block: &Block) {
+        match *block {
+            Block::Plain(ref inlines) | Block::Para(ref inlines) => self.inlines(inlines),
+            Block::LineBlock(ref lines) => {
+                for line in lines {
+                    self.inlines(line);
+                }
+            }
+            Block::CodeBlock(..) => self.stats.code_blocks += 1,
+            Block::BlockQuote(ref blocks) | Block::Div(_, ref blocks) => self.blocks(blocks),
+            Block::OrderedList(_, ref items) | Block::BulletList(ref items) => {
+                for item in items {
+                    self.blocks(item);
+                }
+            }
+            Block::DefinitionList(ref items) => {
+                for (term, definitions) in items {
+                    self.inlines(term);
+                    for definition in definitions {
+                        self.blocks(definition);
+                    }
+                }
+            }
+            Block::Figure(_, (_, ref caption), ref blocks) => {
+                self.stats.figures += 1;
+                self.blocks(caption);
+                self.blocks(blocks);
+            }
+            Block::Header(_, _, ref inlines) => {
+                self.stats.headings += 1;
+                self.inlines(inlines);
+            }
+            Block::Table(_, (_, ref caption), _, (_, ref head), ref bodies, (_, ref foot)) => {
+                self.stats.tables += 1;
+                self.blocks(caption);
+                self.rows(head);
+                for (_, _, head, body) in bodies {
+                    self.rows(head);
+                    self.rows(body);
+                }
+                self.rows(foot);
+            }
+            Block::RawBlock(..) | Block::HorizontalRule | Block::Null => {}
+        }
+        self.text.push(' ');
+    }
+
+    fn rows(&mut self, rows: &[Row]) {
+        for (_, cells) in rows {
+            for (_, _, _, _, blocks) in cells {
+                self.blocks(blocks);
+            }
+        }
+    }
+
+    fn inlines(&mut self, inlines: &[Inline]) {
+        for inline in inlines {
+            match *inline {
+                Inline::Str(ref s) | Inline::Code(_, ref s) | Inline::Math(_, ref s) => {
+                    self.text.push_str(s)
+                }
+                Inline::Space | Inline::SoftBreak | Inline::LineBreak => self.text.push(' '),
+                Inline::Emph(ref inner)
+                | Inline::Underline(ref inner)
+                | Inline::Strong(ref inner)
+                | Inline::Strikeout(ref inner)
+                | Inline::Superscript(ref inner)
+                | Inline::Subscript(ref inner)
+                | Inline::SmallCaps(ref inner)
+                | Inline::Quoted(_, ref inner)
+                | Inline::Cite(_, ref inner)
+                | Inline::Link(_, ref inner, _)
+                | Inline::Image(_, ref inner, _)
+                | Inline::Span(_, ref inner) => self.inlines(inner),
+                Inline::Note(ref blocks) => {
+                    self.text.push(' ');
+                    self.blocks(blocks);
+                }
+                Inline::RawInline(..) => {}
+            }
+        }
+    }
+}
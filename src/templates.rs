@@ -0,0 +1,110 @@
+//! management of pandoc templates
+//!
+//! Pandoc looks up templates given by name in the `templates` directory of its data
+//! directory. A [`DataDir`] installs (possibly customized) default templates there and sets
+//! up a `Pandoc` builder to use them:
+//!
+//! ```no_run
+//! use pandoc::templates::DataDir;
+//! use pandoc::OutputFormat;
+//!
+//! let data_dir = DataDir::new("pandoc-data");
+//! let template = pandoc::templates::default_template(&OutputFormat::Html5).unwrap();
+//! data_dir
+//!     .install("article.html5", &template.replace("<body>", "<body class=\"article\">"))
+//!     .unwrap();
+//!
+//! let mut pandoc = pandoc::new();
+//! data_dir.apply(&mut pandoc, Some("article"));
+//! ```
+
+use crate::{Invocation, ProcessExecutor};
+use crate::{OutputFormat, Pandoc, PandocError, PandocExecutor, PandocOption};
+use std::path::{Path, PathBuf};
+
+/// Fetch pandoc's default template for the writer of `format`.
+pub fn default_template(format: &OutputFormat) -> Result<String, PandocError> {
+    let mut cmd = Pandoc::new().command();
+    cmd.arg(format!("--print-default-template={}", format))
+        .stdout(std::process::Stdio::piped())
+        .stderr(std::process::Stdio::piped());
+    let output = ProcessExecutor.execute(Invocation::new(cmd, None))?;
+    if !output.status.success() {
+        return Err(PandocError::Err(output));
+    }
+    Ok(String::from_utf8(output.stdout)?)
+}
+
+/// A pandoc data directory, see pandoc's `--data-dir` option.
+#[derive(PartialEq, Eq, Clone, Debug)]
+pub struct DataDir {
+    path: PathBuf,
+}
+
+impl DataDir {
+    /// Use the data directory at `path`, which is created when installing the first template.
+    pub fn new<T: AsRef<Path> + ?Sized>(path: &T) -> DataDir {
+        DataDir {
+            path: path.as_ref().to_owned(),
+        }
+    }
+
+    /// The path of the data directory.
+    pub fn path(&self) -> &Path {
+        &self.path
+    }
+
+    /// The directory the templates are stored in.
+    pub fn template_dir(&self) -> PathBuf {
+        self.path.join("templates")
+    }
+
+    /// Install a template with the file name `name`, e.g. `report.latex`, returning its path.
+    pub fn install<T: AsRef<str> + ?Sized>(
+        &self,
+        name: &T,
+        template: &str,
+    ) -> Result<PathBuf, PandocError> {
+        let dir = self.template_dir();
+        std::fs::create_dir_all(&dir).map_err(PandocError::IoErr)?;
+        let path = dir.join(name.as_ref());
+        std::fs::write(&path, template).map_err(PandocError::IoErr)?;
+        Ok(path)
+    }
+
+    /// Install pandoc's default template for `format` as `default.FORMAT`, which pandoc then
+    /// uses instead of its built-in template when run with this data directory.
+    pub fn install_default(&self, format: &OutputFormat) -> Result<PathBuf, PandocError> {
+        let template = default_template(format)?;
+        self.install(&format!("default.{}", format), &template)
+    }
+
+    /// The file names of the installed templates, sorted.
+    pub fn templates(&self) -> Result<Vec<String>, PandocError> {
+        let entries = match std::fs::read_dir(self.template_dir()) {
+            Ok(entries) => entries,
+            Err(ref e) if e.kind() == std::io::ErrorKind::NotFound => return Ok(Vec::new()),
+            Err(e) => return Err(PandocError::IoErr(e)),
+        };
+        let mut templates = Vec::new();
+        for entry in entries {
+            let entry = entry.map_err(PandocError::IoErr)?;
+            if entry.path().is_file() {
+                templates.push(entry.file_name().to_string_lossy().into_owned());
+            }
+        }
+        templates.sort();
+        Ok(templates)
+    }
+
+    /// Make `pandoc` use this data directory, and the installed template `template` if given.
+    ///
+    /// Like on pandoc's command line, the template name may omit the extension if it equals
+    /// the output format.
+    pub fn apply(&self, pandoc: &mut Pandoc, template: Option<&str>) {
+        pandoc.add_option(PandocOption::DataDir(self.path.clone()));
+        if let Some(template) = template {
+            pandoc.add_option(PandocOption::Template(PathBuf::from(template)));
+        }
+    }
+}
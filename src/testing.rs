@@ -0,0 +1,143 @@
+//! golden-file tests for templates, filters and other conversion setups
+//!
+//! [`assert_converts_to`] converts an input file and compares the output to a stored golden
+//! file, ignoring the parts that change between runs:
+//!
+//! ```no_run
+//! let mut config = pandoc::new();
+//! config.set_output_format(pandoc::OutputFormat::Html5, Vec::new());
+//! config.add_option(pandoc::PandocOption::Template("report.html".into()));
+//! pandoc::testing::assert_converts_to("tests/report.md", config, "tests/report.golden.html");
+//! ```
+//!
+//! Run the tests with the `PANDOC_UPDATE_GOLDEN` environment variable set to write the
+//! current outputs to the golden files instead, e.g. after an intended template change.
+
+use crate::{InputKind, OutputKind, Pandoc, PandocOutput};
+use std::path::Path;
+use std::time::{SystemTime, UNIX_EPOCH};
+
+/// The environment variable enabling the update mode.
+pub const UPDATE_VAR: &str = "PANDOC_UPDATE_GOLDEN";
+
+/// today's date as `YYYY-MM-DD`, in UTC
+fn today() -> String {
+    let secs = SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map(|since| since.as_secs())
+        .unwrap_or(0);
+    crate::iso_date(secs)
+}
+
+/// Convert `input` with `config` and compare the output to the golden file `expected`.
+///
+/// Today's date as `YYYY-MM-DD` is replaced by `<DATE>`, and the version of pandoc by
+/// `<PANDOC-VERSION>`, before comparing. The output is written to a pipe, so `config` should
+/// not set an output, and has to produce a text format.
+///
+/// # Panics
+///
+/// Panics with a line diff if the output differs from the golden file, or if the conversion
+/// fails.
+pub fn assert_converts_to<P: AsRef<Path>, Q: AsRef<Path>>(
+    input: P,
+    mut config: Pandoc,
+    expected: Q,
+) {
+    let version = config.version().ok();
+    config.set_input(InputKind::Files(vec![input.as_ref().to_owned()]));
+    config.set_output(OutputKind::Pipe);
+    let output = match config.execute() {
+        Ok(PandocOutput::ToBuffer(output)) => output,
+        Ok(_) => panic!("golden files can only be compared for text formats"),
+        Err(err) => panic!("converting {} failed: {}", input.as_ref().display(), err),
+    };
+    let mut output = output.replace(&today(), "<DATE>");
+    if let Some(version) = version {
+        output = output.replace(&version, "<PANDOC-VERSION>");
+    }
+    assert_matches_golden(&output, expected);
+}
+
+/// Compare `actual` to the golden file `expected`, or update the file in update mode.
+///
+/// # Panics
+///
+/// Panics with a line diff if `actual` differs from the golden file, or if the golden file
+/// doesn't exist.
+pub fn assert_matches_golden<Q: AsRef<Path>>(actual: &str, expected: Q) {
+    let expected = expected.as_ref();
+    if std::env::var_os(UPDATE_VAR).is_some() {
+        if let Some(dir) = expected.parent() {
+            std::fs::create_dir_all(dir).expect("failed to create the golden file directory");
+        }
+        std::fs::write(expected, actual).expect("failed to write the golden file");
+        return;
+    }
+    let golden = match std::fs::read_to_string(expected) {
+        Ok(golden) => golden,
+        Err(err) => panic!(
+            "failed to read the golden file {}: {}\nrun with {}=1 to create it",
+            expected.display(),
+            err,
+            UPDATE_VAR
+        ),
+    };
+    if golden != actual {
+        panic!(
+            "the output differs from the golden file {}:\n{}\nrun with {}=1 to update it",
+            expected.display(),
+            diff(&golden, actual),
+            UPDATE_VAR
+        );
+    }
+}
+
+/// a line diff, `-` marking lines of `old` and `+` lines of `new`, with two lines of context
+fn diff(old: &str, new: &str) -> String {
+    let old: Vec<&str> = old.lines().collect();
+    let new: Vec<&str> = new.lines().collect();
+    // lengths of the longest common subsequences of the suffixes
+    let mut lcs = vec![vec![0usize; new.len() + 1]; old.len() + 1];
+    for i in (0..old.len()).rev() {
+        for j in (0..new.len()).rev() {
+            lcs[i][j] = if old[i] == new[j] {
+                lcs[i + 1][j + 1] + 1
+            } else {
+                lcs[i + 1][j].max(lcs[i][j + 1])
+            };
+        }
+    }
+    let mut lines = Vec::new();
+    let (mut i, mut j) = (0, 0);
+    while i < old.len() || j < new.len() {
+        if i < old.len() && j < new.len() && old[i] == new[j] {
+            lines.push((' ', old[i]));
+            i += 1;
+            j += 1;
+        } else if i < old.len() && (j == new.len() || lcs[i + 1][j] >= lcs[i][j + 1]) {
+            lines.push(('-', old[i]));
+            i += 1;
+        } else {
+            lines.push(('+', new[j]));
+            j += 1;
+        }
+    }
+    let changed: Vec<usize> = (0..lines.len()).filter(|&k| lines[k].0 != ' ').collect();
+    if changed.is_empty() {
+        return "(the outputs only differ in line endings)\n".to_string();
+    }
+    let mut report = String::new();
+    let mut last = None;
+    for (k, &(mark, line)) in lines.iter().enumerate() {
+        if !changed.iter().any(|&c| c.abs_diff(k) <= 2) {
+            continue;
+        }
+        if matches!(last, Some(l) if l + 1 != k) {
+            report.push_str("...\n");
+        }
+        report.push_str(&format!("{} {}\n", mark, line));
+        last = Some(k);
+    }
+    report
+}
@@ -0,0 +1,132 @@
+//! conversion of whole directory trees
+
+use crate::{OutputFormat, OutputKind, Pandoc, PandocError, PandocOption};
+use std::collections::BTreeMap;
+use std::path::{Path, PathBuf};
+
+/// What [`convert_tree`] converts and how.
+#[derive(Clone, Debug)]
+pub struct TreeOptions {
+    /// the configuration every file is converted with, the input and output are set per file
+    pub pandoc: Pandoc,
+    /// the extension of converted files, mapped to the extension and format of their output
+    pub conversions: BTreeMap<String, (String, OutputFormat)>,
+    /// copy all other files unchanged, e.g. images and stylesheets
+    pub copy_assets: bool,
+}
+
+impl Default for TreeOptions {
+    /// Convert markdown files to standalone HTML5 and copy all other files.
+    fn default() -> TreeOptions {
+        let mut pandoc = Pandoc::new();
+        pandoc.add_option(PandocOption::Standalone);
+        let mut options = TreeOptions {
+            pandoc,
+            conversions: BTreeMap::new(),
+            copy_assets: true,
+        };
+        options.add_conversion("md", "html", OutputFormat::Html5);
+        options.add_conversion("markdown", "html", OutputFormat::Html5);
+        options
+    }
+}
+
+impl TreeOptions {
+    /// Convert files ending in `.from` to `format`, in files ending in `.to`.
+    pub fn add_conversion<T: AsRef<str> + ?Sized, U: AsRef<str> + ?Sized>(
+        &mut self,
+        from: &T,
+        to: &U,
+        format: OutputFormat,
+    ) -> &mut TreeOptions {
+        self.conversions
+            .insert(from.as_ref().to_owned(), (to.as_ref().to_owned(), format));
+        self
+    }
+}
+
+/// Convert all files in `src_dir` and its subdirectories into the same structure in `dst_dir`.
+///
+/// Files are converted if their extension has a conversion in `options`, and copied otherwise
+/// if `options.copy_assets` is set. Hidden files and directories are skipped, as is `dst_dir`
+/// if it is inside `src_dir`. Links between the documents are not rewritten, so documents
+/// should link to the converted files, e.g. to `usage.html` instead of `usage.md`.
+///
+/// Returns the paths of the converted files.
+///
+/// ```no_run
+/// let outputs = pandoc::convert_tree("docs", "target/site", &Default::default()).unwrap();
+/// println!("converted {} documents", outputs.len());
+/// ```
+pub fn convert_tree<P: AsRef<Path>, Q: AsRef<Path>>(
+    src_dir: P,
+    dst_dir: Q,
+    options: &TreeOptions,
+) -> Result<Vec<PathBuf>, PandocError> {
+    let mut converted = Vec::new();
+    walk(
+        src_dir.as_ref(),
+        dst_dir.as_ref(),
+        dst_dir.as_ref(),
+        options,
+        &mut converted,
+    )?;
+    Ok(converted)
+}
+
+fn walk(
+    src: &Path,
+    dst: &Path,
+    dst_root: &Path,
+    options: &TreeOptions,
+    converted: &mut Vec<PathBuf>,
+) -> Result<(), PandocError> {
+    std::fs::create_dir_all(dst).map_err(PandocError::IoErr)?;
+    let mut entries = std::fs::read_dir(src)
+        .map_err(PandocError::IoErr)?
+        .collect::<Result<Vec<_>, _>>()
+        .map_err(PandocError::IoErr)?;
+    entries.sort_by_key(|entry| entry.file_name());
+    for entry in entries {
+        let name = entry.file_name();
+        if name.to_string_lossy().starts_with('.') {
+            continue;
+        }
+        let path = entry.path();
+        if path.is_dir() {
+            if is_same_dir(&path, dst_root) {
+                continue;
+            }
+            walk(&path, &dst.join(&name), dst_root, options, converted)?;
+            continue;
+        }
+        let conversion = path
+            .extension()
+            .and_then(|extension| extension.to_str())
+            .and_then(|extension| options.conversions.get(extension));
+        match conversion {
+            Some((extension, format)) => {
+                let output = dst.join(&name).with_extension(extension);
+                let mut pandoc = options.pandoc.clone();
+                pandoc
+                    .add_input(&path)
+                    .set_output_format(format.clone(), Vec::new())
+                    .set_output(OutputKind::File(output.clone()));
+                pandoc.execute()?;
+                converted.push(output);
+            }
+            None if options.copy_assets => {
+                std::fs::copy(&path, dst.join(&name)).map_err(PandocError::IoErr)?;
+            }
+            None => {}
+        }
+    }
+    Ok(())
+}
+
+fn is_same_dir(a: &Path, b: &Path) -> bool {
+    match (a.canonicalize(), b.canonicalize()) {
+        (Ok(a), Ok(b)) => a == b,
+        _ => false,
+    }
+}
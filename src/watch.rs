@@ -0,0 +1,177 @@
+//! rebuilding documents when their sources change
+
+use crate::{OutputKind, Pandoc, PandocError, PandocOutput};
+use notify::{EventKind, RecommendedWatcher, RecursiveMode, Watcher as _};
+use std::collections::HashSet;
+use std::path::{Path, PathBuf};
+use std::sync::mpsc::{channel, Receiver, RecvTimeoutError};
+use std::time::Duration;
+
+/// Reruns a conversion whenever one of its sources changes.
+///
+/// The input files, and the files referenced by options like the template, bibliography or
+/// include files, are watched along with any additional paths. Changes in quick succession,
+/// like an editor saving several files, cause a single rebuild after
+/// [the debounce time](#method.set_debounce).
+///
+/// The rebuilds run on the thread calling [`run`](#method.run) or
+/// [`wait_for_change`](#method.wait_for_change):
+///
+/// ```no_run
+/// let mut pandoc = pandoc::new();
+/// pandoc.add_input("notes.md");
+/// pandoc.set_output(pandoc::OutputKind::File("notes.html".into()));
+/// let watcher = pandoc::watch::Watcher::new(pandoc, &["img"]).unwrap();
+/// watcher
+///     .run(|result| {
+///         if let Err(err) = result {
+///             eprintln!("{}", err);
+///         }
+///         true
+///     })
+///     .unwrap();
+/// ```
+pub struct Watcher {
+    pandoc: Pandoc,
+    files: HashSet<PathBuf>,
+    dirs: Vec<PathBuf>,
+    output: Option<PathBuf>,
+    debounce: Duration,
+    events: Receiver<notify::Result<notify::Event>>,
+    _watcher: RecommendedWatcher,
+}
+
+impl std::fmt::Debug for Watcher {
+    fn fmt(&self, fmt: &mut std::fmt::Formatter) -> std::fmt::Result {
+        fmt.debug_struct("Watcher")
+            .field("files", &self.files)
+            .field("dirs", &self.dirs)
+            .field("debounce", &self.debounce)
+            .finish()
+    }
+}
+
+fn watch_error(err: notify::Error) -> PandocError {
+    match err.kind {
+        notify::ErrorKind::Io(err) => PandocError::IoErr(err),
+        _ => PandocError::IoErr(std::io::Error::other(err)),
+    }
+}
+
+/// `path` as absolute path, with the directory resolved like in the paths of the events
+fn absolute(path: &Path) -> PathBuf {
+    let path = std::env::current_dir()
+        .map(|dir| dir.join(path))
+        .unwrap_or_else(|_| path.to_owned());
+    match (path.parent(), path.file_name()) {
+        (Some(dir), Some(name)) => match dir.canonicalize() {
+            Ok(dir) => dir.join(name),
+            Err(_) => path,
+        },
+        _ => path,
+    }
+}
+
+impl Watcher {
+    /// Watch the sources of `pandoc` and the files and directories in `paths`.
+    ///
+    /// Directories are watched including their subdirectories. Files are watched through
+    /// their directory, so files replaced by editors are noticed, too.
+    pub fn new<I>(pandoc: Pandoc, paths: I) -> Result<Watcher, PandocError>
+    where
+        I: IntoIterator,
+        I::Item: AsRef<Path>,
+    {
+        let (sender, events) = channel();
+        let mut watcher = notify::recommended_watcher(sender).map_err(watch_error)?;
+        let mut files = HashSet::new();
+        let mut dirs = Vec::new();
+        let paths = pandoc
+            .dependencies()
+            .into_iter()
+            .chain(paths.into_iter().map(|path| path.as_ref().to_owned()));
+        for path in paths {
+            let path = absolute(&path);
+            if path.is_dir() {
+                watcher
+                    .watch(&path, RecursiveMode::Recursive)
+                    .map_err(watch_error)?;
+                dirs.push(path);
+            } else if files.insert(path.clone()) {
+                let dir = path.parent().unwrap_or(&path);
+                watcher
+                    .watch(dir, RecursiveMode::NonRecursive)
+                    .map_err(watch_error)?;
+            }
+        }
+        let output = match pandoc.output {
+            Some(OutputKind::File(ref file)) | Some(OutputKind::Tee(ref file)) => {
+                Some(absolute(file))
+            }
+            _ => None,
+        };
+        Ok(Watcher {
+            pandoc,
+            files,
+            dirs,
+            output,
+            debounce: Duration::from_millis(100),
+            events,
+            _watcher: watcher,
+        })
+    }
+
+    /// How long to wait for further changes before rebuilding, 100ms by default.
+    pub fn set_debounce(&mut self, debounce: Duration) -> &mut Watcher {
+        self.debounce = debounce;
+        self
+    }
+
+    fn is_relevant(&self, event: &notify::Event) -> bool {
+        if let EventKind::Access(_) = event.kind {
+            return false;
+        }
+        event.paths.iter().any(|path| {
+            Some(path) != self.output.as_ref()
+                && (self.files.contains(path) || self.dirs.iter().any(|dir| path.starts_with(dir)))
+        })
+    }
+
+    /// Block until a source changes and no further changes happened for the debounce time.
+    pub fn wait_for_change(&mut self) -> Result<(), PandocError> {
+        let disconnected = || PandocError::IoErr(std::io::Error::other("the file watcher stopped"));
+        loop {
+            let event = self.events.recv().map_err(|_| disconnected())?;
+            if self.is_relevant(&event.map_err(watch_error)?) {
+                break;
+            }
+        }
+        loop {
+            match self.events.recv_timeout(self.debounce) {
+                Ok(event) => {
+                    event.map_err(watch_error)?;
+                }
+                Err(RecvTimeoutError::Timeout) => return Ok(()),
+                Err(RecvTimeoutError::Disconnected) => return Err(disconnected()),
+            }
+        }
+    }
+
+    /// Run the conversion.
+    pub fn rebuild(&self) -> Result<PandocOutput, PandocError> {
+        self.pandoc.clone().execute()
+    }
+
+    /// Run the conversion now and after every change, passing each result to `on_rebuild`.
+    ///
+    /// Returns once `on_rebuild` returns `false`, or with an error if watching fails.
+    pub fn run<F>(mut self, mut on_rebuild: F) -> Result<(), PandocError>
+    where
+        F: FnMut(Result<PandocOutput, PandocError>) -> bool,
+    {
+        while on_rebuild(self.rebuild()) {
+            self.wait_for_change()?;
+        }
+        Ok(())
+    }
+}
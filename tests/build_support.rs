@@ -0,0 +1,39 @@
+//! The build script helpers read `OUT_DIR` and find pandoc through `PANDOC`, so they are tested
+//! in their own process, where changing the environment can't affect other tests.
+
+#![cfg(unix)]
+
+extern crate pandoc;
+
+mod common;
+
+#[test]
+fn convert_to_out_dir() {
+    let dir = pandoc::TempDir::new().unwrap();
+    let out_dir = dir.path().join("out");
+    std::fs::create_dir(&out_dir).unwrap();
+    let input = dir.path().join("README.md");
+    std::fs::write(&input, "# tool").unwrap();
+    // writes its arguments to the output file
+    let script = "for arg; do [ \"$prev\" = -o ] && out=$arg; prev=$arg; done\n\
+                  echo \"$@\" >\"$out\"\n";
+    let program = common::fake_program(dir.path(), "pandoc", script);
+    std::env::set_var("OUT_DIR", &out_dir);
+    assert_eq!(pandoc::build_support::out_dir(), out_dir);
+
+    std::env::set_var("PANDOC", &program);
+    let man_page = pandoc::build_support::convert_to_out_dir(&input, "tool.1")
+        .unwrap()
+        .unwrap();
+    assert_eq!(man_page, out_dir.join("tool.1"));
+    let args = std::fs::read_to_string(&man_page).unwrap();
+    assert!(args.contains(input.to_str().unwrap()));
+    assert!(args.contains("--standalone"));
+
+    // a missing pandoc is not an error in build scripts
+    std::env::set_var("PANDOC", dir.path().join("missing"));
+    let skipped = pandoc::build_support::convert_to_out_dir(&input, "skipped.1").unwrap();
+    assert_eq!(skipped, None);
+    assert!(!out_dir.join("skipped.1").exists());
+    std::env::remove_var("PANDOC");
+}
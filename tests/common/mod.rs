@@ -0,0 +1,13 @@
+//! helpers shared by the integration tests
+
+/// Write an executable shell script `program` running `script` into `dir`, to stand in for
+/// pandoc or the tools it calls. Returns the path of the script.
+#[cfg(unix)]
+pub fn fake_program(dir: &std::path::Path, program: &str, script: &str) -> std::path::PathBuf {
+    use std::os::unix::fs::PermissionsExt;
+
+    let path = dir.join(program);
+    std::fs::write(&path, format!("#!/bin/sh\n{}", script)).unwrap();
+    std::fs::set_permissions(&path, std::fs::Permissions::from_mode(0o755)).unwrap();
+    path
+}
@@ -0,0 +1,48 @@
+//! The one-line conversions find pandoc through `PANDOC`, so they are tested in their own
+//! process, where changing the environment can't affect other tests.
+
+#![cfg(unix)]
+
+extern crate pandoc;
+
+mod common;
+
+#[test]
+fn one_line_conversions() {
+    let dir = pandoc::TempDir::new().unwrap();
+    // prints the formats and its input, or writes its arguments to the output file
+    let script = "for arg; do case $prev in -f) from=$arg;; -t) to=$arg;; \
+                  -o) out=$arg;; esac; prev=$arg; done\n\
+                  if [ -z \"$out\" ] || [ \"$out\" = - ]; then printf '%s>%s:' \"$from\" \"$to\"; \
+                  cat; else echo \"$@\" >\"$out\"; fi\n";
+    let program = common::fake_program(dir.path(), "pandoc", script);
+    std::env::set_var("PANDOC", &program);
+
+    assert_eq!(
+        pandoc::markdown_to_html("*hi*").unwrap(),
+        "markdown>html5:*hi*"
+    );
+    assert_eq!(
+        pandoc::html_to_markdown("<p>hi</p>").unwrap(),
+        "html>markdown:<p>hi</p>"
+    );
+    assert_eq!(
+        pandoc::markdown_to_docx_bytes("hi").unwrap(),
+        b"markdown>docx:hi".to_vec()
+    );
+
+    let input = dir.path().join("notes.rst");
+    std::fs::write(&input, "notes").unwrap();
+    let converted = |to: &str| {
+        let output = pandoc::convert_file(&input, dir.path().join(to)).unwrap();
+        std::fs::read_to_string(output).unwrap()
+    };
+    let args = converted("notes.html");
+    assert!(args.contains("-f rst"));
+    assert!(args.contains("-t html"));
+    assert!(args.contains("--standalone"));
+    assert!(converted("notes.txt").contains("-t markdown"));
+    // pandoc falls back to HTML itself
+    assert!(!converted("notes.unknown").contains("-t "));
+    std::env::remove_var("PANDOC");
+}
@@ -0,0 +1,100 @@
+//! Pandoc is looked up through `PANDOC`, the path hints and the built-in locations, which are
+//! partly found through `CARGO_HOME`, so the lookup is tested in its own process, where
+//! changing the environment can't affect other tests.
+
+#![cfg(unix)]
+
+extern crate pandoc;
+
+mod common;
+
+use common::fake_program;
+use pandoc::{InputKind, OutputKind, PandocOutput};
+use std::sync::Mutex;
+
+/// the tests change the environment and clear the path cache
+static LOOKUP: Mutex<()> = Mutex::new(());
+
+/// the output of the pandoc found with the path hint `dir`
+fn run(dir: &std::path::Path) -> String {
+    let mut pandoc = pandoc::new();
+    pandoc.add_pandoc_path_hint(dir);
+    pandoc.set_input(InputKind::Pipe(String::new()));
+    pandoc.set_output(OutputKind::Pipe);
+    match pandoc.execute().unwrap() {
+        PandocOutput::ToBuffer(output) => output,
+        _ => panic!("expected the output of pandoc"),
+    }
+}
+
+#[test]
+fn pandoc_variable() {
+    let _lock = LOOKUP.lock().unwrap_or_else(|e| e.into_inner());
+    let dir = pandoc::TempDir::new().unwrap();
+    let hinted = dir.path().join("hinted");
+    std::fs::create_dir(&hinted).unwrap();
+    fake_program(&hinted, "pandoc", "echo hinted\n");
+    let program = fake_program(dir.path(), "pandoc", "echo variable\n");
+
+    // the executable `PANDOC` names is run instead of the one found with the path hints
+    std::env::set_var("PANDOC", &program);
+    assert_eq!(run(&hinted), "variable\n");
+    std::env::set_var("PANDOC", "");
+    assert_eq!(run(&hinted), "hinted\n");
+    std::env::remove_var("PANDOC");
+    assert_eq!(run(&hinted), "hinted\n");
+}
+
+#[test]
+fn builtin_path_hints() {
+    let _lock = LOOKUP.lock().unwrap_or_else(|e| e.into_inner());
+    let dir = pandoc::TempDir::new().unwrap();
+    // `cargo install` puts tectonic in `CARGO_HOME`, one of the built-in locations
+    let cargo_bin = dir.path().join("bin");
+    std::fs::create_dir(&cargo_bin).unwrap();
+    let engine = fake_program(&cargo_bin, "pandoc-test-engine", "exit 0\n");
+    let cargo_home = std::env::var_os("CARGO_HOME");
+    std::env::set_var("CARGO_HOME", dir.path());
+    pandoc::clear_path_cache();
+
+    let mut pandoc = pandoc::new();
+    pandoc.add_pandoc_path_hint(dir.path());
+    pandoc.add_option(pandoc::PandocOption::PdfEngine("pandoc-test-engine".into()));
+    assert_eq!(pandoc.resolve_pdf_engine(), Some(engine));
+    pandoc.disable_builtin_path_hints();
+    assert_eq!(pandoc.resolve_pdf_engine(), None);
+
+    match cargo_home {
+        Some(cargo_home) => std::env::set_var("CARGO_HOME", cargo_home),
+        None => std::env::remove_var("CARGO_HOME"),
+    }
+    pandoc::clear_path_cache();
+}
+
+#[test]
+fn clear_path_cache() {
+    let _lock = LOOKUP.lock().unwrap_or_else(|e| e.into_inner());
+    let dir = pandoc::TempDir::new().unwrap();
+    let (first, second) = (dir.path().join("first"), dir.path().join("second"));
+    std::fs::create_dir(&first).unwrap();
+    std::fs::create_dir(&second).unwrap();
+    fake_program(&second, "pandoc", "echo second\n");
+    let run = || {
+        let mut pandoc = pandoc::new();
+        pandoc.add_pandoc_path_hint(&first);
+        pandoc.add_pandoc_path_hint(&second);
+        pandoc.set_input(InputKind::Pipe(String::new()));
+        pandoc.set_output(OutputKind::Pipe);
+        match pandoc.execute().unwrap() {
+            PandocOutput::ToBuffer(output) => output,
+            _ => panic!("expected the output of pandoc"),
+        }
+    };
+    assert_eq!(run(), "second\n");
+
+    // a pandoc installed later in a directory searched first is only found after clearing
+    fake_program(&first, "pandoc", "echo first\n");
+    assert_eq!(run(), "second\n");
+    pandoc::clear_path_cache();
+    assert_eq!(run(), "first\n");
+}
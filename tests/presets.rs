@@ -0,0 +1,61 @@
+//! The PDF preset probes the engines on the `PATH` of the current process, so it is tested in
+//! its own process, where changing the environment can't affect other tests.
+
+#![cfg(all(unix, feature = "test-util"))]
+
+extern crate pandoc;
+
+mod common;
+
+use common::fake_program;
+use pandoc::mock::MockExecutor;
+use pandoc::{ErrorKind, InputKind, OutputKind};
+
+/// the arguments the preset passes to pandoc
+fn pdf_args() -> Vec<String> {
+    let mock = MockExecutor::new();
+    let mut pandoc = pandoc::presets::pdf().unwrap();
+    pandoc.set_input(InputKind::Pipe(String::new()));
+    pandoc.set_output(OutputKind::Pipe);
+    pandoc.execute_with(&mock).unwrap();
+    let call = mock.calls().remove(0);
+    call.args
+        .into_iter()
+        .map(|arg| arg.into_string().unwrap())
+        .collect()
+}
+
+#[test]
+fn pdf_preset() {
+    let dir = pandoc::TempDir::new().unwrap();
+    let path = std::env::var_os("PATH");
+    std::env::set_var("PATH", dir.path());
+    let err = pandoc::presets::pdf().err().unwrap();
+    assert_eq!(err.kind(), ErrorKind::PdfEngineNotFound);
+
+    fake_program(dir.path(), "wkhtmltopdf", "exit 0\n");
+    let args = pdf_args();
+    assert!(args.contains(&"--pdf-engine=wkhtmltopdf".to_string()));
+    assert!(args
+        .windows(2)
+        .any(|pair| pair == ["-V", "margin-top:25mm"]));
+
+    // xelatex is preferred, with the DejaVu fonts that are installed
+    fake_program(dir.path(), "xelatex", "exit 0\n");
+    fake_program(
+        dir.path(),
+        "fc-list",
+        "[ \"$1\" = 'DejaVu Serif' ] && echo DejaVuSerif.ttf\n",
+    );
+    let args = pdf_args();
+    assert!(args.contains(&"--pdf-engine=xelatex".to_string()));
+    let var = |value: &str| args.windows(2).any(|pair| pair == ["-V", value]);
+    assert!(var("geometry:margin=2.5cm"));
+    assert!(var("mainfont:DejaVu Serif"));
+    assert!(!args.iter().any(|arg| arg.starts_with("sansfont:")));
+
+    match path {
+        Some(path) => std::env::set_var("PATH", path),
+        None => std::env::remove_var("PATH"),
+    }
+}
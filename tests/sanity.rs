@@ -1,5 +1,7 @@
 extern crate pandoc;
 
+mod common;
+
 use std::path::PathBuf;
 
 #[test]
@@ -9,6 +11,8 @@ fn creation() {
     let mut pandoc = pandoc::new();
 
     pandoc.add_input("cake");
+    pandoc.add_inputs(&["is", "a"]);
+    pandoc.add_inputs(vec![PathBuf::from("lie")]);
     pandoc.set_output(OutputKind::File(PathBuf::from("lie")));
     pandoc.set_chapters();
     pandoc.set_number_sections();
@@ -25,3 +29,2854 @@ fn creation() {
     let rep = "to";
     pandoc.add_filter(move |s| s.replace(pat, rep));
 }
+
+#[test]
+fn error_kind() {
+    use pandoc::{ErrorKind, PandocError};
+    assert_eq!(
+        PandocError::PandocNotFound.kind(),
+        ErrorKind::PandocNotFound
+    );
+    assert_eq!(
+        PandocError::MissingOutput(PathBuf::from("lie")).kind(),
+        ErrorKind::MissingOutput
+    );
+    let err = PandocError::from(std::io::Error::from(std::io::ErrorKind::NotFound));
+    assert_eq!(err.kind(), ErrorKind::PandocNotFound);
+}
+
+#[test]
+fn pdf_error_hints() {
+    let stderr = "Error producing PDF.\n\
+                  ! LaTeX Error: File `ulem.sty' not found.\n\
+                  \n\
+                  Type X to quit or <RETURN> to proceed,\n";
+    let pdf = pandoc::PdfError::from_stderr(stderr).unwrap();
+    assert_eq!(pdf.missing_packages, vec!["ulem".to_string()]);
+    assert!(pdf.hints[0].contains("mpm --install=ulem"));
+    assert!(pandoc::PdfError::from_stderr("[WARNING] nothing to see").is_none());
+
+    let stderr = "Error producing PDF.\n\
+                  error: package not found (searched for @preview/cetz:0.0.1)\n";
+    let pdf = pandoc::PdfError::from_stderr(stderr).unwrap();
+    assert_eq!(
+        pdf.missing_packages,
+        vec!["@preview/cetz:0.0.1".to_string()]
+    );
+}
+
+#[cfg(feature = "test-util")]
+#[test]
+fn pdf_error_source() {
+    use pandoc::mock::MockExecutor;
+    use pandoc::{InputKind, OutputFormat, OutputKind, PdfError};
+    use std::error::Error;
+
+    let mock = MockExecutor::new();
+    mock.push_failure(
+        43,
+        "Error producing PDF.\n! LaTeX Error: File `ulem.sty' not found.\n",
+    );
+    let mut pandoc = pandoc::new();
+    pandoc.set_input(InputKind::Pipe(String::new()));
+    pandoc.set_output(OutputKind::Pipe);
+    pandoc.set_output_format(OutputFormat::Pdf, Vec::new());
+    let err = pandoc.execute_with(&mock).err().unwrap();
+    let pdf = err.source().unwrap().downcast_ref::<PdfError>().unwrap();
+    assert_eq!(pdf.missing_packages, vec!["ulem".to_string()]);
+}
+
+#[test]
+fn warning_classes() {
+    use pandoc::WarningClass;
+    let stderr =
+        "[WARNING] Could not fetch resource 'missing.png': replacing image with description\n\
+                  [WARNING] Duplicate identifier 'intro' at input.md line 12 column 1\n\
+                  [WARNING] Citeproc: citation doe99 not found\n\
+                  [WARNING] Something else\n";
+    let classes: Vec<_> = pandoc::parse_warnings(stderr)
+        .into_iter()
+        .map(|warning| warning.class)
+        .collect();
+    assert_eq!(
+        classes,
+        vec![
+            WarningClass::MissingResource,
+            WarningClass::DuplicateIdentifier,
+            WarningClass::CitationNotFound,
+            WarningClass::Other,
+        ]
+    );
+}
+
+#[test]
+fn missing_input() {
+    let mut pandoc = pandoc::new();
+    pandoc.add_input("does-not-exist.md");
+    pandoc.set_output(pandoc::OutputKind::Pipe);
+    match pandoc.execute() {
+        Err(pandoc::PandocError::MissingInput(path)) => {
+            assert_eq!(path, PathBuf::from("does-not-exist.md"))
+        }
+        _ => panic!("expected a missing input error"),
+    }
+}
+
+#[cfg(feature = "test-util")]
+#[test]
+fn mock_executor() {
+    use pandoc::mock::MockExecutor;
+    use pandoc::{ErrorKind, InputKind, OutputFormat, OutputKind, PandocOutput};
+
+    let mock = MockExecutor::new();
+    mock.push_output("{\"blocks\":[]}");
+    mock.push_output("<p>filtered</p>");
+    mock.push_failure(64, "pandoc: Unknown writer");
+
+    let mut pandoc = pandoc::new();
+    pandoc.set_input(InputKind::Pipe("# title".to_string()));
+    pandoc.set_output(OutputKind::Pipe);
+    pandoc.set_output_format(OutputFormat::Html, Vec::new());
+    pandoc.add_filter(|json| json.replace("[]", "[1]"));
+    match pandoc.clone().execute_with(&mock).unwrap() {
+        PandocOutput::ToBuffer(html) => assert_eq!(html, "<p>filtered</p>"),
+        _ => panic!("expected a buffer"),
+    }
+    match pandoc.execute_with(&mock) {
+        Err(err) => assert_eq!(err.kind(), ErrorKind::PandocFailed),
+        Ok(_) => panic!("expected the canned failure"),
+    }
+
+    let calls = mock.calls();
+    assert_eq!(calls.len(), 3);
+    assert_eq!(calls[0].stdin.as_deref(), Some(&b"# title"[..]));
+    assert_eq!(calls[1].stdin.as_deref(), Some(&b"{\"blocks\":[1]}"[..]));
+    assert!(calls[1].args.iter().any(|arg| arg == "html"));
+}
+
+#[cfg(feature = "test-util")]
+#[test]
+fn stream_filters() {
+    use pandoc::mock::MockExecutor;
+    use pandoc::{InputKind, OutputFormat, OutputKind};
+    use std::io::{BufRead, BufReader};
+
+    let mock = MockExecutor::new();
+    mock.push_output("{\"blocks\":[]}");
+    mock.push_output("<p>filtered</p>");
+
+    let mut pandoc = pandoc::new();
+    pandoc.set_input(InputKind::Pipe("# title".to_string()));
+    pandoc.set_output(OutputKind::Pipe);
+    pandoc.set_output_format(OutputFormat::Html, Vec::new());
+    pandoc.add_stream_filter(|ast, out| {
+        for line in BufReader::new(ast).lines() {
+            out.write_all(line?.replace("[]", "[1]").as_bytes())?;
+        }
+        Ok(())
+    });
+    pandoc.add_filter(|json| json.replace("[1]", "[1,2]"));
+    pandoc.execute_with(&mock).unwrap();
+
+    let calls = mock.calls();
+    assert_eq!(calls[1].stdin.as_deref(), Some(&b"{\"blocks\":[1,2]}"[..]));
+}
+
+#[cfg(feature = "test-util")]
+#[test]
+fn filter_api_version() {
+    use pandoc::mock::MockExecutor;
+    use pandoc::{ErrorKind, InputKind, OutputKind};
+
+    let ast = r#"{"pandoc-api-version":[1,22,2,1],"meta":{},"blocks":[]}"#;
+    let mock = MockExecutor::new();
+    mock.push_output(ast);
+    mock.push_output("");
+    let mut pandoc = pandoc::new();
+    pandoc.set_input(InputKind::Pipe(String::new()));
+    pandoc.set_output(OutputKind::Pipe);
+    pandoc.add_versioned_filter(|version, json| {
+        assert_eq!(version, [1, 22, 2, 1]);
+        json
+    });
+    pandoc.clone().execute_with(&mock).unwrap();
+
+    mock.push_output(ast);
+    pandoc.require_filter_api_version(1, 23);
+    let err = pandoc.execute_with(&mock).err().unwrap();
+    assert_eq!(err.kind(), ErrorKind::UnsupportedApiVersion);
+}
+
+#[test]
+fn parse_formats() {
+    use pandoc::{ErrorKind, InputFormat, MarkdownExtension, OutputFormat};
+
+    let (format, extensions) =
+        InputFormat::parse_with_extensions("markdown+smart-raw_html").unwrap();
+    assert_eq!(format.to_string(), "markdown");
+    assert!(matches!(extensions[0], MarkdownExtension::Smart));
+    match extensions[1] {
+        MarkdownExtension::Disable(ref e) => assert!(matches!(**e, MarkdownExtension::RawHtml)),
+        _ => panic!("expected a disabled extension"),
+    }
+    assert!(matches!("docx".parse(), Ok(OutputFormat::Docx)));
+    assert!(matches!("gfm".parse(), Ok(OutputFormat::Gfm)));
+    assert!(matches!("zimwiki".parse(), Ok(OutputFormat::ZimWiki)));
+    assert!(matches!(
+        InputFormat::from_extension("muse"),
+        Some(InputFormat::Muse)
+    ));
+    assert!(matches!(
+        InputFormat::from_extension("bib"),
+        Some(InputFormat::BibLaTeX)
+    ));
+    assert_eq!(OutputFormat::CslJson.to_string(), "csljson");
+    assert!(matches!("tsv".parse(), Ok(InputFormat::Tsv)));
+    assert!(matches!(
+        "jats_publishing".parse(),
+        Ok(OutputFormat::JatsPublishing)
+    ));
+    assert!(matches!(
+        OutputFormat::from_extension("ms"),
+        Some(OutputFormat::Ms)
+    ));
+    assert!(matches!("markua".parse(), Ok(OutputFormat::Markua)));
+    let (format, extensions) = OutputFormat::parse_with_extensions("gfm+emoji-alerts").unwrap();
+    assert!(matches!(format, OutputFormat::Gfm));
+    assert!(matches!(extensions[0], MarkdownExtension::Emoji));
+    assert_eq!(extensions[1].to_string(), "alerts");
+    let (_, extensions) =
+        InputFormat::parse_with_extensions("commonmark_x+sourcepos+mark").unwrap();
+    assert!(matches!(extensions[0], MarkdownExtension::Sourcepos));
+    assert!(matches!(extensions[1], MarkdownExtension::Mark));
+    assert!(matches!(
+        OutputFormat::from_extension("TEX"),
+        Some(OutputFormat::Latex)
+    ));
+    let pptx = OutputFormat::from_extension("pptx").unwrap();
+    assert!(matches!(pptx, OutputFormat::Pptx) && pptx.is_binary());
+    for spec in ["", "+smart", "markdown+", "mark down"] {
+        let err = OutputFormat::parse_with_extensions(spec).unwrap_err();
+        assert_eq!(err.kind(), ErrorKind::InvalidFormat);
+    }
+}
+
+#[test]
+fn conversion_pool() {
+    use pandoc::{ConversionPool, ErrorKind};
+
+    let pool = ConversionPool::new(2, 1);
+    let handles: Vec<_> = (0..4).map(|_| pool.submit(pandoc::new)).collect();
+    for handle in handles {
+        let err = handle.wait().err().expect("no output was configured");
+        assert_eq!(err.kind(), ErrorKind::NoOutputSpecified);
+    }
+}
+
+#[cfg(feature = "fallback")]
+#[test]
+fn fallback_renderer() {
+    use pandoc::{InputFormat, InputKind, OutputFormat, OutputKind, PandocOutput};
+
+    let mut pandoc = pandoc::new();
+    pandoc.set_input(InputKind::Pipe("some *text*".to_string()));
+    pandoc.set_input_format(InputFormat::Markdown, Vec::new());
+    pandoc.set_output(OutputKind::Pipe);
+    pandoc.set_output_format(OutputFormat::Html, Vec::new());
+    // pandoc and the fallback agree on this one
+    match pandoc.execute_or_fallback().unwrap().0 {
+        PandocOutput::ToBuffer(html) => assert_eq!(html.trim(), "<p>some <em>text</em></p>"),
+        _ => panic!("expected a buffer"),
+    }
+}
+
+#[test]
+fn template_data_dir() {
+    use pandoc::templates::DataDir;
+
+    let dir = pandoc::TempDir::new().unwrap();
+    let data_dir = DataDir::new(&dir.path().join("data"));
+    assert!(data_dir.templates().unwrap().is_empty());
+    data_dir.install("report.latex", "$body$").unwrap();
+    data_dir.install("default.html5", "$body$").unwrap();
+    assert_eq!(
+        data_dir.templates().unwrap(),
+        ["default.html5", "report.latex"]
+    );
+}
+
+#[cfg(feature = "test-util")]
+#[test]
+fn page_layout() {
+    use pandoc::mock::MockExecutor;
+    use pandoc::{InputKind, Margins, OutputFormat, OutputKind, PageLayout, PaperSize};
+
+    let mock = MockExecutor::new();
+    mock.push_output("");
+    mock.push_output("");
+    let layout = PageLayout {
+        paper_size: Some(PaperSize::A4),
+        margins: Some(Margins::uniform("2cm")),
+        landscape: true,
+        two_column: true,
+    };
+    for format in [OutputFormat::Latex, OutputFormat::Context] {
+        let mut pandoc = pandoc::new();
+        pandoc.set_input(InputKind::Pipe(String::new()));
+        pandoc.set_output(OutputKind::Pipe);
+        pandoc.set_output_format(format, Vec::new());
+        pandoc.set_variable("geometry", "margin=1in");
+        pandoc.set_page_layout(layout.clone());
+        pandoc.execute_with(&mock).unwrap();
+    }
+
+    let calls = mock.calls();
+    let vars = |args: &[std::ffi::OsString]| -> Vec<String> {
+        args.windows(2)
+            .filter(|pair| pair[0] == "-V")
+            .map(|pair| pair[1].to_string_lossy().into_owned())
+            .collect()
+    };
+    assert_eq!(
+        vars(&calls[0].args),
+        [
+            "papersize:a4",
+            "geometry:margin=2cm",
+            "geometry:landscape",
+            "classoption:twocolumn",
+        ]
+    );
+    assert_eq!(
+        vars(&calls[1].args),
+        [
+            "papersize:A4",
+            "papersize:landscape",
+            "margin-top:2cm",
+            "margin-right:2cm",
+            "margin-bottom:2cm",
+            "margin-left:2cm",
+        ]
+    );
+}
+
+#[cfg(feature = "test-util")]
+#[test]
+fn document_metadata() {
+    use pandoc::mock::MockExecutor;
+    use pandoc::{Author, InputKind, OutputKind};
+
+    let mock = MockExecutor::new();
+    mock.push_output("");
+    mock.push_output("");
+    let mut pandoc = pandoc::new();
+    pandoc.set_input(InputKind::Pipe(String::new()));
+    pandoc.set_output(OutputKind::Pipe);
+    pandoc
+        .set_title("Draft")
+        .set_title("Final")
+        .set_lang("en-GB");
+    pandoc.set_keywords(vec!["rust", "pandoc"]);
+    pandoc
+        .set_authors(vec![Author::new("Ada"), Author::new("Grace")])
+        .unwrap();
+    pandoc.clone().execute_with(&mock).unwrap();
+    let mut affiliated = Author::new("Ada");
+    affiliated
+        .affiliations
+        .push("Analytical Engines".to_string());
+    pandoc.set_authors(vec![affiliated]).unwrap();
+    pandoc.execute_with(&mock).unwrap();
+
+    let calls = mock.calls();
+    let meta = |args: &[std::ffi::OsString]| -> Vec<String> {
+        args.windows(2)
+            .filter(|pair| pair[0] == "-M")
+            .map(|pair| pair[1].to_string_lossy().into_owned())
+            .collect()
+    };
+    assert_eq!(
+        meta(&calls[0].args),
+        [
+            "title:Final",
+            "lang:en-GB",
+            "keywords:rust",
+            "keywords:pandoc",
+            "author:Ada",
+            "author:Grace",
+        ]
+    );
+    assert_eq!(
+        meta(&calls[1].args),
+        [
+            "title:Final",
+            "lang:en-GB",
+            "keywords:rust",
+            "keywords:pandoc"
+        ]
+    );
+    assert!(calls[1]
+        .args
+        .iter()
+        .any(|arg| arg.to_string_lossy().starts_with("--metadata-file=")));
+}
+
+#[test]
+fn convert_tree_copies_assets() {
+    let dir = pandoc::TempDir::new().unwrap();
+    let src = dir.path().join("docs");
+    std::fs::create_dir_all(src.join("img")).unwrap();
+    std::fs::write(src.join("img/logo.svg"), "<svg/>").unwrap();
+    std::fs::write(src.join(".hidden"), "").unwrap();
+
+    let mut options = pandoc::TreeOptions::default();
+    options.conversions.clear();
+    let dst = src.join("site");
+    let converted = pandoc::convert_tree(&src, &dst, &options).unwrap();
+    assert!(converted.is_empty());
+    assert!(dst.join("img/logo.svg").is_file());
+    assert!(!dst.join(".hidden").exists());
+    assert!(!dst.join("site").exists());
+}
+
+#[cfg(feature = "watch")]
+#[test]
+fn watcher_notices_changes() {
+    let dir = pandoc::TempDir::new().unwrap();
+    let input = dir.path().join("notes.md");
+    std::fs::write(&input, "# notes").unwrap();
+    let mut pandoc = pandoc::new();
+    pandoc.add_input(&input);
+    pandoc.set_output(pandoc::OutputKind::File(dir.path().join("notes.html")));
+    let mut watcher =
+        pandoc::watch::Watcher::new(pandoc, Vec::<std::path::PathBuf>::new()).unwrap();
+    watcher.set_debounce(std::time::Duration::from_millis(10));
+
+    std::fs::write(dir.path().join("notes.html"), "").unwrap();
+    std::fs::write(&input, "# more notes").unwrap();
+    watcher.wait_for_change().unwrap();
+}
+
+#[cfg(feature = "test-util")]
+#[test]
+fn conversion_cache() {
+    use pandoc::mock::MockExecutor;
+    use pandoc::{InputKind, OutputFormat, OutputKind, PandocOutput};
+
+    let dir = pandoc::TempDir::new().unwrap();
+    let mock = MockExecutor::new();
+    mock.push_output("pandoc 3.1");
+    mock.push_output("<p>cached</p>");
+    mock.push_output("pandoc 3.1");
+    mock.push_output("pandoc 3.1");
+    mock.push_output("<p>changed</p>");
+    let convert = |input: &str| {
+        let mut pandoc = pandoc::new();
+        pandoc.set_input(InputKind::Pipe(input.to_string()));
+        pandoc.set_output(OutputKind::Pipe);
+        pandoc.set_output_format(OutputFormat::Html, Vec::new());
+        pandoc.set_cache_dir(dir.path());
+        match pandoc.execute_with(&mock).unwrap() {
+            PandocOutput::ToBuffer(html) => html,
+            _ => panic!("expected a buffer"),
+        }
+    };
+    assert_eq!(convert("cached"), "<p>cached</p>");
+    assert_eq!(convert("cached"), "<p>cached</p>");
+    assert_eq!(convert("changed"), "<p>changed</p>");
+    assert_eq!(mock.calls().len(), 5);
+}
+
+#[cfg(feature = "test-util")]
+#[test]
+fn conversion_cache_key() {
+    use pandoc::mock::MockExecutor;
+    use pandoc::{ErrorKind, InputKind, OutputKind, WarningClass};
+
+    let dir = pandoc::TempDir::new().unwrap();
+    let warning = "[WARNING] Duplicate identifier 'intro' at line 3\n";
+    let mock = MockExecutor::new();
+    mock.push_output("pandoc 3.1");
+    mock.push_output_with_stderr("<p>warned</p>", warning);
+    mock.push_output("pandoc 3.1");
+    mock.push_output_with_stderr("<p>warned</p>", warning);
+    let mut pandoc = pandoc::new();
+    pandoc.set_input(InputKind::Pipe("warned".to_string()));
+    pandoc.set_output(OutputKind::Pipe);
+    pandoc.set_cache_dir(dir.path());
+    pandoc.clone().execute_with(&mock).unwrap();
+    // runs with warnings are not cached, so they fail like uncached runs
+    pandoc.fail_on(WarningClass::DuplicateIdentifier);
+    let err = pandoc.execute_with(&mock).err().unwrap();
+    assert_eq!(err.kind(), ErrorKind::Warnings);
+    assert_eq!(mock.calls().len(), 4);
+    assert_eq!(std::fs::read_dir(dir.path()).unwrap().count(), 0);
+
+    let mock = MockExecutor::new();
+    let mut pandoc = pandoc::new();
+    pandoc.set_input(InputKind::Pipe("clean".to_string()));
+    pandoc.set_output(OutputKind::Pipe);
+    pandoc.set_cache_dir(dir.path());
+    pandoc.clone().execute_with(&mock).unwrap();
+    pandoc.clone().execute_with(&mock).unwrap();
+    assert_eq!(mock.calls().len(), 3);
+    let entries = || {
+        let mut names: Vec<_> = std::fs::read_dir(dir.path())
+            .unwrap()
+            .map(|entry| entry.unwrap().file_name().into_string().unwrap())
+            .collect();
+        names.sort();
+        names
+    };
+    let names = entries();
+    assert_eq!(names.len(), 1);
+    assert!(names[0].len() == 16 && names[0].chars().all(|c| c.is_ascii_hexdigit()));
+    // the settings of the output and the warnings are part of the key
+    pandoc.set_lossy_utf8(true);
+    pandoc.clone().execute_with(&mock).unwrap();
+    pandoc.fail_on(WarningClass::DuplicateIdentifier);
+    pandoc.execute_with(&mock).unwrap();
+    assert_eq!(mock.calls().len(), 7);
+    assert_eq!(entries().len(), 3);
+}
+
+#[cfg(feature = "test-util")]
+#[test]
+fn golden_files() {
+    use pandoc::testing::assert_matches_golden;
+
+    let dir = pandoc::TempDir::new().unwrap();
+    let golden = dir.path().join("doc.golden.html");
+    std::fs::write(&golden, "<h1>Title</h1>\n<p>one</p>\n<p>two</p>\n").unwrap();
+    assert_matches_golden("<h1>Title</h1>\n<p>one</p>\n<p>two</p>\n", &golden);
+
+    let panic = std::panic::catch_unwind(|| {
+        assert_matches_golden("<h1>Title</h1>\n<p>1</p>\n<p>two</p>\n", &golden)
+    })
+    .unwrap_err();
+    let message = panic.downcast_ref::<String>().unwrap();
+    assert!(
+        message.contains("- <p>one</p>\n+ <p>1</p>\n"),
+        "{}",
+        message
+    );
+}
+
+#[cfg(feature = "test-util")]
+#[test]
+fn reproducible_pins_missing_date() {
+    use pandoc::mock::MockExecutor;
+    use pandoc::{InputKind, OutputKind};
+
+    let mock = MockExecutor::new();
+    mock.push_output("");
+    mock.push_output("");
+    let mut pandoc = pandoc::new();
+    pandoc.set_input(InputKind::Pipe(String::new()));
+    pandoc.set_output(OutputKind::Pipe);
+    pandoc.set_reproducible(true);
+    pandoc.clone().execute_with(&mock).unwrap();
+    pandoc.set_date("2024-01-31");
+    pandoc.execute_with(&mock).unwrap();
+
+    let has_metadata_file = |args: &[std::ffi::OsString]| {
+        args.iter()
+            .any(|arg| arg.to_string_lossy().starts_with("--metadata-file="))
+    };
+    let calls = mock.calls();
+    assert!(has_metadata_file(&calls[0].args));
+    assert!(!has_metadata_file(&calls[1].args));
+}
+
+#[cfg(all(unix, feature = "test-util"))]
+#[test]
+fn non_utf8_paths() {
+    use pandoc::mock::MockExecutor;
+    use pandoc::{InputKind, OutputKind, PandocOption};
+    use std::ffi::OsStr;
+    use std::os::unix::ffi::OsStrExt;
+
+    let dir = OsStr::from_bytes(b"/opt/pand\xffoc");
+    let mock = MockExecutor::new();
+    mock.push_output("");
+    let mut pandoc = pandoc::new();
+    pandoc.set_input(InputKind::Pipe(String::new()));
+    pandoc.set_output(OutputKind::Pipe);
+    pandoc.add_pandoc_path_hint(dir);
+    pandoc.add_option(PandocOption::DataDir(dir.into()));
+    pandoc.arg("lua-filter", dir);
+    pandoc.execute_with(&mock).unwrap();
+
+    let args = &mock.calls()[0].args;
+    for flag in &["--data-dir=", "--lua-filter="] {
+        let mut expected = std::ffi::OsString::from(flag);
+        expected.push(dir);
+        assert!(args.contains(&expected), "{:?}", args);
+    }
+}
+
+#[test]
+fn resolve_pdf_engine() {
+    use pandoc::PandocOption;
+
+    let dir = pandoc::TempDir::new().unwrap();
+    let engine = dir
+        .path()
+        .join(format!("fancylatex{}", std::env::consts::EXE_SUFFIX));
+    std::fs::write(&engine, "").unwrap();
+    let mut pandoc = pandoc::new();
+    pandoc.add_option(PandocOption::PdfEngine("fancylatex".into()));
+    assert_eq!(pandoc.resolve_pdf_engine(), None);
+    pandoc.add_latex_path_hint(dir.path());
+    assert_eq!(pandoc.resolve_pdf_engine(), Some(engine));
+}
+
+#[cfg(feature = "test-util")]
+#[test]
+fn runtime_system_options() {
+    use pandoc::mock::MockExecutor;
+    use pandoc::PandocRuntimeSystemOption::*;
+    use pandoc::{InputKind, OutputKind, PandocOption};
+
+    let mock = MockExecutor::new();
+    mock.push_output("");
+    let mut pandoc = pandoc::new();
+    pandoc.set_input(InputKind::Pipe(String::new()));
+    pandoc.set_output(OutputKind::Pipe);
+    pandoc.add_option(PandocOption::RuntimeSystem(vec![
+        MaximumHeapMemory("512M".to_string()),
+        MaximumStackSize("16M".to_string()),
+        AllocationAreaSize("8M".to_string()),
+        IdleGcDelay("0".to_string()),
+        Other("-c".to_string()),
+    ]));
+    pandoc.execute_with(&mock).unwrap();
+
+    let args = &mock.calls()[0].args;
+    let start = args.iter().position(|arg| arg == "+RTS").unwrap();
+    assert_eq!(
+        args[start..start + 7],
+        ["+RTS", "-M512M", "-K16M", "-A8M", "-I0", "-c", "-RTS"]
+    );
+}
+
+#[cfg(unix)]
+#[test]
+fn hardened_for_untrusted_input() {
+    use pandoc::{ErrorKind, InputKind, InputSource, OutputKind, PandocOption};
+    use std::time::{Duration, Instant};
+
+    let fake_pandoc = |version: &str| {
+        let dir = pandoc::TempDir::new().unwrap();
+        let script = format!(
+            "[ \"$1\" = --version ] && echo 'pandoc {}' && exit\nexec sleep 5\n",
+            version
+        );
+        common::fake_program(dir.path(), "pandoc", &script);
+        dir
+    };
+
+    let old = fake_pandoc("2.9.2");
+    let mut pandoc = pandoc::new();
+    pandoc.add_pandoc_path_hint(old.path());
+    let err = pandoc.hardened_for_untrusted_input().err().unwrap();
+    assert_eq!(err.kind(), ErrorKind::Unsupported);
+
+    let current = fake_pandoc("3.1.2");
+    let mut pandoc = pandoc::new();
+    pandoc.add_pandoc_path_hint(current.path());
+    pandoc.add_option(PandocOption::ResourcePath(vec!["assets".into()]));
+    pandoc.hardened_for_untrusted_input().unwrap();
+    pandoc.set_timeout(Duration::from_millis(200));
+    pandoc.set_input(InputKind::Pipe(String::new()));
+    pandoc.set_output(OutputKind::Pipe);
+    let start = Instant::now();
+    match pandoc.clone().execute() {
+        Err(err) => assert_eq!(err.kind(), ErrorKind::Timeout),
+        Ok(_) => panic!("expected a timeout"),
+    }
+    assert!(start.elapsed() < Duration::from_secs(4));
+
+    pandoc.set_input(InputKind::Sources(vec![InputSource::Url(
+        "https://example.com/doc.md".to_string(),
+    )]));
+    match pandoc.execute() {
+        Err(err) => assert_eq!(err.kind(), ErrorKind::Unsupported),
+        Ok(_) => panic!("expected the remote input to be rejected"),
+    }
+}
+
+#[cfg(feature = "test-util")]
+#[test]
+fn sandbox_unsupported() {
+    use pandoc::mock::MockExecutor;
+    use pandoc::{ErrorKind, InputKind, OutputFormat, OutputKind, PandocOption};
+
+    let mock = MockExecutor::new();
+    mock.push_failure(1, "Could not find data file reference.docx");
+    mock.push_failure(1, "Could not find data file reference.docx");
+    let mut pandoc = pandoc::new();
+    pandoc.set_input(InputKind::Pipe(String::new()));
+    pandoc.set_output(OutputKind::Pipe);
+    pandoc.set_output_format(OutputFormat::Docx, Vec::new());
+    let kind = |result: Result<_, pandoc::PandocError>| result.err().unwrap().kind();
+    assert_eq!(
+        kind(pandoc.clone().execute_with(&mock)),
+        ErrorKind::PandocFailed
+    );
+    pandoc.add_option(PandocOption::Sandbox);
+    assert_eq!(
+        kind(pandoc.execute_with(&mock)),
+        ErrorKind::SandboxUnsupported
+    );
+}
+
+#[cfg(unix)]
+#[test]
+fn unsupported_extension() {
+    use pandoc::{InputFormat, InputKind, MarkdownExtension, OutputKind, PandocError};
+
+    let dir = pandoc::TempDir::new().unwrap();
+    let script = "case \"$1\" in --list-extensions=markdown) \
+                  printf '+smart\\n-emoji\\n'; exit;; esac\necho converted\n";
+    common::fake_program(dir.path(), "pandoc", script);
+
+    let mut pandoc = pandoc::new();
+    pandoc.add_pandoc_path_hint(dir.path());
+    pandoc.set_input(InputKind::Pipe(String::new()));
+    pandoc.set_output(OutputKind::Pipe);
+    pandoc.set_input_format(
+        InputFormat::Markdown,
+        vec![
+            MarkdownExtension::Smart,
+            MarkdownExtension::Disable(Box::new(MarkdownExtension::Emoji)),
+        ],
+    );
+    pandoc.clone().execute().unwrap();
+
+    pandoc.set_input_format(
+        InputFormat::Markdown,
+        vec![
+            MarkdownExtension::Smart,
+            MarkdownExtension::Other("smrt".to_string()),
+        ],
+    );
+    match pandoc.execute() {
+        Err(PandocError::UnsupportedExtension(extension, format)) => {
+            assert_eq!(extension, "smrt");
+            assert_eq!(format, "markdown");
+        }
+        _ => panic!("expected the misspelled extension to be rejected"),
+    }
+}
+
+#[cfg(feature = "test-util")]
+#[test]
+fn mock_extension_check() {
+    use pandoc::mock::MockExecutor;
+    use pandoc::{InputFormat, InputKind, MarkdownExtension, OutputKind, PandocError};
+
+    let mut pandoc = pandoc::new();
+    pandoc.set_input(InputKind::Pipe(String::new()));
+    pandoc.set_output(OutputKind::Pipe);
+    pandoc.set_input_format(InputFormat::Markdown, vec![MarkdownExtension::Smart]);
+    // without extensions set, they are passed on unchecked
+    let mock = MockExecutor::new();
+    mock.push_output("converted");
+    pandoc.clone().execute_with(&mock).unwrap();
+    let calls = mock.calls();
+    assert_eq!(calls.len(), 1);
+    assert!(calls[0].args.iter().any(|arg| arg == "markdown+smart"));
+
+    // the queries neither use up the responses nor are recorded
+    let mock = MockExecutor::new();
+    mock.set_extensions("markdown", &["smart", "emoji"]);
+    mock.push_output("converted");
+    match pandoc.clone().execute_with(&mock).unwrap() {
+        pandoc::PandocOutput::ToBuffer(output) => assert_eq!(output, "converted"),
+        _ => panic!("expected a buffer"),
+    }
+    assert_eq!(mock.calls().len(), 1);
+
+    // a rejected extension skips the conversion
+    pandoc.set_input_format(
+        InputFormat::Markdown,
+        vec![MarkdownExtension::Other("smrt".to_string())],
+    );
+    match pandoc.clone().execute_with(&mock) {
+        Err(PandocError::UnsupportedExtension(extension, _)) => assert_eq!(extension, "smrt"),
+        _ => panic!("expected the misspelled extension to be rejected"),
+    }
+    assert_eq!(mock.calls().len(), 1);
+
+    // freezing the configuration doesn't run pandoc
+    let mock = MockExecutor::new();
+    let prepared = pandoc.freeze().unwrap();
+    prepared.execute_with("*hi*", &mock).unwrap();
+    assert_eq!(mock.calls().len(), 1);
+}
+
+#[cfg(feature = "test-util")]
+#[test]
+fn input_preprocessors() {
+    use pandoc::mock::MockExecutor;
+    use pandoc::{InputKind, OutputKind};
+
+    let dir = pandoc::TempDir::new().unwrap();
+    let file = dir.path().join("doc.md");
+    std::fs::write(&file, "+++\ndraft = true\n+++\n{{< year >}}\n").unwrap();
+    let strip_front_matter = |text: String| match text.strip_prefix("+++\n") {
+        Some(rest) => rest
+            .split_once("+++\n")
+            .map_or(text.clone(), |(_, body)| body.into()),
+        None => text,
+    };
+
+    let mock = MockExecutor::new();
+    let mut pandoc = pandoc::new();
+    pandoc.add_input_preprocessor(strip_front_matter);
+    pandoc.add_input_preprocessor(|text| text.replace("{{< year >}}", "2024"));
+    pandoc.set_output(OutputKind::Pipe);
+    pandoc.set_input(InputKind::Pipe("+++\n+++\n{{< year >}}".to_string()));
+    pandoc.clone().execute_with(&mock).unwrap();
+    pandoc.set_input(InputKind::Files(vec![file.clone()]));
+    pandoc.execute_with(&mock).unwrap();
+
+    let calls = mock.calls();
+    assert_eq!(calls[0].stdin.as_deref(), Some(&b"2024"[..]));
+    let copy = calls[1].args.last().unwrap();
+    assert_ne!(copy, file.as_os_str());
+    assert!(copy.to_string_lossy().ends_with(".md"));
+    assert_eq!(std::fs::read_to_string(&file).unwrap().lines().count(), 4);
+}
+
+#[cfg(feature = "test-util")]
+#[test]
+fn cmdline_callback() {
+    use pandoc::mock::MockExecutor;
+    use pandoc::{InputKind, OutputKind};
+    use std::sync::{Arc, Mutex};
+
+    let lines = Arc::new(Mutex::new(Vec::new()));
+    let sink = Arc::clone(&lines);
+    let mock = MockExecutor::new();
+    let mut pandoc = pandoc::new();
+    pandoc.set_input(InputKind::Pipe(String::new()));
+    pandoc.set_output(OutputKind::Pipe);
+    pandoc.set_cmdline_callback(move |line| sink.lock().unwrap().push(line.to_string()));
+    pandoc.add_filter(|json| json);
+    pandoc.execute_with(&mock).unwrap();
+
+    let lines = lines.lock().unwrap();
+    assert_eq!(lines.len(), 2);
+    assert!(lines.iter().all(|line| line.contains("pandoc")));
+}
+
+#[cfg(feature = "test-util")]
+#[test]
+fn chunked_html_output() {
+    use pandoc::mock::MockExecutor;
+    use pandoc::{ErrorKind, InputKind, OutputFormat, OutputKind};
+
+    let dir = pandoc::TempDir::new().unwrap();
+    let mock = MockExecutor::new();
+    let mut pandoc = pandoc::new();
+    pandoc.set_input(InputKind::Pipe("# one\n\n# two".to_string()));
+    pandoc.set_output(OutputKind::Directory(dir.path().join("manual")));
+    // the mock writes a single file instead of a directory, which can't be listed
+    let err = pandoc.clone().execute_with(&mock).err().unwrap();
+    assert_eq!(err.kind(), ErrorKind::Io);
+
+    pandoc.set_output_format(OutputFormat::ChunkedHtml, Vec::new());
+    pandoc.set_output(OutputKind::File(dir.path().join("manual.zip")));
+    mock.push_output("PK");
+    pandoc.execute_with(&mock).unwrap();
+
+    let calls = mock.calls();
+    assert!(calls[0].args.iter().any(|arg| arg == "chunkedhtml"));
+    assert!(calls[1].output_file().unwrap().ends_with("manual.zip"));
+}
+
+#[cfg(feature = "test-util")]
+#[test]
+fn lua_writer() {
+    use pandoc::mock::MockExecutor;
+    use pandoc::{ErrorKind, InputKind, OutputFormat, OutputKind, PandocOption};
+
+    let dir = pandoc::TempDir::new().unwrap();
+    let writer = dir.path().join("words.lua");
+    std::fs::write(
+        &writer,
+        "function Writer(doc, opts)\n  return pandoc.write(doc, 'plain')\nend\n",
+    )
+    .unwrap();
+    let writer = writer.to_str().unwrap().to_string();
+    assert!(matches!(writer.parse(), Ok(OutputFormat::Lua(ref w)) if *w == writer));
+
+    let mock = MockExecutor::new();
+    let mut pandoc = pandoc::new();
+    pandoc.set_input(InputKind::Pipe("*hello*".to_string()));
+    pandoc.set_output(OutputKind::Pipe);
+    pandoc.set_output_format(OutputFormat::Lua(writer.clone()), Vec::new());
+    pandoc.clone().execute_with(&mock).unwrap();
+    let args = &mock.calls()[0].args;
+    let to = args.iter().position(|arg| arg == "-t").unwrap();
+    assert_eq!(args[to + 1], *writer);
+
+    pandoc.add_option(PandocOption::Standalone);
+    pandoc.add_option(PandocOption::Template(dir.path().join("words.lua")));
+    pandoc.clone().execute_with(&mock).unwrap();
+
+    // only paths are checked, pandoc looks up bare names in its data directory
+    let missing = dir.path().join("missing.lua").to_str().unwrap().to_string();
+    pandoc.set_output_format(OutputFormat::Lua(missing), Vec::new());
+    let err = pandoc.clone().execute_with(&mock).err().unwrap();
+    assert_eq!(err.kind(), ErrorKind::MissingInput);
+    pandoc.set_output_format(OutputFormat::Lua("data-dir.lua".to_string()), Vec::new());
+    pandoc.execute_with(&mock).unwrap();
+    let args = &mock.calls()[2].args;
+    assert!(args.iter().any(|arg| arg == "data-dir.lua"));
+}
+
+#[cfg(feature = "test-util")]
+#[test]
+fn lua_reader() {
+    use pandoc::mock::MockExecutor;
+    use pandoc::{ErrorKind, InputFormat, InputKind, MarkdownExtension, OutputKind};
+
+    let dir = pandoc::TempDir::new().unwrap();
+    let reader = dir.path().join("markup.lua");
+    let mock = MockExecutor::new();
+    let mut pandoc = pandoc::new();
+    pandoc.set_input(InputKind::Pipe("hello".to_string()));
+    pandoc.set_output(OutputKind::Pipe);
+    pandoc.set_input_format(
+        InputFormat::Lua(reader.clone()),
+        vec![MarkdownExtension::Smart],
+    );
+    let err = pandoc.clone().execute_with(&mock).err().unwrap();
+    assert_eq!(err.kind(), ErrorKind::MissingInput);
+
+    std::fs::write(
+        &reader,
+        "function Reader(input)\n  return pandoc.Pandoc({})\nend\n",
+    )
+    .unwrap();
+    pandoc.execute_with(&mock).unwrap();
+    let args = &mock.calls()[0].args;
+    let from = args.iter().position(|arg| arg == "-f").unwrap();
+    assert_eq!(
+        args[from + 1],
+        *format!("{}+smart", reader.display()).as_str()
+    );
+    assert!(matches!("x.lua".parse(), Ok(InputFormat::Lua(_))));
+}
+
+#[cfg(all(unix, feature = "metadata"))]
+#[test]
+fn extract_metadata() {
+    use pandoc::InputKind;
+
+    #[derive(serde::Deserialize)]
+    struct Meta {
+        title: Option<String>,
+        author: Vec<String>,
+    }
+
+    let dir = pandoc::TempDir::new().unwrap();
+    let script = "cat >/dev/null\nfor arg; do case \"$arg\" in --template=*) \
+                  echo '{\"title\":\"Report\",\"author\":[\"Ada\"]}'; exit;; esac; done\nexit 1\n";
+    common::fake_program(dir.path(), "pandoc", script);
+
+    let mut pandoc = pandoc::new();
+    pandoc.add_pandoc_path_hint(dir.path());
+    pandoc.set_input(InputKind::Pipe("% Report\n% Ada\n".to_string()));
+    let meta = pandoc.clone().extract_metadata().unwrap();
+    assert_eq!(meta["title"], "Report");
+    let meta: Meta = pandoc.extract_metadata_as().unwrap();
+    assert_eq!(meta.title.as_deref(), Some("Report"));
+    assert_eq!(meta.author, ["Ada"]);
+}
+
+#[cfg(feature = "ast")]
+#[test]
+fn heading_tree() {
+    let json = r#"{"pandoc-api-version":[1,23,1],"meta":{},"blocks":[
+        {"t":"Header","c":[1,["intro",[],[]],[{"t":"Str","c":"Intro"}]]},
+        {"t":"Header","c":[2,["first-steps",[],[]],
+            [{"t":"Emph","c":[{"t":"Str","c":"First"}]},{"t":"Space"},{"t":"Str","c":"steps"}]]},
+        {"t":"Div","c":[["",[],[]],[
+            {"t":"Header","c":[3,["details",[],[]],[{"t":"Str","c":"Details"}]]}]]},
+        {"t":"Header","c":[2,["hidden",["unlisted"],[]],[{"t":"Str","c":"Hidden"}]]},
+        {"t":"Header","c":[1,["usage",[],[]],[{"t":"Str","c":"Usage"}]]}]}"#;
+    let tree = pandoc::heading_tree(&pandoc::pandoc_ast::Pandoc::from_json(json));
+    assert_eq!(tree.len(), 2);
+    assert_eq!(tree[0].identifier, "intro");
+    assert_eq!(tree[0].children.len(), 1);
+    assert_eq!(tree[0].children[0].text, "First steps");
+    assert_eq!(tree[0].children[0].children[0].identifier, "details");
+    assert_eq!(tree[1].text, "Usage");
+    assert!(tree[1].children.is_empty());
+}
+
+#[cfg(feature = "ast")]
+#[test]
+fn document_stats() {
+    use std::time::Duration;
+
+    let json = r#"{"pandoc-api-version":[1,23,1],"meta":{},"blocks":[
+        {"t":"Header","c":[1,["intro",[],[]],[{"t":"Str","c":"Intro"}]]},
+        {"t":"Para","c":[{"t":"Str","c":"Two"},{"t":"Space"},
+            {"t":"Strong","c":[{"t":"Str","c":"words."}]},
+            {"t":"Note","c":[{"t":"Para","c":[{"t":"Str","c":"Note"}]}]}]},
+        {"t":"CodeBlock","c":[["",[],[]],"let ignored = true;"]},
+        {"t":"BulletList","c":[[{"t":"Plain","c":[{"t":"Str","c":"item"}]}]]}]}"#;
+    let stats = pandoc::document_stats(&pandoc::pandoc_ast::Pandoc::from_json(json));
+    assert_eq!(stats.words, 5);
+    assert_eq!(stats.characters, 22);
+    assert_eq!(stats.headings, 1);
+    assert_eq!(stats.code_blocks, 1);
+    assert_eq!(stats.tables, 0);
+    assert_eq!(stats.reading_time_at(60), Duration::from_secs(5));
+}
+
+#[cfg(feature = "ast")]
+#[test]
+fn link_inventory() {
+    use pandoc::LinkKind;
+
+    let json = r#"{"pandoc-api-version":[1,23,1],"meta":{},"blocks":[
+        {"t":"Para","c":[
+            {"t":"Link","c":[["",[],[["data-pos","doc.md@1:1-1:20"]]],
+                [{"t":"Str","c":"docs"}],["https://docs.rs",""]]},
+            {"t":"Span","c":[["",[],[["data-pos","2:3-2:30"]]],[
+                {"t":"Image","c":[["",[],[]],[{"t":"Str","c":"logo"}],["logo.png","Logo"]]}]]}]}]}"#;
+    let links = pandoc::link_inventory(pandoc::pandoc_ast::Pandoc::from_json(json));
+    assert_eq!(links.len(), 2);
+    assert_eq!(links[0].kind, LinkKind::Link);
+    assert_eq!(links[0].target, "https://docs.rs");
+    let position = links[0].position.as_ref().unwrap();
+    assert_eq!(position.file.as_deref(), Some("doc.md"));
+    assert_eq!((position.start_line, position.end_column), (1, 20));
+    assert_eq!(links[1].kind, LinkKind::Image);
+    assert_eq!(links[1].text, "logo");
+    assert_eq!(links[1].title, "Logo");
+    assert_eq!(links[1].position.as_ref().unwrap().start_line, 2);
+}
+
+#[cfg(feature = "ast")]
+#[test]
+fn document_diff() {
+    use pandoc::diff::{diff_documents, Change};
+    use pandoc::pandoc_ast::Pandoc;
+
+    let document = |blocks: &str| {
+        Pandoc::from_json(&format!(
+            r#"{{"pandoc-api-version":[1,23,1],"meta":{{}},"blocks":[{}]}}"#,
+            blocks
+        ))
+    };
+    let heading = |id: &str| {
+        format!(
+            r#"{{"t":"Header","c":[1,["{}",[],[]],[{{"t":"Str","c":"{}"}}]]}}"#,
+            id, id
+        )
+    };
+    let para = |text: &str| format!(r#"{{"t":"Para","c":[{{"t":"Str","c":"{}"}}]}}"#, text);
+
+    let old = document(
+        &[
+            heading("a"),
+            para("first"),
+            heading("b"),
+            para("second"),
+            heading("c"),
+            para("third"),
+        ]
+        .join(","),
+    );
+    let new = document(
+        &[
+            heading("a"),
+            para("first, edited"),
+            para("added"),
+            heading("c"),
+            para("third"),
+            heading("b"),
+            para("second"),
+        ]
+        .join(","),
+    );
+    assert!(diff_documents(&old, &old).is_empty());
+    let diff = diff_documents(&old, &new);
+    assert!(matches!(diff.changes[0], Change::Unchanged(_)));
+    assert!(matches!(diff.changes[1], Change::Modified { .. }));
+    assert!(matches!(diff.changes[2], Change::Inserted(_)));
+    let moved: Vec<&str> = diff
+        .changes
+        .iter()
+        .filter_map(|change| match *change {
+            Change::Moved { ref heading, .. } => Some(heading.as_str()),
+            _ => None,
+        })
+        .collect();
+    assert_eq!(moved.len(), 1);
+    assert!(diff.to_document().to_json().contains("<ins>"));
+}
+
+#[cfg(all(unix, feature = "ast"))]
+#[test]
+fn split_by_heading() {
+    use pandoc::{InputKind, OutputFormat, OutputKind};
+
+    let json = r#"{"pandoc-api-version":[1,23,1],"meta":{},"blocks":[
+        {"t":"Para","c":[{"t":"Str","c":"preface"}]},
+        {"t":"Header","c":[1,["setup",[],[]],[{"t":"Str","c":"Setup"}]]},
+        {"t":"Header","c":[2,["details",[],[]],[{"t":"Str","c":"Details"}]]},
+        {"t":"Header","c":[1,["",[],[]],[{"t":"Str","c":"Next"},{"t":"Space"},{"t":"Str","c":"Steps"}]]},
+        {"t":"Header","c":[1,["setup",[],[]],[{"t":"Str","c":"Setup"}]]}]}"#;
+    let dir = pandoc::TempDir::new().unwrap();
+    // prints the document when writing to stdout, otherwise copies the input to the output
+    let script = format!(
+        "while [ $# -gt 0 ]; do [ \"$1\" = -o ] && out=$2; shift; done\n\
+         if [ \"${{out:--}}\" = - ]; then cat >/dev/null; echo '{}'; else cat > \"$out\"; fi\n",
+        json.replace('\n', "")
+    );
+    common::fake_program(dir.path(), "pandoc", &script);
+
+    let out = dir.path().join("kb");
+    let mut pandoc = pandoc::new();
+    pandoc.add_pandoc_path_hint(dir.path());
+    pandoc.set_input(InputKind::Pipe(String::new()));
+    pandoc.set_output(OutputKind::Directory(out.clone()));
+    pandoc.set_output_format(OutputFormat::Markdown, Vec::new());
+    let files = pandoc.split_by_heading(1).unwrap();
+    let names: Vec<_> = files
+        .iter()
+        .map(|file| file.strip_prefix(&out).unwrap().to_str().unwrap())
+        .collect();
+    assert_eq!(
+        names,
+        ["index.md", "setup.md", "next-steps.md", "setup-1.md"]
+    );
+    let setup = std::fs::read_to_string(&files[1]).unwrap();
+    assert!(setup.contains("details"));
+    assert!(!setup.contains("preface"));
+}
+
+#[cfg(all(feature = "ast", feature = "test-util"))]
+#[test]
+fn document_builder() {
+    use pandoc::doc::{self, DocumentBuilder};
+    use pandoc::mock::MockExecutor;
+    use pandoc::pandoc_ast::{Block, Inline};
+
+    let mut builder = DocumentBuilder::new();
+    builder
+        .meta("title", "Report")
+        .heading(1, "Sales *2024*")
+        .paragraph(vec![doc::strong("Total:"), Inline::Space, doc::code("<5>")])
+        .bullet_list(["one", "two"])
+        .table(&["Region", "Revenue"], &[["North", "120"]]);
+    let document = builder.build();
+    match document.blocks[0] {
+        Block::Header(1, _, ref inlines) => assert_eq!(inlines.len(), 3),
+        ref other => panic!("expected a heading, got {:?}", other),
+    }
+    let parsed = pandoc::pandoc_ast::Pandoc::from_json(&document.to_json());
+    assert_eq!(parsed, document);
+
+    let mock = MockExecutor::new();
+    mock.push_output("");
+    let mut pandoc = pandoc::new();
+    pandoc.set_input_document(&document);
+    pandoc.set_output(pandoc::OutputKind::Pipe);
+    pandoc.execute_with(&mock).unwrap();
+    let call = &mock.calls()[0];
+    assert!(call.args.iter().any(|arg| arg == "json"));
+    assert_eq!(call.stdin.as_deref(), Some(document.to_json().as_bytes()));
+}
+
+#[cfg(feature = "ast")]
+#[test]
+fn cited_keys() {
+    let json = r#"{"pandoc-api-version":[1,23,1],"meta":{},"blocks":[
+        {"t":"Para","c":[{"t":"Cite","c":[[
+            {"citationId":"knuth84","citationPrefix":[],"citationSuffix":[],
+             "citationMode":{"t":"NormalCitation"},"citationNoteNum":1,"citationHash":0},
+            {"citationId":"lamport94","citationPrefix":[],"citationSuffix":[],
+             "citationMode":{"t":"AuthorInText"},"citationNoteNum":1,"citationHash":0}],
+            [{"t":"Str","c":"[@knuth84;"},{"t":"Space"},{"t":"Str","c":"@lamport94]"}]]}]}]}"#;
+    let keys = pandoc::cited_keys(&pandoc::pandoc_ast::Pandoc::from_json(json));
+    assert_eq!(
+        keys.into_iter().collect::<Vec<_>>(),
+        ["knuth84", "lamport94"]
+    );
+}
+
+#[cfg(all(unix, feature = "test-util"))]
+#[test]
+fn enable_crossref() {
+    use pandoc::mock::MockExecutor;
+    use pandoc::{CitationConfig, CrossrefConfig, ErrorKind, InputKind, OutputKind};
+
+    let dir = pandoc::TempDir::new().unwrap();
+    let mut pandoc = pandoc::new();
+    pandoc.disable_builtin_path_hints();
+    pandoc.add_pandoc_path_hint(dir.path());
+    let err = pandoc
+        .clone()
+        .enable_crossref(CrossrefConfig::default())
+        .err()
+        .unwrap();
+    assert_eq!(err.kind(), ErrorKind::Unsupported);
+
+    let filter = dir.path().join("pandoc-crossref");
+    std::fs::write(&filter, "").unwrap();
+    pandoc::clear_path_cache();
+    pandoc.set_input(InputKind::Pipe(String::new()));
+    pandoc.set_output(OutputKind::Pipe);
+    pandoc.set_citeproc(CitationConfig::default()).unwrap();
+    pandoc
+        .enable_crossref(CrossrefConfig {
+            figure_prefix: Some("Fig.".to_string()),
+            chapters: true,
+            ..CrossrefConfig::default()
+        })
+        .unwrap();
+    let mock = MockExecutor::new();
+    mock.push_output("");
+    pandoc.execute_with(&mock).unwrap();
+    let args: Vec<String> = mock.calls()[0]
+        .args
+        .iter()
+        .map(|arg| arg.to_string_lossy().into_owned())
+        .collect();
+    let position = |arg: &str| args.iter().position(|a| a.as_str() == arg);
+    let filter = format!("--filter={}", filter.display());
+    assert!(position(&filter).unwrap() < position("--citeproc").unwrap());
+    assert!(args.iter().any(|arg| arg == "figPrefix:Fig."));
+    assert!(args.iter().any(|arg| arg == "chapters:true"));
+}
+
+#[cfg(all(feature = "fetch", feature = "test-util"))]
+#[test]
+fn csl_bibliography() {
+    use pandoc::mock::MockExecutor;
+    use pandoc::{InputKind, OutputKind};
+
+    let entry = serde_json::json!({ "id": "knuth84", "type": "book", "title": "The TeXbook" });
+    let mut pandoc = pandoc::new();
+    pandoc.set_input(InputKind::Pipe(String::new()));
+    pandoc.set_output(OutputKind::Pipe);
+    pandoc
+        .add_csl_bibliography(std::slice::from_ref(&entry))
+        .unwrap();
+    let mock = MockExecutor::new();
+    mock.push_output("");
+    pandoc.clone().execute_with(&mock).unwrap();
+    let file = mock.calls()[0]
+        .args
+        .iter()
+        .find_map(|arg| arg.to_str()?.strip_prefix("--bibliography="))
+        .map(std::path::PathBuf::from)
+        .unwrap();
+    let written: serde_json::Value =
+        serde_json::from_slice(&std::fs::read(&file).unwrap()).unwrap();
+    assert_eq!(written, serde_json::json!([entry]));
+    drop(pandoc);
+    assert!(!file.exists());
+}
+
+#[cfg(feature = "fetch")]
+#[test]
+fn doi_url() {
+    use pandoc::bib::fetch::doi_url;
+
+    assert_eq!(
+        doi_url("10.1145/3290366"),
+        "https://doi.org/10.1145/3290366"
+    );
+    assert_eq!(
+        doi_url("10.1002/(SICI)1097-4571#?%"),
+        "https://doi.org/10.1002/%28SICI%291097-4571%23%3F%25"
+    );
+}
+
+#[cfg(feature = "fetch")]
+#[test]
+fn cached_csl_style() {
+    use pandoc::bib::fetch::csl_style;
+    use pandoc::ErrorKind;
+
+    let cache = pandoc::TempDir::new().unwrap();
+    let err = csl_style("../ieee", cache.path()).err().unwrap();
+    assert_eq!(err.kind(), ErrorKind::InvalidConfig);
+
+    // cached styles are used without a download
+    let cached = cache.path().join("ieee.csl");
+    std::fs::write(&cached, "<style/>").unwrap();
+    assert_eq!(csl_style("ieee", cache.path()).unwrap(), cached);
+}
+
+#[cfg(feature = "test-util")]
+#[test]
+fn header_includes() {
+    use pandoc::mock::MockExecutor;
+    use pandoc::{HeaderIncludes, InputKind, OutputFormat, OutputKind};
+
+    let mut preamble = HeaderIncludes::latex();
+    preamble
+        .use_package("geometry", &["margin=2cm"])
+        .raw("\\setlength{\\parskip}{1ex}");
+    let mut more = HeaderIncludes::latex();
+    more.use_package("microtype", &[]);
+
+    let mut pandoc = pandoc::new();
+    pandoc.set_input(InputKind::Pipe(String::new()));
+    pandoc.set_output(OutputKind::Pipe);
+    pandoc.set_output_format(OutputFormat::Latex, Vec::new());
+    pandoc.add_header_includes(&preamble).unwrap();
+    pandoc.add_header_includes(&more).unwrap();
+    assert!(pandoc.add_header_includes(&HeaderIncludes::html()).is_err());
+
+    let mock = MockExecutor::new();
+    mock.push_output("");
+    pandoc.clone().execute_with(&mock).unwrap();
+    let headers: Vec<String> = mock.calls()[0]
+        .args
+        .iter()
+        .filter_map(|arg| arg.to_str()?.strip_prefix("--include-in-header="))
+        .map(|file| std::fs::read_to_string(file).unwrap())
+        .collect();
+    assert_eq!(
+        headers,
+        [
+            "\\usepackage[margin=2cm]{geometry}\n\\setlength{\\parskip}{1ex}\n",
+            "\\usepackage{microtype}\n",
+        ]
+    );
+}
+
+#[cfg(feature = "test-util")]
+#[test]
+fn document_class() {
+    use pandoc::mock::MockExecutor;
+    use pandoc::{DocumentClass, InputKind, OutputKind};
+
+    let mock = MockExecutor::new();
+    mock.push_output("");
+    let mut pandoc = pandoc::new();
+    pandoc.set_input(InputKind::Pipe(String::new()));
+    pandoc.set_output(OutputKind::Pipe);
+    pandoc
+        .set_doc_class(DocumentClass::ScrReport)
+        .add_class_option("11pt")
+        .add_class_option("parskip=half");
+    pandoc.execute_with(&mock).unwrap();
+
+    let args = &mock.calls()[0].args;
+    let vars: Vec<_> = args
+        .windows(2)
+        .filter(|pair| pair[0] == "-V")
+        .map(|pair| pair[1].to_string_lossy().into_owned())
+        .collect();
+    assert_eq!(
+        vars,
+        [
+            "documentclass:scrreprt",
+            "classoption:11pt",
+            "classoption:parskip=half",
+        ]
+    );
+    assert_eq!(
+        DocumentClass::Custom("tufte-book".into()).to_string(),
+        "tufte-book"
+    );
+}
+
+#[cfg(feature = "test-util")]
+#[test]
+fn fonts() {
+    use pandoc::mock::MockExecutor;
+    use pandoc::{Fonts, InputKind, OutputKind, PandocOption};
+
+    let fonts = Fonts {
+        main: Some("Noto Serif".to_string()),
+        mono: Some("Fira Code".to_string()),
+        size: Some("11pt".to_string()),
+        ..Fonts::default()
+    };
+    let mut pandoc = pandoc::new();
+    pandoc.set_input(InputKind::Pipe(String::new()));
+    pandoc.set_output(OutputKind::Pipe);
+    assert!(pandoc.set_fonts(fonts.clone()).is_err());
+    // the size alone works with pdflatex
+    pandoc
+        .set_fonts(Fonts {
+            size: Some("12pt".to_string()),
+            ..Fonts::default()
+        })
+        .unwrap();
+    pandoc.add_option(PandocOption::PdfEngine("xelatex".into()));
+    pandoc.set_fonts(fonts).unwrap();
+
+    let mock = MockExecutor::new();
+    mock.push_output("");
+    pandoc.execute_with(&mock).unwrap();
+    let vars: Vec<_> = mock.calls()[0]
+        .args
+        .windows(2)
+        .filter(|pair| pair[0] == "-V")
+        .map(|pair| pair[1].to_string_lossy().into_owned())
+        .collect();
+    assert_eq!(
+        vars,
+        ["mainfont:Noto Serif", "monofont:Fira Code", "fontsize:11pt"]
+    );
+}
+
+#[cfg(feature = "test-util")]
+#[test]
+fn html_config() {
+    use pandoc::mock::MockExecutor;
+    use pandoc::{HtmlConfig, InputKind, OutputFormat, OutputKind};
+
+    let footer = pandoc::TempPath::new(".html").unwrap();
+    let config = HtmlConfig {
+        stylesheets: vec!["base.css".to_string(), "print.css".to_string()],
+        toc_title: Some("Contents".to_string()),
+        lang: Some("en-GB".to_string()),
+        document_css: Some(false),
+        meta_tags: vec![("description".to_string(), "A \"quoted\" report".to_string())],
+        after_body: vec![footer.path().to_owned()],
+        ..HtmlConfig::default()
+    };
+    let mut pandoc = pandoc::new();
+    pandoc.set_input(InputKind::Pipe(String::new()));
+    pandoc.set_output(OutputKind::Pipe);
+    pandoc.set_output_format(OutputFormat::Latex, Vec::new());
+    assert!(pandoc.set_html_config(config.clone()).is_err());
+    pandoc.set_output_format(OutputFormat::Html5, Vec::new());
+    pandoc.set_html_config(config).unwrap();
+
+    let mock = MockExecutor::new();
+    mock.push_output("");
+    pandoc.clone().execute_with(&mock).unwrap();
+    let args: Vec<String> = mock.calls()[0]
+        .args
+        .iter()
+        .map(|arg| arg.to_string_lossy().into_owned())
+        .collect();
+    let head = args
+        .iter()
+        .find_map(|arg| arg.strip_prefix("--include-in-header="))
+        .unwrap();
+    assert_eq!(
+        std::fs::read_to_string(head).unwrap(),
+        "<meta name=\"description\" content=\"A &quot;quoted&quot; report\" />\n"
+    );
+    let position = |arg: &str| args.iter().position(|a| a == arg).unwrap();
+    assert!(position("--css=base.css") < position("--css=print.css"));
+    for arg in [
+        "toc-title:Contents",
+        "lang:en-GB",
+        "document-css:false",
+        &format!("--include-after-body={}", footer.path().display()),
+    ] {
+        position(arg);
+    }
+}
+
+#[test]
+fn debug_builder() {
+    let mut pandoc = pandoc::new();
+    pandoc
+        .add_input("report.md")
+        .add_option(pandoc::PandocOption::Standalone)
+        .add_filter(|json| json);
+    let debug = format!("{:?}", pandoc);
+    assert!(debug.contains("report.md"), "{}", debug);
+    assert!(debug.contains("Standalone"), "{}", debug);
+    assert!(debug.contains("filters: 1"), "{}", debug);
+}
+
+#[test]
+fn builder_is_send_and_sync() {
+    fn assert_send_sync<T: Send + Sync>(_: &T) {}
+
+    let mut pandoc = pandoc::new();
+    pandoc
+        .add_filter(|json| json)
+        .add_input_preprocessor(|text| text)
+        .set_cmdline_callback(|_| ());
+    assert_send_sync(&pandoc);
+    let shared = std::sync::Arc::new(pandoc);
+    let clone = std::thread::spawn(move || (*shared).clone())
+        .join()
+        .unwrap();
+    assert_send_sync(&clone);
+}
+
+#[test]
+fn configuration_equality() {
+    use std::hash::{Hash, Hasher};
+
+    let hash = |pandoc: &pandoc::Pandoc| {
+        let mut hasher = std::collections::hash_map::DefaultHasher::new();
+        pandoc.hash(&mut hasher);
+        hasher.finish()
+    };
+    let mut pandoc = pandoc::new();
+    pandoc
+        .add_input("report.md")
+        .add_option(pandoc::PandocOption::Standalone)
+        .add_filter(|json| json);
+    let clone = pandoc.clone();
+    assert_eq!(pandoc, clone);
+    assert_eq!(hash(&pandoc), hash(&clone));
+
+    let mut other_filter = pandoc::new();
+    other_filter
+        .add_input("report.md")
+        .add_option(pandoc::PandocOption::Standalone)
+        .add_filter(|json| json);
+    assert_ne!(pandoc, other_filter);
+
+    let mut toc = clone.clone();
+    toc.set_toc();
+    assert_ne!(pandoc, toc);
+    assert_ne!(hash(&pandoc), hash(&toc));
+}
+
+#[cfg(feature = "test-util")]
+#[test]
+fn prepared_command() {
+    use pandoc::mock::MockExecutor;
+    use pandoc::{InputFormat, OutputFormat, OutputKind, PandocOutput};
+
+    let mut pandoc = pandoc::new();
+    pandoc.set_input_format(InputFormat::Markdown, Vec::new());
+    pandoc.set_output_format(OutputFormat::Html5, Vec::new());
+    pandoc.add_filter(|json| json.replace("draft", "final"));
+    let mut to_file = pandoc.clone();
+    to_file.set_output(OutputKind::File("out.html".into()));
+    assert!(to_file.freeze().is_err());
+    let prepared = pandoc.freeze().unwrap();
+
+    let mock = MockExecutor::new();
+    for document in ["one", "two"] {
+        mock.push_output(format!(r#"{{"text":"draft {}"}}"#, document));
+        mock.push_output(format!("<p>{}</p>", document));
+    }
+    for document in ["one", "two"] {
+        match prepared.execute_with(document, &mock).unwrap() {
+            PandocOutput::ToBuffer(html) => assert_eq!(html, format!("<p>{}</p>", document)),
+            _ => panic!("expected a buffer"),
+        }
+    }
+
+    let calls = mock.calls();
+    assert_eq!(calls.len(), 4);
+    assert_eq!(calls[0].stdin.as_deref(), Some(&b"one"[..]));
+    assert_eq!(
+        calls[1].stdin.as_deref(),
+        Some(&br#"{"text":"final one"}"#[..])
+    );
+    assert_eq!(calls[2].stdin.as_deref(), Some(&b"two"[..]));
+    assert_eq!(calls[1].args, calls[3].args);
+    assert!(calls[1]
+        .args
+        .windows(2)
+        .any(|pair| pair[0] == "-f" && pair[1] == "json"));
+    assert_eq!(&prepared.command_line()[1..], &calls[3].args[..]);
+}
+
+#[cfg(feature = "test-util")]
+#[test]
+fn missing_output() {
+    use pandoc::mock::MockExecutor;
+    use pandoc::{InputKind, OutputKind, PandocError, PandocOutput};
+
+    let dir = pandoc::TempDir::new().unwrap();
+    let file = dir.path().join("out.html");
+    let mock = MockExecutor::new();
+    mock.push_output("");
+    mock.push_output("<p>converted</p>");
+    let mut pandoc = pandoc::new();
+    pandoc.set_input(InputKind::Pipe("converted".to_string()));
+    pandoc.set_output(OutputKind::File(file.clone()));
+    match pandoc.clone().execute_with(&mock) {
+        Err(PandocError::MissingOutput(path)) => assert_eq!(path, file),
+        _ => panic!("expected the empty output file to be reported"),
+    }
+    match pandoc.execute_with(&mock).unwrap() {
+        PandocOutput::ToFile(path) => assert_eq!(path, file),
+        _ => panic!("expected a file"),
+    }
+}
+
+#[cfg(unix)]
+#[test]
+fn stale_output() {
+    use pandoc::{InputKind, OutputKind, PandocError, PandocOutput};
+
+    let dir = pandoc::TempDir::new().unwrap();
+    let file = dir.path().join("out.html");
+    std::fs::write(&file, "<p>earlier run</p>").unwrap();
+    // succeeds without writing the output
+    common::fake_program(dir.path(), "pandoc", "cat >/dev/null\n");
+    let mut pandoc = pandoc::new();
+    pandoc.add_pandoc_path_hint(dir.path());
+    pandoc.set_input(InputKind::Pipe("converted".to_string()));
+    pandoc.set_output(OutputKind::File(file.clone()));
+    match pandoc.clone().execute() {
+        Err(PandocError::MissingOutput(path)) => assert_eq!(path, file),
+        _ => panic!("expected the output of the earlier run to be reported"),
+    }
+
+    common::fake_program(
+        dir.path(),
+        "pandoc",
+        "while [ $# -gt 0 ]; do [ \"$1\" = -o ] && out=$2; shift; done\ncat >\"$out\"\n",
+    );
+    match pandoc.execute().unwrap() {
+        PandocOutput::ToFile(path) => {
+            assert_eq!(std::fs::read_to_string(path).unwrap(), "converted")
+        }
+        _ => panic!("expected a file"),
+    }
+}
+
+#[cfg(feature = "test-util")]
+#[test]
+fn lossy_utf8() {
+    use pandoc::mock::MockExecutor;
+    use pandoc::{ErrorKind, InputKind, OutputKind, PandocError, PandocOutput};
+
+    let mock = MockExecutor::new();
+    mock.push_output(&b"caf\xe9"[..]);
+    mock.push_output(&b"caf\xe9"[..]);
+    let mut pandoc = pandoc::new();
+    pandoc.set_input(InputKind::Pipe("café".to_string()));
+    pandoc.set_output(OutputKind::Pipe);
+    match pandoc.clone().execute_with(&mock) {
+        Err(PandocError::BadUtf8Output(err)) => {
+            assert_eq!(err.utf8_error().valid_up_to(), 3);
+            assert_eq!(err.as_bytes(), b"caf\xe9");
+        }
+        _ => panic!("expected the invalid output to be rejected"),
+    }
+    pandoc.set_lossy_utf8(true);
+    match pandoc.execute_with(&mock).unwrap() {
+        PandocOutput::ToBuffer(text) => assert_eq!(text, "caf\u{fffd}"),
+        _ => panic!("expected a buffer"),
+    }
+
+    let bytes = b"caf\xe9".to_vec();
+    let err = PandocError::from(std::str::from_utf8(&bytes).unwrap_err());
+    assert!(matches!(err, PandocError::BadUtf8Conversion(3)));
+    let err = PandocError::from(String::from_utf8(bytes).unwrap_err());
+    assert_eq!(err.kind(), ErrorKind::BadUtf8Conversion);
+    assert!(std::error::Error::source(&err).is_some());
+}
+
+#[cfg(all(feature = "bytes", feature = "test-util"))]
+#[test]
+fn bytes_output() {
+    use pandoc::mock::MockExecutor;
+    use pandoc::{InputKind, OutputFormat, OutputKind};
+
+    let mock = MockExecutor::new();
+    mock.push_output(&b"PK\x03\x04\xff\xfe"[..]);
+    let mut pandoc = pandoc::new();
+    pandoc.set_input(InputKind::Pipe("binary".to_string()));
+    pandoc.set_output(OutputKind::File("ignored.docx".into()));
+    pandoc.set_output_format(OutputFormat::Docx, Vec::new());
+    let bytes = pandoc.execute_to_bytes_with(&mock).unwrap();
+    assert_eq!(&bytes[..], b"PK\x03\x04\xff\xfe");
+    assert!(mock.calls()[0].args.iter().all(|arg| arg != "ignored.docx"));
+}
+
+#[cfg(feature = "test-util")]
+#[test]
+fn temp_file_output() {
+    use pandoc::mock::MockExecutor;
+    use pandoc::{InputKind, OutputKind, PandocOutput};
+
+    let mock = MockExecutor::new();
+    mock.push_output("<p>temporary</p>");
+    let mut pandoc = pandoc::new();
+    pandoc.set_input(InputKind::Pipe("temporary".to_string()));
+    pandoc.set_output(OutputKind::TempFile(".html".to_string()));
+    let temp = match pandoc.execute_with(&mock).unwrap() {
+        PandocOutput::ToTempFile(temp) => temp,
+        _ => panic!("expected a temporary file"),
+    };
+    let path = temp.path().to_owned();
+    assert_eq!(path.extension(), Some("html".as_ref()));
+    assert_eq!(mock.calls()[0].output_file(), Some(path.clone()));
+    assert_eq!(std::fs::read_to_string(&path).unwrap(), "<p>temporary</p>");
+    drop(temp);
+    assert!(!path.exists());
+}
+
+#[test]
+fn binary_output_formats() {
+    use pandoc::OutputFormat;
+
+    for format in &[
+        OutputFormat::Docx,
+        OutputFormat::Epub,
+        OutputFormat::Odt,
+        OutputFormat::Pdf,
+        OutputFormat::Pptx,
+        OutputFormat::Other("docx+styles".to_string()),
+    ] {
+        assert!(format.is_binary(), "{:?} is binary", format);
+    }
+    for format in &[
+        OutputFormat::Html,
+        OutputFormat::Markdown,
+        OutputFormat::Latex,
+        OutputFormat::Plain,
+        OutputFormat::Other("gfm".to_string()),
+    ] {
+        assert!(!format.is_binary(), "{:?} is text", format);
+    }
+}
+
+#[cfg(unix)]
+#[test]
+fn execution_report() {
+    use pandoc::{InputKind, OutputFormat, OutputKind, PandocOutput, WarningClass};
+
+    let dir = pandoc::TempDir::new().unwrap();
+    let script = "cat >/dev/null\n\
+                  echo '[WARNING] Duplicate identifier '\"'intro'\"' at line 3' >&2\n\
+                  echo '<p>reported</p>'\n";
+    let program = common::fake_program(dir.path(), "pandoc", script);
+
+    let mut pandoc = pandoc::new();
+    pandoc.add_pandoc_path_hint(dir.path());
+    pandoc.set_input(InputKind::Pipe("reported".to_string()));
+    pandoc.set_output(OutputKind::Pipe);
+    pandoc.set_output_format(OutputFormat::Html, Vec::new());
+    let (output, report) = pandoc.execute_with_report().unwrap();
+    match output {
+        PandocOutput::ToBuffer(html) => assert_eq!(html, "<p>reported</p>\n"),
+        _ => panic!("expected a buffer"),
+    }
+    assert!(report.status.success());
+    assert_eq!(report.args[0], program.as_os_str());
+    assert!(report
+        .args
+        .windows(2)
+        .any(|pair| pair[0] == "-t" && pair[1] == "html"));
+    let warnings = pandoc::parse_warnings(&report.stderr);
+    assert_eq!(warnings.len(), 1);
+    assert_eq!(warnings[0].class, WarningClass::DuplicateIdentifier);
+}
+
+#[cfg(feature = "test-util")]
+#[test]
+fn output_targets() {
+    use pandoc::mock::MockExecutor;
+    use pandoc::{InputKind, OutputFormat, OutputKind, PandocOutput};
+
+    let mock = MockExecutor::new();
+    mock.push_output(r#"{"blocks":[]}"#);
+    mock.push_output("<p>html</p>");
+    mock.push_output("\\section{latex}");
+    let mut pandoc = pandoc::new();
+    pandoc.set_input(InputKind::Pipe("# targets".to_string()));
+    pandoc.add_output_target(OutputFormat::Html, OutputKind::Pipe);
+    pandoc.add_output_target(OutputFormat::Latex, OutputKind::Pipe);
+    let outputs = pandoc.execute_targets_with(&mock).unwrap();
+    let buffers: Vec<_> = outputs
+        .into_iter()
+        .map(|output| match output {
+            PandocOutput::ToBuffer(text) => text,
+            _ => panic!("expected a buffer"),
+        })
+        .collect();
+    assert_eq!(buffers, vec!["<p>html</p>", "\\section{latex}"]);
+
+    let calls = mock.calls();
+    assert_eq!(calls.len(), 3);
+    assert_eq!(calls[0].stdin.as_deref(), Some(&b"# targets"[..]));
+    let writes = |call: &pandoc::mock::RecordedCall, format: &str| {
+        call.args
+            .windows(2)
+            .any(|pair| pair[0] == "-t" && pair[1] == format)
+    };
+    assert!(writes(&calls[0], "json"));
+    assert!(writes(&calls[1], "html"));
+    assert!(writes(&calls[2], "latex"));
+    assert_eq!(calls[1].stdin.as_deref(), Some(&br#"{"blocks":[]}"#[..]));
+    assert_eq!(calls[2].stdin, calls[1].stdin);
+}
+
+#[cfg(feature = "test-util")]
+#[test]
+fn output_targets_options() {
+    use pandoc::mock::MockExecutor;
+    use pandoc::{InputFormat, OutputFormat, OutputKind, PandocOption, TrackChanges};
+
+    let dir = pandoc::TempDir::new().unwrap();
+    let input = dir.path().join("report.docx");
+    std::fs::write(&input, "docx").unwrap();
+    let meta = dir.path().join("meta.yaml");
+    std::fs::write(&meta, "title: Report").unwrap();
+    let mut pandoc = pandoc::new();
+    pandoc.add_input(&input);
+    pandoc.set_input_format(InputFormat::Docx, Vec::new());
+    pandoc.add_options(&[
+        PandocOption::ShiftHeadingLevelBy(1),
+        PandocOption::TrackChanges(TrackChanges::Accept),
+        PandocOption::TabStop(2),
+        PandocOption::DataDir(dir.path().to_owned()),
+        PandocOption::MetadataFile(meta),
+        PandocOption::Standalone,
+    ]);
+    let options = |call: &pandoc::mock::RecordedCall| -> Vec<String> {
+        call.args
+            .iter()
+            .map(|arg| arg.to_string_lossy().into_owned())
+            .filter(|arg| arg.starts_with("--"))
+            .collect()
+    };
+
+    let single = MockExecutor::new();
+    let mut one = pandoc.clone();
+    one.add_output_target(OutputFormat::Html, OutputKind::Pipe);
+    one.execute_targets_with(&single).unwrap();
+    let expected = options(&single.calls()[0]);
+
+    let split = MockExecutor::new();
+    split.push_output(r#"{"blocks":[]}"#);
+    pandoc.add_output_target(OutputFormat::Html, OutputKind::Pipe);
+    pandoc.add_output_target(OutputFormat::Latex, OutputKind::Pipe);
+    pandoc.execute_targets_with(&split).unwrap();
+    let calls = split.calls();
+    assert_eq!(calls.len(), 3);
+    let (reader, writer) = (options(&calls[0]), options(&calls[1]));
+    for option in &expected {
+        assert!(
+            reader.contains(option) || writer.contains(option),
+            "{} is missing",
+            option
+        );
+    }
+    // the heading shift is applied once, the data directory is used by both passes
+    assert!(reader.contains(&"--shift-heading-level-by=1".to_string()));
+    assert!(!writer
+        .iter()
+        .any(|arg| arg.starts_with("--shift-heading-level-by")));
+    assert!(writer.iter().any(|arg| arg.starts_with("--data-dir=")));
+    assert!(writer.contains(&"--standalone".to_string()));
+    // the images of the docx are kept for the writers until all targets are written
+    let media = reader
+        .iter()
+        .find_map(|arg| arg.strip_prefix("--extract-media="))
+        .unwrap();
+    assert!(!std::path::Path::new(media).exists());
+}
+
+#[cfg(feature = "test-util")]
+#[test]
+fn book_chapters() {
+    use pandoc::book::{Book, Chapter};
+    use pandoc::mock::MockExecutor;
+    use pandoc::PandocOption;
+
+    let dir = pandoc::TempDir::new().unwrap();
+    let intro = dir.path().join("intro.md");
+    std::fs::write(&intro, "intro").unwrap();
+    let usage = dir.path().join("usage.md");
+    std::fs::write(&usage, "usage").unwrap();
+    let mut book = Book::new("The Manual");
+    book.add_chapter(
+        Chapter::new(&intro)
+            .set_title("Introduction")
+            .set_id("intro"),
+    )
+    .add_chapter(
+        Chapter::new(&usage)
+            .set_shift_heading_level_by(1)
+            .add_metadata("lang", "en"),
+    );
+    let mut template = pandoc::new();
+    template.add_option(PandocOption::DataDir(dir.path().to_owned()));
+    template.add_option(PandocOption::PdfEngine("xelatex".into()));
+    let mock = MockExecutor::new();
+    mock.push_output("{}").push_output("{}").push_output("{}");
+    let output = dir.path().join("manual.pdf");
+    let pandoc = book.build_with(&template, &mock, &output).unwrap();
+
+    let calls = mock.calls();
+    assert_eq!(calls.len(), 3);
+    let has = |call: &pandoc::mock::RecordedCall, arg: &str| call.args.iter().any(|a| a == arg);
+    let has_prefix = |call: &pandoc::mock::RecordedCall, prefix: &str| {
+        call.args
+            .iter()
+            .any(|a| a.to_string_lossy().starts_with(prefix))
+    };
+    assert_eq!(
+        calls[0].stdin.as_deref(),
+        Some(&b"# Introduction {#intro}\n"[..])
+    );
+    assert!(has(&calls[1], intro.to_str().unwrap()));
+    assert!(has_prefix(&calls[1], "--lua-filter="));
+    assert!(has(&calls[2], usage.to_str().unwrap()));
+    assert!(has(&calls[2], "--shift-heading-level-by=1"));
+    assert!(has(&calls[2], "lang:en"));
+    // the chapters are parsed with the data directory of the template, but not its writer
+    // options
+    for call in &calls {
+        assert!(has(call, "json"));
+        assert!(has_prefix(call, "--data-dir="));
+        assert!(!has_prefix(call, "--pdf-engine="));
+    }
+
+    mock.push_output("%PDF");
+    pandoc.execute_with(&mock).unwrap();
+    let calls = mock.calls();
+    let book = &calls[3];
+    assert_eq!(book.output_file(), Some(output));
+    assert!(has(book, "--file-scope"));
+    assert!(has(book, "--top-level-division=chapter"));
+    assert!(has_prefix(book, "--pdf-engine=xelatex"));
+    let parts = book
+        .args
+        .iter()
+        .filter(|arg| arg.to_string_lossy().ends_with(".json"))
+        .count();
+    assert_eq!(parts, 3);
+}
+
+#[cfg(feature = "test-util")]
+#[test]
+fn tee_output() {
+    use pandoc::mock::MockExecutor;
+    use pandoc::{InputKind, OutputKind, PandocOutput};
+
+    let dir = pandoc::TempDir::new().unwrap();
+    let file = dir.path().join("copy.html");
+    let mock = MockExecutor::new();
+    mock.push_output("<p>both</p>");
+    let mut pandoc = pandoc::new();
+    pandoc.set_input(InputKind::Pipe("both".to_string()));
+    pandoc.set_output(OutputKind::Tee(file.clone()));
+    match pandoc.execute_with(&mock).unwrap() {
+        PandocOutput::ToBuffer(html) => assert_eq!(html, "<p>both</p>"),
+        _ => panic!("expected a buffer"),
+    }
+    assert_eq!(std::fs::read_to_string(&file).unwrap(), "<p>both</p>");
+    assert_eq!(mock.calls()[0].output_file(), None);
+}
+
+#[cfg(unix)]
+#[test]
+fn directory_output() {
+    use pandoc::{InputKind, OutputKind, PandocError, PandocOutput};
+
+    // the input selects what the fake pandoc leaves at the output path
+    let dir = pandoc::TempDir::new().unwrap();
+    let script = "while [ $# -gt 0 ]; do [ \"$1\" = -o ] && out=$2; shift; done\n\
+                  case $(cat) in\n\
+                  chunks) mkdir -p \"$out/sub\"; echo a >\"$out/index.html\"; \
+                  echo b >\"$out/sub/1.html\";;\n\
+                  empty) mkdir -p \"$out\";;\n\
+                  file) echo c >\"$out\";;\n\
+                  esac\n";
+    common::fake_program(dir.path(), "pandoc", script);
+    // files of earlier runs are not listed
+    for stale in &["chunks", "empty"] {
+        std::fs::create_dir(dir.path().join(stale)).unwrap();
+        std::fs::write(dir.path().join(stale).join("stale.html"), "stale").unwrap();
+    }
+
+    let convert = |mode: &str| {
+        let out = dir.path().join(mode);
+        let mut pandoc = pandoc::new();
+        pandoc.add_pandoc_path_hint(dir.path());
+        pandoc.set_input(InputKind::Pipe(mode.to_string()));
+        pandoc.set_output(OutputKind::Directory(out.clone()));
+        (out, pandoc.execute())
+    };
+    match convert("chunks") {
+        (out, Ok(PandocOutput::ToDirectory(dir, files))) => {
+            assert_eq!(dir, out);
+            assert_eq!(
+                files,
+                vec![out.join("index.html"), out.join("sub").join("1.html")]
+            );
+        }
+        _ => panic!("expected the chunks to be listed"),
+    }
+    match convert("empty") {
+        (out, Err(PandocError::MissingOutput(dir))) => assert_eq!(dir, out),
+        _ => panic!("expected the empty directory to be reported"),
+    }
+    match convert("file") {
+        (_, Err(PandocError::IoErr(_))) => {}
+        _ => panic!("expected the unreadable directory to be reported"),
+    }
+}
+
+#[cfg(all(unix, feature = "test-util"))]
+#[test]
+fn url_inputs() {
+    use pandoc::mock::MockExecutor;
+    use pandoc::{ErrorKind, OutputKind};
+
+    let dir = pandoc::TempDir::new().unwrap();
+    let local = dir.path().join("local.md");
+    std::fs::write(&local, "# local").unwrap();
+    let url = "https://example.com/remote.md";
+    let mock = MockExecutor::new();
+    let mut pandoc = pandoc::new();
+    pandoc.add_input_url(url);
+    pandoc.add_input(&local);
+    pandoc.set_output(OutputKind::Pipe);
+    pandoc.clone().execute_with(&mock).unwrap();
+    let args = &mock.calls()[0].args;
+    let position = |arg: &std::ffi::OsStr| args.iter().position(|a| a == arg).unwrap();
+    assert!(position(url.as_ref()) < position(local.as_os_str()));
+
+    common::fake_program(dir.path(), "pandoc", "echo 'pandoc 3.1.2'\n");
+    pandoc.add_pandoc_path_hint(dir.path());
+    pandoc.hardened_for_untrusted_input().unwrap();
+    let err = pandoc.clone().execute_with(&mock).err().unwrap();
+    assert_eq!(err.kind(), ErrorKind::Unsupported);
+    // the pass reading the input for the filters rejects it as well
+    pandoc.add_filter(|json| json);
+    let err = pandoc.execute_with(&mock).err().unwrap();
+    assert_eq!(err.kind(), ErrorKind::Unsupported);
+    assert_eq!(mock.calls().len(), 1);
+}
+
+#[cfg(feature = "test-util")]
+#[test]
+fn glob_inputs() {
+    use pandoc::mock::MockExecutor;
+    use pandoc::{ErrorKind, OutputKind};
+
+    let dir = pandoc::TempDir::new().unwrap();
+    for name in &["b.md", "a.md", "c.txt", "10.md"] {
+        std::fs::write(dir.path().join(name), name).unwrap();
+    }
+    let pattern = dir.path().join("*.md");
+    let mock = MockExecutor::new();
+    let mut pandoc = pandoc::new();
+    pandoc.add_input_glob(pattern.to_str().unwrap());
+    pandoc.set_output(OutputKind::Pipe);
+    pandoc.clone().execute_with(&mock).unwrap();
+    let args = &mock.calls()[0].args;
+    let inputs: Vec<_> = args
+        .iter()
+        .filter(|arg| arg.to_string_lossy().ends_with(".md"))
+        .collect();
+    let expected: Vec<_> = vec!["10.md", "a.md", "b.md"]
+        .into_iter()
+        .map(|name| dir.path().join(name).into_os_string())
+        .collect();
+    assert_eq!(inputs, expected.iter().collect::<Vec<_>>());
+
+    pandoc.add_input_glob("[");
+    let err = pandoc.execute_with(&mock).err().unwrap();
+    assert_eq!(err.kind(), ErrorKind::InvalidGlob);
+}
+
+#[cfg(all(unix, feature = "test-util"))]
+#[test]
+fn input_handle() {
+    use pandoc::mock::MockExecutor;
+    use pandoc::{ErrorKind, InputHandle, InputKind, OutputKind, PandocOutput};
+
+    let dir = pandoc::TempDir::new().unwrap();
+    let input = dir.path().join("input.md");
+    std::fs::write(&input, "streamed").unwrap();
+
+    // the handle is connected to pandoc's stdin instead of being read into memory
+    let mock = MockExecutor::new();
+    let mut pandoc = pandoc::new();
+    let handle = InputHandle::new(std::fs::File::open(&input).unwrap());
+    pandoc.set_input(InputKind::Handle(handle));
+    pandoc.set_output(OutputKind::Pipe);
+    pandoc.clone().execute_with(&mock).unwrap();
+    assert_eq!(mock.calls()[0].stdin, None);
+    assert!(!mock.calls()[0]
+        .args
+        .iter()
+        .any(|arg| arg == input.as_os_str()));
+    let err = pandoc.execute_with(&mock).err().unwrap();
+    assert_eq!(err.kind(), ErrorKind::NoInputSpecified);
+
+    common::fake_program(dir.path(), "pandoc", "cat\n");
+    let mut pandoc = pandoc::new();
+    pandoc.add_pandoc_path_hint(dir.path());
+    let handle = InputHandle::new(std::fs::File::open(&input).unwrap());
+    pandoc.set_input(InputKind::Handle(handle));
+    pandoc.set_output(OutputKind::Pipe);
+    match pandoc.execute().unwrap() {
+        PandocOutput::ToBuffer(text) => assert_eq!(text, "streamed"),
+        _ => panic!("expected a buffer"),
+    }
+}
+
+#[cfg(feature = "test-util")]
+#[test]
+fn infer_input_format() {
+    use pandoc::mock::MockExecutor;
+    use pandoc::{OutputKind, PandocError};
+
+    let dir = pandoc::TempDir::new().unwrap();
+    let file = |name: &str| {
+        let path = dir.path().join(name);
+        std::fs::write(&path, name).unwrap();
+        path
+    };
+    let (intro, body, appendix) = (file("intro.md"), file("body.markdown"), file("app.rst"));
+    let mock = MockExecutor::new();
+    let mut pandoc = pandoc::new();
+    pandoc.set_infer_input_format(true);
+    pandoc.add_input(&intro);
+    pandoc.add_input(&body);
+    pandoc.set_output(OutputKind::Pipe);
+    pandoc.clone().execute_with(&mock).unwrap();
+    assert!(mock.calls()[0]
+        .args
+        .windows(2)
+        .any(|pair| pair[0] == "-f" && pair[1] == "markdown"));
+
+    pandoc.add_input(&appendix);
+    match pandoc.execute_with(&mock) {
+        Err(PandocError::AmbiguousInputFormat(first, second)) => {
+            assert_eq!(first, intro);
+            assert_eq!(second, appendix);
+        }
+        _ => panic!("expected the mixed inputs to be rejected"),
+    }
+    assert_eq!(mock.calls().len(), 1);
+}
+
+#[cfg(feature = "test-util")]
+#[test]
+fn inherit_stdin() {
+    use pandoc::mock::MockExecutor;
+    use pandoc::{InputKind, OutputKind};
+
+    let mock = MockExecutor::new();
+    let mut pandoc = pandoc::new();
+    pandoc.set_input(InputKind::InheritStdin);
+    pandoc.set_output(OutputKind::Pipe);
+    pandoc.execute_with(&mock).unwrap();
+    let calls = mock.calls();
+    assert_eq!(calls.len(), 1);
+    // nothing is piped, pandoc reads the stdin of the current process itself
+    assert_eq!(calls[0].stdin, None);
+    assert_eq!(calls[0].output_file(), None);
+}
+
+#[cfg(feature = "test-util")]
+#[test]
+fn inherit_stdout() {
+    use pandoc::mock::MockExecutor;
+    use pandoc::{InputKind, OutputFormat, OutputKind, PandocOutput};
+
+    let mock = MockExecutor::new();
+    let mut pandoc = pandoc::new();
+    pandoc.set_input(InputKind::Pipe("# printed".to_string()));
+    pandoc.set_output(OutputKind::InheritStdout);
+    pandoc.set_output_format(OutputFormat::Html, Vec::new());
+    match pandoc.clone().execute_with(&mock).unwrap() {
+        PandocOutput::ToStdout => {}
+        _ => panic!("expected the output to go to stdout"),
+    }
+    pandoc.set_output_format(OutputFormat::Docx, Vec::new());
+    match pandoc.execute_with(&mock).unwrap() {
+        PandocOutput::ToStdout => {}
+        _ => panic!("expected the output to go to stdout"),
+    }
+
+    let calls = mock.calls();
+    assert!(!calls[0].args.iter().any(|arg| arg == "-o"));
+    // binary writers refuse to write to a terminal unless asked to explicitly
+    assert!(calls[1]
+        .args
+        .windows(2)
+        .any(|pair| pair[0] == "-o" && pair[1] == "-"));
+}
+
+#[cfg(all(feature = "serde", feature = "test-util"))]
+#[test]
+fn serde_round_trip() {
+    use pandoc::mock::MockExecutor;
+    use pandoc::{InputKind, OutputFormat, OutputKind, PandocOption};
+
+    let mut pandoc = pandoc::new();
+    pandoc.set_input(InputKind::Pipe("# serialized".to_string()));
+    pandoc.set_output(OutputKind::Pipe);
+    pandoc.set_output_format(OutputFormat::Html5, Vec::new());
+    pandoc.set_toc();
+    pandoc.set_number_sections();
+    pandoc.set_variable("lang", "de");
+    pandoc.add_option(PandocOption::ResourcePath(vec!["assets".into()]));
+    pandoc.arg("wrap", "none");
+    let json = serde_json::to_string(&pandoc).unwrap();
+    let restored: pandoc::Pandoc = serde_json::from_str(&json).unwrap();
+    assert_eq!(restored, pandoc);
+
+    let mock = MockExecutor::new();
+    pandoc.execute_with(&mock).unwrap();
+    restored.execute_with(&mock).unwrap();
+    let calls = mock.calls();
+    assert_eq!(calls[0], calls[1]);
+    assert!(calls[1].args.iter().any(|arg| arg == "--wrap=none"));
+}
+
+#[cfg(all(feature = "tracing", feature = "test-util"))]
+#[test]
+fn tracing_events() {
+    use pandoc::mock::MockExecutor;
+    use pandoc::{InputKind, OutputKind};
+    use std::sync::{Arc, Mutex};
+    use tracing::field::{Field, Visit};
+    use tracing::span::{Attributes, Id, Record};
+    use tracing::{Event, Metadata};
+
+    /// collects the messages of all events
+    struct Messages(Arc<Mutex<Vec<String>>>);
+
+    impl Visit for Messages {
+        fn record_debug(&mut self, field: &Field, value: &dyn std::fmt::Debug) {
+            if field.name() == "message" {
+                self.0.lock().unwrap().push(format!("{:?}", value));
+            }
+        }
+    }
+
+    impl tracing::Subscriber for Messages {
+        fn enabled(&self, _: &Metadata<'_>) -> bool {
+            true
+        }
+        fn new_span(&self, _: &Attributes<'_>) -> Id {
+            Id::from_u64(1)
+        }
+        fn record(&self, _: &Id, _: &Record<'_>) {}
+        fn record_follows_from(&self, _: &Id, _: &Id) {}
+        fn event(&self, event: &Event<'_>) {
+            event.record(&mut Messages(Arc::clone(&self.0)));
+        }
+        fn enter(&self, _: &Id) {}
+        fn exit(&self, _: &Id) {}
+    }
+
+    let messages = Arc::new(Mutex::new(Vec::new()));
+    let mock = MockExecutor::new();
+    let mut pandoc = pandoc::new();
+    pandoc.set_input(InputKind::Pipe(String::new()));
+    pandoc.set_output(OutputKind::Pipe);
+    pandoc.set_show_cmdline(true);
+    tracing::subscriber::with_default(Messages(Arc::clone(&messages)), || {
+        pandoc.execute_with(&mock).unwrap();
+    });
+    let messages = messages.lock().unwrap();
+    assert_eq!(messages.len(), 3);
+    assert!(messages[0].contains("pandoc"));
+    assert_eq!(messages[1], "running pandoc");
+    assert_eq!(messages[2], "pandoc finished");
+}
+
+#[cfg(all(feature = "mdbook", feature = "test-util"))]
+#[test]
+fn mdbook_render_context() {
+    use pandoc::mdbook::RenderContext;
+    use pandoc::mock::MockExecutor;
+
+    let dir = pandoc::TempDir::new().unwrap();
+    let json = r###"{
+        "root": "/books/guide",
+        "destination": "DEST",
+        "book": {"sections": [
+            {"Chapter": {"name": "Intro", "content": "# Intro", "path": "intro.md",
+                "sub_items": [{"Chapter": {"name": "Setup", "content": "## Setup",
+                    "path": "start/setup.md", "sub_items": []}}]}},
+            "Separator",
+            {"Chapter": {"name": "Draft", "content": "# Draft", "path": null}}
+        ]},
+        "config": {
+            "book": {"title": "Guide", "authors": ["Ada"], "src": "text"},
+            "output": {"pandoc": {"output-file": "guide.html", "toc": true}}
+        }
+    }"###
+        .replace("DEST", dir.path().to_str().unwrap());
+    let ctx = RenderContext::from_reader(json.as_bytes()).unwrap();
+    let mock = MockExecutor::new();
+    mock.push_output("<h1>Intro</h1>");
+    pandoc::mdbook::to_pandoc(&ctx)
+        .unwrap()
+        .execute_with(&mock)
+        .unwrap();
+
+    let call = &mock.calls()[0];
+    assert_eq!(call.stdin.as_deref(), Some(&b"# Intro\n\n## Setup"[..]));
+    assert_eq!(call.output_file(), Some(dir.path().join("guide.html")));
+    let args: Vec<_> = call.args.iter().map(|arg| arg.to_str().unwrap()).collect();
+    assert!(args.contains(&"--table-of-contents"));
+    assert!(args.windows(2).any(|pair| pair == ["-M", "title:Guide"]));
+    assert!(args.windows(2).any(|pair| pair == ["-M", "author:Ada"]));
+    let separator = if cfg!(windows) { ';' } else { ':' };
+    let resource_path = format!(
+        "--resource-path=/books/guide/text{}/books/guide/text/start",
+        separator
+    );
+    assert!(args.contains(&resource_path.as_str()));
+
+    assert!(RenderContext::from_reader(&b"{}"[..]).is_err());
+}
+
+#[cfg(unix)]
+#[test]
+fn extract_media_to_temp() {
+    use pandoc::{InputKind, OutputKind, PandocOutput};
+
+    let dir = pandoc::TempDir::new().unwrap();
+    let script = "cat >/dev/null\nfor arg; do case $arg in --extract-media=*) \
+                  media=${arg#--extract-media=}; mkdir -p \"$media/media\"; \
+                  echo png >\"$media/media/image1.png\"; echo \"$media\";; esac; done\n";
+    common::fake_program(dir.path(), "pandoc", script);
+
+    let mut pandoc = pandoc::new();
+    pandoc.add_pandoc_path_hint(dir.path());
+    pandoc.set_input(InputKind::Pipe("docx".to_string()));
+    pandoc.set_output(OutputKind::Pipe);
+    let (output, media) = pandoc.clone().extract_media_to_temp().unwrap();
+    match output {
+        PandocOutput::ToBuffer(printed) => {
+            assert_eq!(printed.trim_end(), media.dir().to_str().unwrap())
+        }
+        _ => panic!("expected a buffer"),
+    }
+    let media_dir = media.dir().to_owned();
+    assert_eq!(
+        media.files(),
+        &[media_dir.join("media").join("image1.png")][..]
+    );
+    drop(media);
+    assert!(!media_dir.exists());
+
+    let (_, media) = pandoc.extract_media_to_temp().unwrap();
+    let media_dir = media.dir().to_owned();
+    let files = media.keep();
+    assert!(files[0].exists());
+    std::fs::remove_dir_all(media_dir).unwrap();
+}
+
+#[cfg(feature = "server")]
+#[test]
+fn server_requests() {
+    use pandoc::server::ServerClient;
+    use pandoc::{ErrorKind, InputFormat, OutputKind, PandocOption, PandocOutput};
+    use std::io::{BufRead, BufReader, Read, Write};
+
+    // answers every request with the same output and sends the request bodies back
+    let listener = std::net::TcpListener::bind("127.0.0.1:0").unwrap();
+    let client = ServerClient::new(&format!("http://{}", listener.local_addr().unwrap()));
+    let (sender, bodies) = std::sync::mpsc::channel();
+    std::thread::spawn(move || {
+        for stream in listener.incoming() {
+            let mut stream = BufReader::new(stream.unwrap());
+            let mut length = 0;
+            loop {
+                let mut line = String::new();
+                stream.read_line(&mut line).unwrap();
+                let lower = line.to_ascii_lowercase();
+                if let Some(value) = lower.strip_prefix("content-length:") {
+                    length = value.trim().parse().unwrap();
+                }
+                if line.trim().is_empty() {
+                    break;
+                }
+            }
+            let mut body = vec![0; length];
+            stream.read_exact(&mut body).unwrap();
+            sender.send(String::from_utf8(body).unwrap()).unwrap();
+            let answer = r#"{"output":"<p>served</p>","base64":false,"messages":[]}"#;
+            write!(
+                stream.get_mut(),
+                "HTTP/1.1 200 OK\r\nContent-Type: application/json\r\n\
+                 Content-Length: {}\r\nConnection: close\r\n\r\n{}",
+                answer.len(),
+                answer
+            )
+            .unwrap();
+        }
+    });
+
+    let dir = pandoc::TempDir::new().unwrap();
+    let file = |name: &str, content: &[u8]| {
+        let path = dir.path().join(name);
+        std::fs::write(&path, content).unwrap();
+        path
+    };
+    let mut pandoc = pandoc::new();
+    pandoc.add_input(&file("one.md", b"# one"));
+    pandoc.add_input(&file("two.md", b"# two"));
+    pandoc.set_output(OutputKind::Pipe);
+    pandoc.set_toc();
+    pandoc.add_option(PandocOption::Meta(
+        "title".to_string(),
+        Some("Served".to_string()),
+    ));
+    match pandoc.execute_on_server(&client).unwrap() {
+        PandocOutput::ToBuffer(html) => assert_eq!(html, "<p>served</p>"),
+        _ => panic!("expected a buffer"),
+    }
+    let body: serde_json::Value = serde_json::from_str(&bodies.recv().unwrap()).unwrap();
+    assert_eq!(body["text"], "# one\n\n# two");
+    assert_eq!(body["table-of-contents"], true);
+    assert_eq!(body["metadata"]["title"], "Served");
+
+    let mut pandoc = pandoc::new();
+    pandoc.add_input(&file("one.docx", b"PK\x03\x04"));
+    pandoc.set_input_format(InputFormat::Docx, Vec::new());
+    pandoc.set_output(OutputKind::Pipe);
+    pandoc.clone().execute_on_server(&client).unwrap();
+    let body: serde_json::Value = serde_json::from_str(&bodies.recv().unwrap()).unwrap();
+    assert_eq!(body["text"], "UEsDBA==");
+    assert_eq!(body["from"], "docx");
+
+    pandoc.add_input(&file("two.docx", b"PK\x03\x04"));
+    let err = pandoc.execute_on_server(&client).err().unwrap();
+    assert_eq!(err.kind(), ErrorKind::InvalidConfig);
+}
+
+#[cfg(all(feature = "server", unix))]
+#[test]
+fn server_fallback() {
+    use pandoc::server::{PandocServer, ServerClient};
+    use pandoc::{ErrorKind, InputKind, OutputFormat, OutputKind};
+
+    let mut pandoc = pandoc::new();
+    pandoc.set_input(InputKind::Pipe("*hi*".to_string()));
+    pandoc.set_output(OutputKind::Pipe);
+    assert!(ServerClient::supports(&pandoc));
+    let spawned_only: Vec<fn(&mut pandoc::Pandoc)> = vec![
+        |pandoc| {
+            pandoc.arg("toc", "");
+        },
+        |pandoc| {
+            pandoc.set_timeout(std::time::Duration::from_secs(1));
+        },
+        |pandoc| {
+            pandoc.set_reproducible(true);
+        },
+        |pandoc| {
+            pandoc.set_cache_dir("cache");
+        },
+        |pandoc| {
+            pandoc.set_output_format(OutputFormat::Lua("writer.lua".to_string()), Vec::new());
+        },
+    ];
+    // nothing listens there, the settings are rejected before a request is made
+    let client = ServerClient::new("http://127.0.0.1:1");
+    for setting in spawned_only {
+        let mut pandoc = pandoc.clone();
+        setting(&mut pandoc);
+        assert!(!ServerClient::supports(&pandoc));
+        let err = pandoc.execute_on_server(&client).err().unwrap();
+        assert_eq!(err.kind(), ErrorKind::Unsupported);
+    }
+
+    // the server is started with the pandoc found with the path hints
+    let dir = pandoc::TempDir::new().unwrap();
+    let args = dir.path().join("args");
+    let script = format!("echo \"$@\" >{}\nexit 1\n", args.display());
+    common::fake_program(dir.path(), "pandoc", &script);
+    let mut pandoc = pandoc::new();
+    pandoc.add_pandoc_path_hint(dir.path());
+    let err = PandocServer::launch_with(&pandoc).err().unwrap();
+    assert_eq!(err.kind(), ErrorKind::ServerFailed);
+    let args = std::fs::read_to_string(args).unwrap();
+    assert!(args.starts_with("server --port "));
+    assert!(args.trim_end().ends_with("--timeout 2"));
+}
+
+#[cfg(all(feature = "ast", feature = "test-util"))]
+#[test]
+fn execute_to_ast() {
+    use pandoc::mock::MockExecutor;
+    use pandoc::pandoc_ast::Block;
+    use pandoc::{ErrorKind, InputKind, OutputFormat, OutputKind};
+
+    let mock = MockExecutor::new();
+    mock.push_output(
+        r#"{"pandoc-api-version":[1,23,1],"meta":{},"blocks":[
+            {"t":"Para","c":[{"t":"Str","c":"parsed"}]}]}"#,
+    );
+    mock.push_output("not json");
+    let mut pandoc = pandoc::new();
+    pandoc.set_input(InputKind::Pipe("parsed".to_string()));
+    pandoc.set_output(OutputKind::File("ignored.html".into()));
+    pandoc.set_output_format(OutputFormat::Html, Vec::new());
+    let ast = pandoc.clone().execute_to_ast_with(&mock).unwrap();
+    assert_eq!(ast.blocks.len(), 1);
+    assert!(matches!(ast.blocks[0], Block::Para(_)));
+    let err = pandoc.execute_to_ast_with(&mock).err().unwrap();
+    assert_eq!(err.kind(), ErrorKind::InvalidAst);
+
+    let call = &mock.calls()[0];
+    assert!(call
+        .args
+        .windows(2)
+        .any(|pair| pair[0] == "-t" && pair[1] == "json"));
+    assert_eq!(call.output_file(), None);
+}
+
+#[cfg(feature = "test-util")]
+#[test]
+fn ipynb() {
+    use pandoc::mock::MockExecutor;
+    use pandoc::{InputFormat, InputKind, IpynbOutput, OutputFormat, OutputKind, PandocOption};
+
+    assert_eq!("ipynb".parse::<InputFormat>().unwrap(), InputFormat::Ipynb);
+    assert_eq!(
+        "ipynb".parse::<OutputFormat>().unwrap(),
+        OutputFormat::Ipynb
+    );
+    assert_eq!(
+        InputFormat::from_extension("ipynb"),
+        Some(InputFormat::Ipynb)
+    );
+    assert_eq!(
+        OutputFormat::from_extension("IPYNB"),
+        Some(OutputFormat::Ipynb)
+    );
+
+    let mock = MockExecutor::new();
+    for output in &[IpynbOutput::All, IpynbOutput::None, IpynbOutput::Best] {
+        let mut pandoc = pandoc::new();
+        pandoc.set_input(InputKind::Pipe("{}".to_string()));
+        pandoc.set_input_format(InputFormat::Ipynb, Vec::new());
+        pandoc.set_output(OutputKind::Pipe);
+        pandoc.set_output_format(OutputFormat::Markdown, Vec::new());
+        pandoc.add_option(PandocOption::IpynbOutput(*output));
+        pandoc.execute_with(&mock).unwrap();
+    }
+    let calls = mock.calls();
+    assert!(calls[0]
+        .args
+        .windows(2)
+        .any(|pair| pair[0] == "-f" && pair[1] == "ipynb"));
+    let options: Vec<_> = calls
+        .iter()
+        .flat_map(|call| {
+            call.args
+                .iter()
+                .filter(|arg| arg.to_string_lossy().starts_with("--ipynb-output="))
+        })
+        .collect();
+    assert_eq!(
+        options,
+        vec![
+            "--ipynb-output=all",
+            "--ipynb-output=none",
+            "--ipynb-output=best"
+        ]
+    );
+}
+
+#[test]
+fn output_format_from_extension() {
+    use pandoc::OutputFormat;
+
+    assert_eq!(
+        OutputFormat::from_extension("md"),
+        Some(OutputFormat::Markdown)
+    );
+    assert_eq!(
+        OutputFormat::from_extension("txt"),
+        Some(OutputFormat::Markdown)
+    );
+    assert_eq!(
+        OutputFormat::from_extension("TEXT"),
+        Some(OutputFormat::Markdown)
+    );
+    assert_eq!(
+        OutputFormat::from_extension("htm"),
+        Some(OutputFormat::Html)
+    );
+    assert_eq!(OutputFormat::from_extension("1"), Some(OutputFormat::Man));
+    assert_eq!(OutputFormat::from_extension("unknown"), None);
+}
+
+#[cfg(feature = "test-util")]
+#[test]
+fn standalone_html_preset() {
+    use pandoc::mock::MockExecutor;
+    use pandoc::{InputKind, OutputKind};
+
+    let mock = MockExecutor::new();
+    let mut pandoc = pandoc::presets::standalone_html("Report");
+    pandoc.set_input(InputKind::Pipe("# Report".to_string()));
+    pandoc.set_output(OutputKind::Pipe);
+    pandoc.execute_with(&mock).unwrap();
+    let args: Vec<_> = mock.calls()[0]
+        .args
+        .iter()
+        .map(|arg| arg.to_str().unwrap().to_string())
+        .collect();
+    assert!(args.windows(2).any(|pair| pair == ["-t", "html5"]));
+    assert!(args.contains(&"--standalone".to_string()));
+    assert!(args.contains(&"--embed-resources".to_string()));
+    assert!(args
+        .windows(2)
+        .any(|pair| pair == ["-M", "pagetitle:Report"]));
+}
+
+#[cfg(feature = "test-util")]
+#[test]
+fn epub_builder() {
+    use pandoc::mock::MockExecutor;
+
+    let dir = pandoc::TempDir::new().unwrap();
+    let file = |name: &str| {
+        let path = dir.path().join(name);
+        std::fs::write(&path, name).unwrap();
+        path
+    };
+    let (intro, usage) = (file("intro.md"), file("usage.md"));
+    let (cover, css, font) = (file("cover.png"), file("book.css"), file("serif.ttf"));
+    let mut book = pandoc::presets::EpubBuilder::new("My Book");
+    book.add_author("Jane Doe")
+        .add_author("John Doe")
+        .set_language("en-GB")
+        .set_date("2024-01-31")
+        .add_chapter(&intro)
+        .add_chapter(&usage)
+        .set_cover_image(&cover)
+        .add_stylesheet(&css)
+        .add_font(&font)
+        .set_chapter_level(2)
+        .set_toc();
+    let output = dir.path().join("book.epub");
+    let mock = MockExecutor::new();
+    mock.push_output("PK");
+    book.build(&output).execute_with(&mock).unwrap();
+
+    let call = &mock.calls()[0];
+    assert_eq!(call.output_file(), Some(output));
+    let args: Vec<_> = call
+        .args
+        .iter()
+        .map(|arg| arg.to_str().unwrap().to_string())
+        .collect();
+    let has = |arg: String| args.contains(&arg);
+    let meta = |value: &str| args.windows(2).any(|pair| pair == ["-M", value]);
+    assert!(args.windows(2).any(|pair| pair == ["-t", "epub"]));
+    let position = |path: &std::path::Path| {
+        args.iter()
+            .position(|arg| arg == path.to_str().unwrap())
+            .unwrap()
+    };
+    assert!(position(&intro) < position(&usage));
+    assert!(meta("title:My Book"));
+    assert!(meta("author:Jane Doe") && meta("author:John Doe"));
+    assert!(meta("lang:en-GB") && meta("date:2024-01-31"));
+    assert!(has(format!("--epub-cover-image={}", cover.display())));
+    assert!(has(format!("--css={}", css.display())));
+    assert!(has(format!("--epub-embed-font={}", font.display())));
+    assert!(has("--epub-chapter-level=2".to_string()));
+    assert!(has("--table-of-contents".to_string()));
+}
+
+#[cfg(feature = "test-util")]
+#[test]
+fn slides_preset() {
+    use pandoc::mock::MockExecutor;
+    use pandoc::presets::{slides, SlideEngine};
+    use pandoc::InputKind;
+
+    let dir = pandoc::TempDir::new().unwrap();
+    let reference = dir.path().join("theme.pptx");
+    std::fs::write(&reference, "PK").unwrap();
+    let mock = MockExecutor::new();
+    let engines = vec![
+        (SlideEngine::Revealjs, "metropolis", "talk.html"),
+        (SlideEngine::Beamer, "metropolis", "talk.tex"),
+        (SlideEngine::Pptx, reference.to_str().unwrap(), "talk.pptx"),
+    ];
+    for (engine, theme, output) in engines {
+        let mut pandoc = slides(engine)
+            .set_slide_level(2)
+            .set_incremental()
+            .set_theme(theme)
+            .set_color_theme("owl")
+            .build(&dir.path().join(output));
+        pandoc.set_input(InputKind::Pipe("# talk".to_string()));
+        mock.push_output("slides");
+        pandoc.execute_with(&mock).unwrap();
+    }
+
+    let calls: Vec<Vec<String>> = mock
+        .calls()
+        .iter()
+        .map(|call| {
+            call.args
+                .iter()
+                .map(|arg| arg.to_str().unwrap().to_string())
+                .collect()
+        })
+        .collect();
+    let var = |args: &[String], value: &str| args.windows(2).any(|pair| pair == ["-V", value]);
+    for (args, format) in calls.iter().zip(&["revealjs", "beamer", "pptx"]) {
+        assert!(args.windows(2).any(|pair| pair == ["-t", *format]));
+        assert!(args.contains(&"--standalone".to_string()));
+        assert!(args.contains(&"--slide-level=2".to_string()));
+        assert!(args.contains(&"--incremental".to_string()));
+    }
+    assert!(var(&calls[0], "theme:metropolis"));
+    assert!(!var(&calls[0], "colortheme:owl"));
+    assert!(var(&calls[1], "theme:metropolis"));
+    assert!(var(&calls[1], "colortheme:owl"));
+    assert!(!calls[2].iter().any(|arg| arg.starts_with("theme:")));
+    assert!(calls[2].contains(&format!("--reference-doc={}", reference.display())));
+}
+
+#[cfg(feature = "test-util")]
+#[test]
+fn citeproc() {
+    use pandoc::mock::MockExecutor;
+    use pandoc::{CitationConfig, InputKind, OutputKind, PandocError};
+
+    let dir = pandoc::TempDir::new().unwrap();
+    let bib = dir.path().join("refs.bib");
+    let csl = dir.path().join("apa.csl");
+    std::fs::write(&bib, "@book{doe99,}").unwrap();
+    std::fs::write(&csl, "<style/>").unwrap();
+
+    let mut pandoc = pandoc::new();
+    let missing = dir.path().join("missing.bib");
+    let config = CitationConfig {
+        bibliographies: vec![bib.clone(), missing.clone()],
+        ..CitationConfig::default()
+    };
+    match pandoc.set_citeproc(config) {
+        Err(PandocError::MissingInput(path)) => assert_eq!(path, missing),
+        _ => panic!("expected the missing bibliography to be reported"),
+    }
+
+    pandoc
+        .set_citeproc(CitationConfig {
+            bibliographies: vec![bib.clone()],
+            csl: Some(csl.clone()),
+            link_citations: true,
+            nocite: vec!["doe99".to_string()],
+        })
+        .unwrap();
+    pandoc.set_input(InputKind::Pipe("[@doe99]".to_string()));
+    pandoc.set_output(OutputKind::Pipe);
+    let mock = MockExecutor::new();
+    // the generated metadata file is removed with the last clone of the builder
+    pandoc.clone().execute_with(&mock).unwrap();
+    let args: Vec<_> = mock.calls()[0]
+        .args
+        .iter()
+        .map(|arg| arg.to_str().unwrap().to_string())
+        .collect();
+    assert!(args.contains(&"--citeproc".to_string()));
+    assert!(args.contains(&format!("--bibliography={}", bib.display())));
+    assert!(args.contains(&format!("--csl={}", csl.display())));
+    assert!(args
+        .windows(2)
+        .any(|pair| pair == ["-M", "link-citations:true"]));
+    let metadata = args
+        .iter()
+        .find_map(|arg| arg.strip_prefix("--metadata-file="))
+        .unwrap();
+    assert!(std::fs::read_to_string(metadata)
+        .unwrap()
+        .contains("@doe99"));
+}
+
+#[cfg(feature = "test-util")]
+#[test]
+fn toc_config() {
+    use pandoc::mock::MockExecutor;
+    use pandoc::{InputKind, OutputKind, TocConfig};
+
+    let mut pandoc = pandoc::new();
+    pandoc.set_input(InputKind::Pipe("# toc".to_string()));
+    pandoc.set_output(OutputKind::Pipe);
+    pandoc.set_toc();
+    pandoc.set_toc_config(TocConfig {
+        depth: Some(4),
+        title: Some("Old".to_string()),
+        numbered: false,
+    });
+    pandoc.set_toc_config(TocConfig {
+        depth: Some(2),
+        title: Some("Contents".to_string()),
+        numbered: true,
+    });
+    let mock = MockExecutor::new();
+    pandoc.execute_with(&mock).unwrap();
+    let args: Vec<_> = mock.calls()[0]
+        .args
+        .iter()
+        .map(|arg| arg.to_str().unwrap().to_string())
+        .collect();
+    let count = |value: &str| args.iter().filter(|arg| *arg == value).count();
+    assert_eq!(count("--table-of-contents"), 1);
+    assert_eq!(count("--toc-depth=2"), 1);
+    assert_eq!(count("--toc-depth=4"), 0);
+    assert_eq!(count("toc-title:Contents"), 1);
+    assert_eq!(count("toc-title:Old"), 0);
+    assert_eq!(count("--number-sections"), 1);
+}
+
+#[cfg(feature = "test-util")]
+#[test]
+fn man_page_preset() {
+    use pandoc::mock::MockExecutor;
+    use pandoc::{InputKind, OutputKind, PandocOption};
+
+    let mock = MockExecutor::new();
+    let mut pandoc = pandoc::presets::man_page(1, "MYTOOL", "2024-01-31");
+    pandoc.add_option(PandocOption::Meta(
+        "footer".to_string(),
+        Some("mytool 1.0".to_string()),
+    ));
+    pandoc.set_input(InputKind::Pipe("# NAME".to_string()));
+    pandoc.set_output(OutputKind::Pipe);
+    pandoc.execute_with(&mock).unwrap();
+    let mut unnamed = pandoc::presets::man_page(42, "MYTOOL", "2024-01-31");
+    unnamed.set_input(InputKind::Pipe(String::new()));
+    unnamed.set_output(OutputKind::Pipe);
+    unnamed.execute_with(&mock).unwrap();
+
+    let calls: Vec<Vec<String>> = mock
+        .calls()
+        .iter()
+        .map(|call| {
+            call.args
+                .iter()
+                .map(|arg| arg.to_str().unwrap().to_string())
+                .collect()
+        })
+        .collect();
+    let args = &calls[0];
+    let meta = |args: &[String], value: &str| args.windows(2).any(|pair| pair == ["-M", value]);
+    assert!(args.windows(2).any(|pair| pair == ["-t", "man"]));
+    assert!(args.contains(&"--standalone".to_string()));
+    assert!(meta(args, "title:MYTOOL"));
+    assert!(meta(args, "date:2024-01-31"));
+    assert!(meta(args, "section:1"));
+    assert!(meta(args, "header:General Commands Manual"));
+    assert!(meta(args, "footer:mytool 1.0"));
+    // sections without a traditional name get no header
+    assert!(meta(&calls[1], "section:42"));
+    assert!(!calls[1].iter().any(|arg| arg.starts_with("header:")));
+}